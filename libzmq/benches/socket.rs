@@ -55,6 +55,35 @@ pub(crate) fn bench(c: &mut Criterion) {
                 }
             });
         })
+        .with_function("server-client (pooled)", move |b| {
+            let producer = ServerBuilder::new()
+                .bind(&*ADDR)
+                .send_hwm(HWM)
+                .build()
+                .unwrap();
+
+            let bound = producer.last_endpoint().unwrap();
+            let consumer = ClientBuilder::new()
+                .connect(bound)
+                .recv_hwm(HWM)
+                .build()
+                .unwrap();
+
+            consumer.send("").unwrap();
+            let msg = producer.recv_msg().unwrap();
+            let id = msg.routing_id().unwrap();
+            let pool = BufPool::new(MSG_SIZE);
+
+            b.iter(|| {
+                let dataset = gen_dataset(MSG_AMOUNT, MSG_SIZE);
+                for data in dataset {
+                    let data: Msg = data.into();
+
+                    producer.route(data, id).unwrap();
+                    let _ = consumer.try_recv_pooled(&pool);
+                }
+            });
+        })
         .with_function("radio-dish", move |b| {
             let producer = RadioBuilder::new()
                 .bind(&*ADDR)
@@ -1,8 +1,23 @@
 use libzmq::auth::CurveCert;
 
+use std::env;
+
 // Used to generate `CURVE` certificates.
+//
+// If a path is given, the certificate is saved in the `czmq` format
+// instead, with the secret certificate suffixed with `_secret`:
+// `$ cargo run --example gen_curve_cert -- client.key`
 fn main() {
     let cert = CurveCert::new_unique();
-    println!("public: \"{}\"", cert.public().as_str());
-    println!("secret: \"{}\"", cert.secret().as_str());
+
+    match env::args().nth(1) {
+        Some(path) => {
+            cert.save(&path).unwrap();
+            println!("saved certificate to \"{}\"", path);
+        }
+        None => {
+            println!("public: \"{}\"", cert.public().as_str());
+            println!("secret: \"{}\"", cert.secret().as_str());
+        }
+    }
 }
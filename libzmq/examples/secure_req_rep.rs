@@ -49,6 +49,7 @@ fn main() -> Result<(), failure::Error> {
         loop {
             let request = server.recv_msg()?;
             assert_eq!(request.to_str(), Ok("ping"));
+            assert_eq!(request.metadata("Service"), Some("ping"));
 
             // Retrieve the routing_id to route the reply to the client.
             let id = request.routing_id().unwrap();
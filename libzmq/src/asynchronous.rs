@@ -0,0 +1,534 @@
+//! Asynchronous socket operations.
+
+use crate::{
+    core::{GetRawSocket, RawSocket, RecvMsg, SendMsg},
+    error::*,
+    msg::Msg,
+    poll::{Events, PollId, Pollable, Poller, Trigger, READABLE, WRITABLE},
+};
+
+use async_io::Async;
+use futures::{ready, sink::Sink, stream::Stream};
+
+use std::{
+    io,
+    os::unix::io::{AsRawFd, RawFd},
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll},
+};
+
+/// The file descriptor of a `Poller`.
+///
+/// It is owned by the poller, so it must not be closed on drop.
+#[derive(Debug)]
+struct PollerFd(RawFd);
+
+impl AsRawFd for PollerFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+#[derive(Debug)]
+struct PollerState {
+    poller: Poller,
+    events: Events,
+}
+
+// The poller is only ever accessed behind the mutex.
+unsafe impl Send for PollerState {}
+
+fn reactor_error(err: io::Error) -> Error {
+    match err.raw_os_error() {
+        Some(libc::EMFILE) | Some(libc::ENFILE) => {
+            Error::new(ErrorKind::SocketLimit)
+        }
+        _ if err.kind() == io::ErrorKind::Interrupted => {
+            Error::new(ErrorKind::Interrupted)
+        }
+        _ => Error::new(ErrorKind::InvalidInput("unable to poll fd")),
+    }
+}
+
+/// Waits for a `Trigger` condition on a socket.
+///
+/// Thread-safe sockets do not expose a `ZMQ_FD`. Instead, each poller that
+/// monitors one of them registers a signaler in its mailbox. The signaler's
+/// file descriptor remains readable until the poller is polled again, so it
+/// can be registered in the reactor.
+#[derive(Debug)]
+struct Waiter {
+    // Must be dropped before the poller, which closes the fd.
+    fd: Async<PollerFd>,
+    state: Mutex<PollerState>,
+}
+
+impl Waiter {
+    fn new(raw_socket: &RawSocket, trigger: Trigger) -> Result<Self, Error> {
+        let mut poller = Poller::new();
+        poller.add(Pollable::Socket(raw_socket), PollId(0), trigger)?;
+
+        let fd = Async::new(PollerFd(poller.fd()?)).map_err(reactor_error)?;
+        let state = Mutex::new(PollerState {
+            poller,
+            events: Events::new(),
+        });
+
+        Ok(Self { fd, state })
+    }
+
+    /// Returns `WouldBlock` if the condition is not met.
+    fn try_ready(&self) -> Result<(), Error> {
+        let mut guard = self.state.lock().unwrap();
+        let PollerState { poller, events } = &mut *guard;
+
+        // This also resets the signaler.
+        poller.try_poll(events)
+    }
+
+    /// Waits until the condition is met.
+    ///
+    /// Any number of tasks can wait concurrently.
+    async fn ready(&self) -> Result<(), Error> {
+        loop {
+            match self.try_ready() {
+                Err(err) if err.kind() == ErrorKind::WouldBlock => (),
+                result => return result,
+            }
+
+            self.fd.readable().await.map_err(reactor_error)?;
+        }
+    }
+
+    /// Polls until the condition is met.
+    ///
+    /// Only the waker of the last task that polled is registered, so it
+    /// must not be called by concurrent tasks.
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        loop {
+            match self.try_ready() {
+                Err(err) if err.kind() == ErrorKind::WouldBlock => (),
+                result => return Poll::Ready(result),
+            }
+
+            ready!(self.fd.poll_readable(cx)).map_err(reactor_error)?;
+        }
+    }
+}
+
+/// An asynchronous wrapper around a socket.
+///
+/// It provides `async` versions of the [`SendMsg`] and [`RecvMsg`] methods,
+/// as well as [`Stream`] and [`Sink`] implementations. The socket is
+/// monitored by the [`async-io`] reactor, which makes the `AsyncSocket`
+/// usable from any executor, including `tokio` and `async-std`.
+///
+/// The blocking methods of the inner socket should not be used while it is
+/// wrapped, since they would block the executor.
+///
+/// The `async` methods can be awaited by any number of tasks concurrently.
+/// The [`Stream`] and [`Sink`] implementations require exclusive access,
+/// so they can only be used by a single task at a time.
+///
+/// # Feature Flags
+/// This type requires the feature flag "async" to be enabled.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::{prelude::*, *};
+/// use futures::{executor::block_on, StreamExt};
+///
+/// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+///
+/// let server = ServerBuilder::new().bind(addr).build()?;
+/// let bound = server.last_endpoint()?;
+/// let client = ClientBuilder::new().connect(bound).build()?;
+///
+/// let mut server = AsyncSocket::new(server)?;
+/// let client = AsyncSocket::new(client)?;
+///
+/// block_on(async {
+///     client.send("ping").await?;
+///
+///     let msg = server.next().await.unwrap()?;
+///     assert_eq!(msg.to_str()?, "ping");
+///
+///     let id = msg.routing_id().unwrap();
+///     let mut reply: Msg = "pong".into();
+///     reply.set_routing_id(id);
+///     server.send(reply).await?;
+///
+///     let msg = client.recv_msg().await?;
+///     assert_eq!(msg.to_str()?, "pong");
+///     Ok(())
+/// })
+/// # }
+/// ```
+///
+/// [`SendMsg`]: prelude/trait.SendMsg.html
+/// [`RecvMsg`]: prelude/trait.RecvMsg.html
+/// [`Stream`]: https://docs.rs/futures/0.3/futures/stream/trait.Stream.html
+/// [`Sink`]: https://docs.rs/futures/0.3/futures/sink/trait.Sink.html
+/// [`async-io`]: https://docs.rs/async-io
+#[derive(Debug)]
+pub struct AsyncSocket<S> {
+    // The waiters reference the socket, so they must be dropped first.
+    readable: Waiter,
+    writable: Waiter,
+    socket: S,
+    // The message pending in the `Sink`.
+    buffer: Option<Msg>,
+}
+
+impl<S> AsyncSocket<S>
+where
+    S: GetRawSocket,
+{
+    /// Wraps the socket so that it can be used asynchronously.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (socket is not thread-safe)
+    /// * [`SocketLimit`] (file descriptor limit was reached)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`SocketLimit`]: ../enum.ErrorKind.html#variant.SocketLimit
+    pub fn new(socket: S) -> Result<Self, Error> {
+        let readable = Waiter::new(socket.raw_socket(), READABLE)?;
+        let writable = Waiter::new(socket.raw_socket(), WRITABLE)?;
+
+        Ok(Self {
+            readable,
+            writable,
+            socket,
+            buffer: None,
+        })
+    }
+
+    /// Returns a reference to the inner socket.
+    pub fn get_ref(&self) -> &S {
+        &self.socket
+    }
+
+    /// Unwraps the inner socket.
+    pub fn into_inner(self) -> S {
+        let Self { socket, .. } = self;
+        socket
+    }
+}
+
+impl<S> AsyncSocket<S>
+where
+    S: SendMsg,
+{
+    /// Push a message into the outgoing socket queue, waiting until
+    /// it can be done without blocking.
+    ///
+    /// This is the `async` version of [`send`].
+    ///
+    /// # Returned Errors
+    /// The `Msg` is returned as the content of the `Error`.
+    ///
+    /// * [`InvalidCtx`]
+    /// * [`Interrupted`]
+    /// * [`HostUnreachable`] (only for [`Server`] socket)
+    ///
+    /// [`send`]: prelude/trait.SendMsg.html#method.send
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`HostUnreachable`]: ../enum.ErrorKind.html#variant.HostUnreachable
+    /// [`Server`]: struct.Server.html
+    pub async fn send<M>(&self, msg: M) -> Result<(), Error<Msg>>
+    where
+        M: Into<Msg>,
+    {
+        let mut msg = msg.into();
+        loop {
+            match self.socket.try_send(msg) {
+                Ok(()) => return Ok(()),
+                Err(mut err) => {
+                    if let ErrorKind::WouldBlock = err.kind() {
+                        msg = err.take().unwrap();
+                    } else {
+                        return Err(err);
+                    }
+                }
+            }
+
+            if let Err(err) = self.writable.ready().await {
+                return Err(Error::with_content(err.kind(), msg));
+            }
+        }
+    }
+
+    /// Attempts to send the message, which is only taken on completion.
+    fn poll_send(
+        &self,
+        cx: &mut Context<'_>,
+        msg: &mut Option<Msg>,
+    ) -> Poll<Result<(), Error<Msg>>> {
+        loop {
+            match self.socket.try_send(msg.take().unwrap()) {
+                Ok(()) => return Poll::Ready(Ok(())),
+                Err(mut err) => {
+                    if let ErrorKind::WouldBlock = err.kind() {
+                        *msg = err.take();
+                    } else {
+                        return Poll::Ready(Err(err));
+                    }
+                }
+            }
+
+            if let Err(err) = ready!(self.writable.poll_ready(cx)) {
+                return Poll::Ready(Err(Error::with_content(
+                    err.kind(),
+                    msg.take().unwrap(),
+                )));
+            }
+        }
+    }
+}
+
+impl<S> AsyncSocket<S>
+where
+    S: RecvMsg,
+{
+    /// Retreive a message from the inbound socket queue, waiting until
+    /// one is available.
+    ///
+    /// This is the `async` version of [`recv`].
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`]
+    /// * [`Interrupted`]
+    ///
+    /// [`recv`]: prelude/trait.RecvMsg.html#method.recv
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    pub async fn recv(&self, msg: &mut Msg) -> Result<(), Error> {
+        loop {
+            match self.socket.try_recv(msg) {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    if let ErrorKind::WouldBlock = err.kind() {
+                    } else {
+                        return Err(err);
+                    }
+                }
+            }
+
+            self.readable.ready().await?;
+        }
+    }
+
+    /// A convenience function that allocates a [`Msg`] with the same
+    /// properties as [`recv`].
+    ///
+    /// [`recv`]: #method.recv
+    /// [`Msg`]: ../msg/struct.Msg.html
+    pub async fn recv_msg(&self) -> Result<Msg, Error> {
+        let mut msg = Msg::new();
+        self.recv(&mut msg).await?;
+
+        Ok(msg)
+    }
+
+    fn poll_recv(
+        &self,
+        cx: &mut Context<'_>,
+        msg: &mut Msg,
+    ) -> Poll<Result<(), Error>> {
+        loop {
+            match self.socket.try_recv(msg) {
+                Ok(()) => return Poll::Ready(Ok(())),
+                Err(err) => {
+                    if let ErrorKind::WouldBlock = err.kind() {
+                    } else {
+                        return Poll::Ready(Err(err));
+                    }
+                }
+            }
+
+            ready!(self.readable.poll_ready(cx))?;
+        }
+    }
+}
+
+impl<S> Stream for AsyncSocket<S>
+where
+    S: RecvMsg + Unpin,
+{
+    type Item = Result<Msg, Error>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut msg = Msg::new();
+        let result = ready!(self.poll_recv(cx, &mut msg));
+
+        Poll::Ready(Some(result.map(|()| msg)))
+    }
+}
+
+impl<S, M> Sink<M> for AsyncSocket<S>
+where
+    S: SendMsg + Unpin,
+    M: Into<Msg>,
+{
+    type Error = Error<Msg>;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        <Self as Sink<M>>::poll_flush(self, cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: M) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        debug_assert!(this.buffer.is_none());
+        this.buffer = Some(item.into());
+
+        Ok(())
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        if this.buffer.is_none() {
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut buffer = this.buffer.take();
+        let result = this.poll_send(cx, &mut buffer);
+        this.buffer = buffer;
+
+        result
+    }
+
+    fn poll_close(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        <Self as Sink<M>>::poll_flush(self, cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{prelude::*, *};
+    use futures::{executor::block_on, SinkExt, StreamExt};
+    use std::{sync::Arc, thread, time::Duration};
+
+    #[test]
+    fn test_async_scatter_gather() {
+        let addr = InprocAddr::new_unique();
+
+        let gather = GatherBuilder::new().bind(&addr).build().unwrap();
+        let scatter = ScatterBuilder::new().connect(&addr).build().unwrap();
+
+        let mut gather = AsyncSocket::new(gather).unwrap();
+        let scatter = AsyncSocket::new(scatter).unwrap();
+
+        let handle = thread::spawn(move || {
+            block_on(async {
+                for i in 0..100 {
+                    scatter.send(i.to_string()).await.unwrap();
+                }
+            })
+        });
+
+        block_on(async {
+            for i in 0..100 {
+                let msg = gather.next().await.unwrap().unwrap();
+                assert_eq!(msg.to_str().unwrap(), i.to_string());
+            }
+        });
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_async_send_mute() {
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new().bind(addr).build().unwrap();
+        let bound = server.last_endpoint().unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .send_hwm(1)
+            .build()
+            .unwrap();
+        let client = AsyncSocket::new(client).unwrap();
+        let server = AsyncSocket::new(server).unwrap();
+
+        // The client blocks once its queue is full, and is woken up as
+        // soon as the server drains it.
+        let handle = thread::spawn(move || {
+            block_on(async {
+                for _ in 0..10 {
+                    client.send("").await.unwrap();
+                }
+            })
+        });
+
+        block_on(async {
+            for _ in 0..10 {
+                server.recv_msg().await.unwrap();
+            }
+        });
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_async_concurrent_recv() {
+        let addr = InprocAddr::new_unique();
+
+        let gather = GatherBuilder::new().bind(&addr).build().unwrap();
+        let scatter = ScatterBuilder::new().connect(&addr).build().unwrap();
+
+        let gather = Arc::new(AsyncSocket::new(gather).unwrap());
+
+        // Both tasks must be woken up, no matter which one registered last.
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let gather = Arc::clone(&gather);
+                thread::spawn(move || block_on(gather.recv_msg()).unwrap())
+            })
+            .collect();
+
+        thread::sleep(Duration::from_millis(50));
+        scatter.send("").unwrap();
+        scatter.send("").unwrap();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_async_sink() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let mut client = AsyncSocket::new(client).unwrap();
+        let server = AsyncSocket::new(server).unwrap();
+
+        block_on(async {
+            let mut stream = futures::stream::iter(vec![Ok("a"), Ok("b")]);
+            client.send_all(&mut stream).await.unwrap();
+
+            assert_eq!(server.recv_msg().await.unwrap().to_str().unwrap(), "a");
+            assert_eq!(server.recv_msg().await.unwrap().to_str().unwrap(), "b");
+        });
+    }
+}
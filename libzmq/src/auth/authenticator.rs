@@ -0,0 +1,351 @@
+use super::{event::unmap_addr, server::WORKER_ENDPOINT, *};
+use crate::{prelude::*, socket::*, *};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv6Addr},
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc},
+    thread,
+};
+
+/// The credentials provided by a peer during a `ZAP` handshake.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Credentials {
+    /// The peer uses the `NULL` mechanism and provided no credentials.
+    Null,
+    /// The username and password of a peer using the `PLAIN` mechanism.
+    Plain(PlainClientCreds),
+    /// The public key of a peer using the `CURVE` mechanism.
+    Curve(CurvePublicKey),
+}
+
+/// A typed `ZAP` authentication request.
+///
+/// See [`ZAP`].
+///
+/// [`ZAP`]: https://rfc.zeromq.org/spec:27/ZAP/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZapRequest {
+    pub(crate) domain: String,
+    // Peers that do not use an IP based transport (e.g. `ipc`)
+    // have no address.
+    pub(crate) addr: Option<Ipv6Addr>,
+    pub(crate) identity: Vec<u8>,
+    pub(crate) credentials: Credentials,
+}
+
+impl ZapRequest {
+    /// The `ZAP` domain of the socket that accepted the connection.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// The IP address of the peer, if it connected using an IP based
+    /// transport.
+    ///
+    /// IPv4-mapped IPv6 addresses are converted back to IPv4.
+    pub fn addr(&self) -> Option<IpAddr> {
+        self.addr.map(unmap_addr)
+    }
+
+    /// The routing id of the peer, which is empty for thread safe sockets.
+    pub fn identity(&self) -> &[u8] {
+        &self.identity
+    }
+
+    /// The credentials that the peer provided.
+    pub fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+}
+
+/// The outcome of the authentication of a [`ZapRequest`].
+///
+/// [`ZapRequest`]: struct.ZapRequest.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verdict {
+    status_code: StatusCode,
+    status_text: String,
+    user_id: String,
+    metadata: BTreeMap<String, String>,
+}
+
+impl Verdict {
+    /// Create a `Verdict` with the given status code and text.
+    pub fn new<S>(status_code: StatusCode, status_text: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            status_code,
+            status_text: status_text.into(),
+            user_id: String::new(),
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// A `Verdict` that allows the peer to connect.
+    pub fn allow() -> Self {
+        Self::new(StatusCode::Allowed, "OK")
+    }
+
+    /// A `Verdict` that denies the connection.
+    pub fn deny() -> Self {
+        Self::new(StatusCode::Denied, "NOT OK")
+    }
+
+    /// Set the user id associated with the peer.
+    ///
+    /// It can be read from the messages received from the peer via
+    /// [`Msg::user_id`].
+    ///
+    /// [`Msg::user_id`]: ../struct.Msg.html#method.user_id
+    pub fn with_user_id<U>(mut self, user_id: U) -> Self
+    where
+        U: Into<String>,
+    {
+        self.user_id = user_id.into();
+        self
+    }
+
+    /// Add a metadata property associated with the peer.
+    ///
+    /// It can be read from the messages received from the peer via
+    /// [`Msg::property`]. Names longer than 255 bytes are ignored.
+    ///
+    /// [`Msg::property`]: ../struct.Msg.html#method.property
+    pub fn add_metadata<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.metadata.insert(name.into(), value.into());
+        self
+    }
+
+    // Replaces all the metadata properties at once.
+    pub(crate) fn with_metadata(
+        mut self,
+        metadata: BTreeMap<String, String>,
+    ) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Returns the status code of the `Verdict`.
+    pub fn status_code(&self) -> StatusCode {
+        self.status_code
+    }
+
+    /// Returns the status text of the `Verdict`.
+    pub fn status_text(&self) -> &str {
+        &self.status_text
+    }
+
+    /// Returns the user id of the `Verdict`.
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Returns the metadata properties of the `Verdict`.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+}
+
+/// A custom authentication handler.
+///
+/// Once installed on a `Ctx` via [`AuthClient::set_authenticator`] or
+/// [`AuthClient::run_authenticator`], it takes the place of the
+/// `PLAIN` and `CURVE` registries of the `AuthServer`. Peers are still
+/// filtered by the whitelist and blacklist beforehand.
+///
+/// Any function or closure of the form `Fn(&ZapRequest) -> Verdict`
+/// implements `Authenticator`.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::{prelude::*, auth::*, *};
+/// use std::time::Duration;
+///
+/// // Use a new context to avoid impacting other sockets.
+/// let ctx = Ctx::new();
+/// let handle = ctx.handle();
+///
+/// let auth = AuthClient::with_ctx(handle)?;
+/// auth.set_authenticator(
+///     |request: &ZapRequest| match request.credentials() {
+///         Credentials::Plain(creds) if creds.password() == "hunter2" => {
+///             Verdict::allow().with_user_id(creds.username())
+///         }
+///         _ => Verdict::deny(),
+///     },
+///     1,
+/// )?;
+///
+/// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+///
+/// let server = ServerBuilder::new()
+///     .bind(addr)
+///     .mechanism(Mechanism::PlainServer)
+///     .recv_timeout(Duration::from_millis(200))
+///     .with_ctx(handle)?;
+///
+/// let bound = server.last_endpoint()?;
+///
+/// let client = ClientBuilder::new()
+///     .connect(bound)
+///     .mechanism(PlainClientCreds::new("alice", "hunter2"))
+///     .with_ctx(handle)?;
+///
+/// client.send("")?;
+/// let msg = server.recv_msg()?;
+/// assert_eq!(msg.user_id(), Some("alice"));
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`AuthClient::set_authenticator`]: struct.AuthClient.html#method.set_authenticator
+/// [`AuthClient::run_authenticator`]: struct.AuthClient.html#method.run_authenticator
+pub trait Authenticator: Send + Sync + 'static {
+    /// Authenticate the peer that issued the request.
+    fn authenticate(&self, request: &ZapRequest) -> Verdict;
+}
+
+impl<F> Authenticator for F
+where
+    F: Fn(&ZapRequest) -> Verdict + Send + Sync + 'static,
+{
+    fn authenticate(&self, request: &ZapRequest) -> Verdict {
+        self(request)
+    }
+}
+
+// Sent by an authenticator worker to the `AuthServer`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) enum WorkerRequest {
+    Register,
+    Verdict(u64, Verdict),
+}
+
+// Sent by the `AuthServer` to an authenticator worker.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) enum WorkerReply {
+    Registered,
+    Authenticate(u64, ZapRequest),
+    Stop,
+}
+
+// A worker that forwards the `ZAP` requests dispatched by the `AuthServer`
+// to an `Authenticator`.
+pub(crate) struct AuthWorker {
+    client: Client,
+}
+
+impl AuthWorker {
+    pub(crate) fn with_ctx(handle: CtxHandle) -> Result<Self, Error> {
+        let client = ClientBuilder::new()
+            .connect(&*WORKER_ENDPOINT)
+            .with_ctx(handle)
+            .map_err(Error::cast)?;
+
+        Ok(Self { client })
+    }
+
+    fn send(&self, request: &WorkerRequest) -> Result<(), Error> {
+        let ser = bincode::serialize(request).unwrap();
+        self.client.send(ser).map_err(Error::cast)
+    }
+
+    fn recv(&self) -> Result<WorkerReply, Error> {
+        let msg = self.client.recv_msg()?;
+        Ok(bincode::deserialize(msg.as_bytes()).unwrap())
+    }
+
+    // Blocks until the `AuthServer` acknowledges the worker.
+    pub(crate) fn register(&self) -> Result<(), Error> {
+        self.send(&WorkerRequest::Register)?;
+        let reply = self.recv()?;
+        assert_eq!(reply, WorkerReply::Registered);
+
+        Ok(())
+    }
+
+    // Runs until the `AuthServer` stops the worker or the `Ctx`
+    // is terminated.
+    pub(crate) fn run(
+        &self,
+        authenticator: &dyn Authenticator,
+    ) -> Result<(), Error> {
+        loop {
+            match self.recv()? {
+                WorkerReply::Authenticate(id, request) => {
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                        authenticator.authenticate(&request)
+                    }));
+
+                    let verdict = result.unwrap_or_else(|_| {
+                        error!("authenticator panicked");
+                        Verdict::new(StatusCode::InternalError, "ERROR")
+                    });
+
+                    self.send(&WorkerRequest::Verdict(id, verdict))?;
+                }
+                WorkerReply::Stop => return Ok(()),
+                WorkerReply::Registered => unreachable!(),
+            }
+        }
+    }
+}
+
+// Spawns `count` registered workers sharing the authenticator.
+pub(crate) fn spawn_workers<A>(
+    handle: CtxHandle,
+    authenticator: A,
+    count: usize,
+) -> Result<(), Error>
+where
+    A: Authenticator,
+{
+    let authenticator = Arc::new(authenticator);
+    let (sender, receiver) = mpsc::channel();
+
+    for _ in 0..count {
+        let authenticator = Arc::clone(&authenticator);
+        let sender = sender.clone();
+
+        thread::spawn(move || {
+            let worker = match AuthWorker::with_ctx(handle) {
+                Ok(worker) => worker,
+                Err(err) => {
+                    let _ = sender.send(Err(err));
+                    return;
+                }
+            };
+            let result = worker.register();
+            let registered = result.is_ok();
+            let _ = sender.send(result);
+            drop(sender);
+
+            if registered {
+                // This thread is guaranteed to terminate with the ctx
+                // because it terminates on `InvalidCtx` errors.
+                let _ = worker.run(&*authenticator);
+            }
+        });
+    }
+
+    for _ in 0..count {
+        receiver.recv().unwrap()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,322 @@
+use super::event::unmap_addr;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::{hash_map::Entry, BTreeSet, HashMap, VecDeque},
+    net::{IpAddr, Ipv6Addr},
+    time::{Duration, Instant},
+};
+
+// The `AuthServer` receives the peer addresses as IPv6.
+pub(crate) fn map_addr(addr: IpAddr) -> Ipv6Addr {
+    match addr {
+        IpAddr::V4(ipv4) => ipv4.to_ipv6_mapped(),
+        IpAddr::V6(ipv6) => ipv6,
+    }
+}
+
+/// A policy that temporarily bans the addresses of peers that repeatedly
+/// fail to authenticate.
+///
+/// An address is banned once `max_failures` `PLAIN` or `CURVE`
+/// authentication failures occured within the `window`. The ban
+/// automatically expires after `ban_duration`. A `ban_duration` too large
+/// to be represented results in a permanent ban.
+///
+/// # Example
+/// ```
+/// use libzmq::auth::BanPolicy;
+/// use std::time::Duration;
+///
+/// // Ban for an hour the addresses that failed 5 times within a minute.
+/// let policy = BanPolicy::new(
+///     5,
+///     Duration::from_secs(60),
+///     Duration::from_secs(3600),
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BanPolicy {
+    max_failures: u32,
+    #[serde(with = "humantime_serde")]
+    window: Duration,
+    #[serde(with = "humantime_serde")]
+    ban_duration: Duration,
+}
+
+impl BanPolicy {
+    /// Create a new `BanPolicy`.
+    pub fn new(
+        max_failures: u32,
+        window: Duration,
+        ban_duration: Duration,
+    ) -> Self {
+        Self {
+            max_failures,
+            window,
+            ban_duration,
+        }
+    }
+
+    /// Returns the number of failures that trigger a ban.
+    pub fn max_failures(&self) -> u32 {
+        self.max_failures
+    }
+
+    /// Returns the window in which the failures are counted.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Returns the duration of a ban.
+    pub fn ban_duration(&self) -> Duration {
+        self.ban_duration
+    }
+}
+
+/// An active ban of an address by the `AuthServer`.
+///
+/// See [`BanPolicy`].
+///
+/// [`BanPolicy`]: struct.BanPolicy.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Ban {
+    addr: Ipv6Addr,
+    remaining: Duration,
+}
+
+impl Ban {
+    /// The banned IP address.
+    pub fn addr(&self) -> IpAddr {
+        unmap_addr(self.addr)
+    }
+
+    /// The remaining duration of the ban.
+    ///
+    /// A permanent ban has the largest representable `Duration` remaining.
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+}
+
+// A ban without expiry is permanent.
+fn is_active(expiry: Option<Instant>, now: Instant) -> bool {
+    match expiry {
+        Some(expiry) => expiry > now,
+        None => true,
+    }
+}
+
+// Tracks the authentication failures and bans of a `ZAP` domain.
+#[derive(Default)]
+pub(crate) struct BanTracker {
+    policy: Option<BanPolicy>,
+    failures: HashMap<Ipv6Addr, VecDeque<Instant>>,
+    // All the failures in chronological order, so that those out of the
+    // window are forgotten without scanning every address.
+    failure_queue: VecDeque<(Instant, Ipv6Addr)>,
+    // The instant at which each ban expires, if it is not permanent.
+    bans: HashMap<Ipv6Addr, Option<Instant>>,
+    // The bans that are not permanent, ordered by expiry.
+    expiries: BTreeSet<(Instant, Ipv6Addr)>,
+}
+
+impl BanTracker {
+    pub(crate) fn set_policy(&mut self, policy: Option<BanPolicy>) {
+        if policy.is_none() {
+            self.clear_bans();
+        }
+        self.policy = policy;
+    }
+
+    pub(crate) fn policy(&self) -> Option<&BanPolicy> {
+        self.policy.as_ref()
+    }
+
+    pub(crate) fn is_banned(&mut self, addr: Ipv6Addr, now: Instant) -> bool {
+        self.purge(now);
+        self.bans.contains_key(&addr)
+    }
+
+    // Forgets the failures that are out of the window and the expired bans.
+    fn purge(&mut self, now: Instant) {
+        if let Some(policy) = &self.policy {
+            while let Some(&(instant, addr)) = self.failure_queue.front() {
+                if now.duration_since(instant) < policy.window {
+                    break;
+                }
+                self.failure_queue.pop_front();
+
+                // The failures of the address might have been reset since.
+                if let Entry::Occupied(mut entry) = self.failures.entry(addr) {
+                    if entry.get().front() == Some(&instant) {
+                        entry.get_mut().pop_front();
+                    }
+                    if entry.get().is_empty() {
+                        entry.remove();
+                    }
+                }
+            }
+        }
+
+        while let Some(&(expiry, addr)) = self.expiries.iter().next() {
+            if is_active(Some(expiry), now) {
+                break;
+            }
+            info!("ban of addr {} expired", addr);
+            self.expiries.remove(&(expiry, addr));
+            self.bans.remove(&addr);
+        }
+    }
+
+    // Records a failure and bans the address if the policy is exceeded.
+    pub(crate) fn add_failure(&mut self, addr: Ipv6Addr, now: Instant) {
+        if self.policy.is_none() {
+            return;
+        }
+        self.purge(now);
+
+        let failures = self.failures.entry(addr).or_default();
+        failures.push_back(now);
+        self.failure_queue.push_back((now, addr));
+
+        let policy = self.policy.as_ref().unwrap();
+        if failures.len() >= policy.max_failures as usize {
+            info!("banned addr {} for {:?}", addr, policy.ban_duration);
+            self.failures.remove(&addr);
+
+            let expiry = now.checked_add(policy.ban_duration);
+            if let Some(Some(previous)) = self.bans.insert(addr, expiry) {
+                self.expiries.remove(&(previous, addr));
+            }
+            if let Some(expiry) = expiry {
+                self.expiries.insert((expiry, addr));
+            }
+        }
+    }
+
+    pub(crate) fn bans(&mut self, now: Instant) -> Vec<Ban> {
+        self.purge(now);
+        self.bans
+            .iter()
+            .map(|(addr, expiry)| Ban {
+                addr: *addr,
+                remaining: match expiry {
+                    Some(expiry) => expiry.duration_since(now),
+                    None => Duration::new(u64::MAX, 999_999_999),
+                },
+            })
+            .collect()
+    }
+
+    pub(crate) fn remove_ban(&mut self, addr: Ipv6Addr) {
+        self.failures.remove(&addr);
+        if let Some(Some(expiry)) = self.bans.remove(&addr) {
+            self.expiries.remove(&(expiry, addr));
+        }
+    }
+
+    pub(crate) fn clear_bans(&mut self) {
+        self.failures.clear();
+        self.failure_queue.clear();
+        self.bans.clear();
+        self.expiries.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ban_tracker() {
+        let window = Duration::from_secs(10);
+        let ban_duration = Duration::from_secs(60);
+        let mut tracker = BanTracker::default();
+        let addr = map_addr("10.0.0.1".parse().unwrap());
+        let now = Instant::now();
+
+        // No policy, no bans.
+        for _ in 0..10 {
+            tracker.add_failure(addr, now);
+        }
+        assert!(!tracker.is_banned(addr, now));
+
+        tracker.set_policy(Some(BanPolicy::new(3, window, ban_duration)));
+
+        // The first failure is out of the window.
+        tracker.add_failure(addr, now);
+        tracker.add_failure(addr, now + window);
+        tracker.add_failure(addr, now + window);
+        assert!(!tracker.is_banned(addr, now + window));
+
+        let now = now + window;
+        tracker.add_failure(addr, now);
+        assert!(tracker.is_banned(addr, now));
+
+        let bans = tracker.bans(now);
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].addr(), "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(bans[0].remaining(), ban_duration);
+
+        // The ban expires.
+        let later = now + ban_duration;
+        assert!(!tracker.is_banned(addr, later));
+        assert!(tracker.bans(later).is_empty());
+
+        for _ in 0..3 {
+            tracker.add_failure(addr, later);
+        }
+        assert!(tracker.is_banned(addr, later));
+        tracker.remove_ban(addr);
+        assert!(!tracker.is_banned(addr, later));
+    }
+
+    #[test]
+    fn test_purge() {
+        let window = Duration::from_secs(10);
+        let ban_duration = Duration::from_secs(60);
+        let mut tracker = BanTracker::default();
+        let now = Instant::now();
+
+        tracker.set_policy(Some(BanPolicy::new(2, window, ban_duration)));
+        for i in 0..100u16 {
+            let addr = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, i);
+            tracker.add_failure(addr, now);
+            tracker.add_failure(addr, now);
+            tracker.add_failure(Ipv6Addr::LOCALHOST, now);
+        }
+        assert_eq!(tracker.bans.len(), 101);
+
+        // The failures out of the window and the expired bans are forgotten
+        // on the next failure, even though the addresses never reconnect.
+        let later = now + ban_duration;
+        let addr = map_addr("10.0.0.1".parse().unwrap());
+        tracker.add_failure(addr, later);
+        assert!(tracker.bans.is_empty());
+        assert!(tracker.expiries.is_empty());
+        assert_eq!(tracker.failures.len(), 1);
+        assert_eq!(tracker.failure_queue.len(), 1);
+    }
+
+    #[test]
+    fn test_permanent_ban() {
+        let max = Duration::new(u64::MAX, 999_999_999);
+        let mut tracker = BanTracker::default();
+        let addr = map_addr("10.0.0.1".parse().unwrap());
+        let now = Instant::now();
+
+        tracker.set_policy(Some(BanPolicy::new(
+            1,
+            Duration::from_secs(1),
+            max,
+        )));
+        tracker.add_failure(addr, now);
+
+        let later = now + Duration::from_secs(3600 * 24 * 365);
+        assert!(tracker.is_banned(addr, later));
+        assert_eq!(tracker.bans(later)[0].remaining(), max);
+    }
+}
@@ -0,0 +1,510 @@
+use super::{files::AuthFileError, *};
+use crate::zpl::Zpl;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    slice, vec,
+};
+
+const PUBLIC_HEADER: &str = "\
+#   ****  Generated by libzmq-rs  ****
+#   ZeroMQ CURVE Public Certificate
+#   Exchange securely, or use a secure mechanism to verify the contents
+#   of this file after exchange. Store public certificates in your home
+#   directory, in the .curve subdirectory.
+
+";
+
+const SECRET_HEADER: &str = "\
+#   ****  Generated by libzmq-rs  ****
+#   ZeroMQ CURVE **Secret** Certificate
+#   DO NOT PROVIDE THIS FILE TO OTHER USERS nor change its permissions.
+
+";
+
+const SECRET_SUFFIX: &str = "_secret";
+
+// Returns the path of the secret certificate, which `czmq` stores next to
+// the public one.
+fn secret_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(SECRET_SUFFIX);
+    path.with_file_name(name)
+}
+
+fn write_file(
+    path: &Path,
+    text: &str,
+    private: bool,
+) -> Result<(), AuthFileError> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    // Mirrors `czmq`, which makes the secret certificates only readable
+    // by their owner.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        if private {
+            options.mode(0o600);
+        }
+    }
+
+    let mut file = options
+        .open(path)
+        .map_err(|err| AuthFileError::io(path, err))?;
+
+    // The mode only applies to newly created files, so an existing file
+    // is restricted before the secret is written.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if private {
+            file.set_permissions(fs::Permissions::from_mode(0o600))
+                .map_err(|err| AuthFileError::io(path, err))?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+
+    file.write_all(text.as_bytes())
+        .map_err(|err| AuthFileError::io(path, err))
+}
+
+fn encode_cert(
+    path: &Path,
+    metadata: &BTreeMap<String, String>,
+    keys: Vec<(&str, &str)>,
+) -> Result<String, AuthFileError> {
+    let mut meta = Zpl::new("metadata", None);
+    for (name, value) in metadata {
+        Zpl::check_name(name)
+            .and_then(|()| Zpl::check_value(value))
+            .map_err(|err| AuthFileError::invalid(path, err.msg()))?;
+        meta.children
+            .push(Zpl::new(name.as_str(), Some(value.to_owned())));
+    }
+
+    let mut curve = Zpl::new("curve", None);
+    curve.children = keys
+        .into_iter()
+        .map(|(name, key)| Zpl::new(name, Some(key.to_owned())))
+        .collect();
+
+    let mut text = String::new();
+    Zpl::write(&[meta, curve], &mut text);
+    Ok(text)
+}
+
+// The content of a certificate file.
+struct CertFile {
+    metadata: BTreeMap<String, String>,
+    public: CurvePublicKey,
+    secret: Option<CurveSecretKey>,
+}
+
+impl CertFile {
+    fn load(path: &Path) -> Result<Self, AuthFileError> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| AuthFileError::io(path, err))?;
+        let nodes = Zpl::parse(&text).map_err(|err| {
+            let line = err.line().unwrap_or_default();
+            AuthFileError::parse(path, line, err.msg())
+        })?;
+
+        let mut metadata = BTreeMap::new();
+        let mut public = None;
+        let mut secret = None;
+
+        for node in &nodes {
+            match node.name.as_str() {
+                "metadata" => {
+                    for child in &node.children {
+                        let value = child.value.clone().unwrap_or_default();
+                        metadata.insert(child.name.to_owned(), value);
+                    }
+                }
+                "curve" => {
+                    let key = |name| {
+                        node.child(name).and_then(|key| key.value.as_ref())
+                    };
+                    public = key("public-key")
+                        .map(CurvePublicKey::new)
+                        .transpose()
+                        .map_err(|err| {
+                            AuthFileError::invalid(path, err.to_string())
+                        })?;
+                    secret = key("secret-key")
+                        .map(CurveSecretKey::new)
+                        .transpose()
+                        .map_err(|err| {
+                            AuthFileError::invalid(path, err.to_string())
+                        })?;
+                }
+                // Unknown sections are ignored, like `czmq` does.
+                _ => (),
+            }
+        }
+
+        let public = public.ok_or_else(|| {
+            AuthFileError::invalid(path, "missing public key")
+        })?;
+
+        Ok(Self {
+            metadata,
+            public,
+            secret,
+        })
+    }
+}
+
+impl CurveCert {
+    /// Save the certificate in the `czmq` `zcert` format.
+    ///
+    /// The public certificate is written to `path` and the secret
+    /// certificate to `path` suffixed with `_secret`. On unix, the secret
+    /// certificate is only readable by its owner.
+    ///
+    /// # Example
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::auth::CurveCert;
+    ///
+    /// let cert = CurveCert::new_unique().add_metadata("name", "server");
+    ///
+    /// # let path = std::env::temp_dir()
+    /// #     .join(format!("libzmq-doc-{}.key", uuid::Uuid::new_v4()));
+    /// cert.save(&path)?;
+    ///
+    /// let loaded = CurveCert::load(&path)?;
+    /// assert_eq!(cert, loaded);
+    /// #
+    /// # std::fs::remove_file(&path)?;
+    /// # std::fs::remove_file(path.with_extension("key_secret"))?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn save<P>(&self, path: P) -> Result<(), AuthFileError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        self.save_public(path)?;
+
+        let path = secret_path(path);
+        let text = encode_cert(
+            &path,
+            &self.metadata,
+            vec![
+                ("public-key", self.public.as_str()),
+                ("secret-key", self.secret.as_str()),
+            ],
+        )?;
+
+        write_file(&path, &(SECRET_HEADER.to_owned() + &text), true)
+    }
+
+    /// Save only the public certificate in the `czmq` `zcert` format.
+    ///
+    /// This is the file that is handed over to the peers.
+    pub fn save_public<P>(&self, path: P) -> Result<(), AuthFileError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let text = encode_cert(
+            path,
+            &self.metadata,
+            vec![("public-key", self.public.as_str())],
+        )?;
+
+        write_file(path, &(PUBLIC_HEADER.to_owned() + &text), false)
+    }
+
+    /// Load a certificate saved in the `czmq` `zcert` format.
+    ///
+    /// The keys are read from the secret certificate, which is `path`
+    /// suffixed with `_secret`.
+    pub fn load<P>(path: P) -> Result<Self, AuthFileError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = secret_path(path.as_ref());
+        let file = CertFile::load(&path)?;
+
+        let secret = file.secret.ok_or_else(|| {
+            AuthFileError::invalid(&path, "missing secret key")
+        })?;
+
+        if CurvePublicKey::from(&secret) != file.public {
+            return Err(AuthFileError::invalid(
+                &path,
+                "public key does not match the secret key",
+            ));
+        }
+
+        Ok(Self {
+            public: file.public,
+            secret,
+            metadata: file.metadata,
+        })
+    }
+}
+
+/// A directory of public certificates in the `czmq` `zcert` format.
+///
+/// Each certificate becomes a [`CurveRegistryEntry`] that assigns the
+/// certificate's metadata to the peer. Hidden files and secret certificates
+/// (whose name ends with `_secret`) are ignored.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::auth::*;
+///
+/// # let dir = std::env::temp_dir()
+/// #     .join(format!("libzmq-doc-{}", uuid::Uuid::new_v4()));
+/// std::fs::create_dir_all(&dir)?;
+///
+/// let cert = CurveCert::new_unique();
+/// cert.save(dir.join("client.key"))?;
+///
+/// let store = CertStore::load(&dir)?;
+/// assert!(store.get(cert.public()).is_some());
+///
+/// // Only allow the public keys of the store.
+/// let _ = AuthBuilder::new().curve_registry(&store).build()?;
+/// #
+/// # std::fs::remove_dir_all(&dir)?;
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`CurveRegistryEntry`]: struct.CurveRegistryEntry.html
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CertStore {
+    entries: Vec<CurveRegistryEntry>,
+}
+
+impl CertStore {
+    /// Load the public certificates of the directory, in the order of
+    /// their file name.
+    pub fn load<P>(dir: P) -> Result<Self, AuthFileError>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+
+        let mut paths = vec![];
+        let read_dir =
+            fs::read_dir(dir).map_err(|err| AuthFileError::io(dir, err))?;
+        for entry in read_dir {
+            let path = entry.map_err(|err| AuthFileError::io(dir, err))?.path();
+            let name = match path.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => continue,
+            };
+
+            if path.is_file()
+                && !name.starts_with('.')
+                && !name.ends_with(SECRET_SUFFIX)
+            {
+                paths.push(path);
+            }
+        }
+        // Make the order of the entries independent of the file system.
+        paths.sort();
+
+        let mut entries = vec![];
+        for path in paths {
+            let file = CertFile::load(&path)?;
+            let mut entry = CurveRegistryEntry::new(file.public);
+            entry.metadata = file.metadata;
+            entries.push(entry);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Returns the entry of the public key, if present.
+    pub fn get(
+        &self,
+        public_key: &CurvePublicKey,
+    ) -> Option<&CurveRegistryEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.public_key() == public_key)
+    }
+
+    /// Returns an iterator over the entries.
+    pub fn iter(&self) -> slice::Iter<'_, CurveRegistryEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns the number of certificates.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the store contains no certificates.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl IntoIterator for CertStore {
+    type Item = CurveRegistryEntry;
+    type IntoIter = vec::IntoIter<CurveRegistryEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CertStore {
+    type Item = &'a CurveRegistryEntry;
+    type IntoIter = slice::Iter<'a, CurveRegistryEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Creates a new empty directory.
+    fn test_dir() -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("libzmq-cert-{}", uuid::Uuid::new_v4()));
+        fs::create_dir(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_save_load() {
+        let dir = test_dir();
+        let path = dir.join("server.key");
+
+        let cert = CurveCert::new_unique()
+            .add_metadata("name", "server")
+            .add_metadata("email", "ops@example.com");
+        cert.save(&path).unwrap();
+
+        let public = fs::read_to_string(&path).unwrap();
+        assert!(public.contains("public-key"));
+        assert!(!public.contains("secret-key"));
+        assert!(!public.contains(cert.secret().as_str()));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let meta = fs::metadata(secret_path(&path)).unwrap();
+            assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+        }
+
+        assert_eq!(CurveCert::load(&path).unwrap(), cert);
+
+        // The secret certificate is required.
+        let other = dir.join("other.key");
+        cert.save_public(&other).unwrap();
+        CurveCert::load(&other).unwrap_err();
+
+        // An existing secret certificate is made private as well.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let secret = secret_path(&path);
+            let mode = fs::Permissions::from_mode(0o644);
+            fs::set_permissions(&secret, mode).unwrap();
+
+            cert.save(&path).unwrap();
+            let meta = fs::metadata(&secret).unwrap();
+            assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+        }
+
+        // Metadata that cannot be written is rejected.
+        let invalid = &[("", "v"), ("a b", "v"), ("k", "a\nb"), ("k", "'\"")];
+        for (name, value) in invalid {
+            let err = CurveCert::new_unique()
+                .add_metadata(*name, *value)
+                .save(dir.join("invalid.key"))
+                .unwrap_err();
+            match err {
+                AuthFileError::Invalid { .. } => (),
+                _ => panic!("unexpected error: {}", err),
+            }
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_czmq() {
+        let dir = test_dir();
+        let cert = CurveCert::new_unique();
+
+        // As written by `zcert_save_secret`.
+        let text = format!(
+            "#   ****  Generated on 2019-06-01 12:00:00 by CZMQ  ****\n\
+             #   ZeroMQ CURVE **Secret** Certificate\n\
+             \n\
+             metadata\n    \
+                 name = \"client\"\n\
+             curve\n    \
+                 public-key = \"{}\"\n    \
+                 secret-key = \"{}\"\n",
+            cert.public(),
+            cert.secret().as_str(),
+        );
+        fs::write(dir.join("client.key_secret"), text).unwrap();
+
+        let loaded = CurveCert::load(dir.join("client.key")).unwrap();
+        assert_eq!(loaded, cert.clone().add_metadata("name", "client"));
+
+        // The keys must match.
+        let other = CurveCert::new_unique();
+        let text = format!(
+            "curve\n    public-key = \"{}\"\n    secret-key = \"{}\"\n",
+            cert.public(),
+            other.secret().as_str(),
+        );
+        fs::write(dir.join("bad.key_secret"), text).unwrap();
+        CurveCert::load(dir.join("bad.key")).unwrap_err();
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cert_store() {
+        let dir = test_dir();
+
+        let a = CurveCert::new_unique().add_metadata("name", "a");
+        let b = CurveCert::new_unique();
+        a.save(dir.join("a.key")).unwrap();
+        b.save_public(dir.join("b.key")).unwrap();
+        fs::write(dir.join(".hidden"), "ignored").unwrap();
+
+        let store = CertStore::load(&dir).unwrap();
+        assert_eq!(store.len(), 2);
+
+        let entries: Vec<&CurveRegistryEntry> = store.iter().collect();
+        assert_eq!(entries[0].public_key(), a.public());
+        assert_eq!(entries[0].metadata(), a.metadata());
+        assert_eq!(entries[1].public_key(), b.public());
+        assert!(store.get(b.public()).unwrap().metadata().is_empty());
+
+        fs::write(dir.join("c.key"), "curve\n    secret-key = \"\"\n").unwrap();
+        CertStore::load(&dir).unwrap_err();
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,37 +1,65 @@
-use super::{server::COMMAND_ENDPOINT, *};
-use crate::{addr::IntoIpAddrs, prelude::*, socket::*, *};
+use super::{authenticator::*, server::COMMAND_ENDPOINT, *};
+use crate::{
+    addr::{IntoIpAddrs, IntoSubnets, Subnet},
+    core::DEFAULT_ZAP_DOMAIN,
+    prelude::*,
+    socket::*,
+    *,
+};
 
 use serde::{Deserialize, Serialize};
 
-use std::net::{IpAddr, Ipv6Addr};
+use std::net::IpAddr;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub(crate) enum AuthRequest {
-    AddBlacklist(Ipv6Addr),
-    RemoveBlacklist(Ipv6Addr),
-    SetBlacklist(Vec<Ipv6Addr>),
-    AddWhitelist(Ipv6Addr),
-    RemoveWhitelist(Ipv6Addr),
-    SetWhitelist(Vec<Ipv6Addr>),
-    AddPlainRegistry(PlainClientCreds),
+    AddBlacklist(Subnet),
+    RemoveBlacklist(Subnet),
+    SetBlacklist(Vec<Subnet>),
+    AddWhitelist(Subnet),
+    RemoveWhitelist(Subnet),
+    SetWhitelist(Vec<Subnet>),
+    AddPlainRegistry(PlainRegistryEntry),
     RemovePlainRegistry(String),
-    SetPlainRegistry(Vec<PlainClientCreds>),
-    AddCurveRegistry(CurvePublicKey),
+    SetPlainRegistry(Vec<PlainRegistryEntry>),
+    AddCurveRegistry(CurveRegistryEntry),
     RemoveCurveRegistry(CurvePublicKey),
-    SetCurveRegistry(Vec<CurvePublicKey>),
+    SetCurveRegistry(Vec<CurveRegistryEntry>),
     SetCurveAuth(bool),
+    SetHashParams(HashParams),
+    ClearAuthenticator,
+    SetBanPolicy(Option<BanPolicy>),
+    Bans,
+    RemoveBan(IpAddr),
+    ClearBans,
+    Blacklist,
+    Whitelist,
+    PlainRegistry,
+    CurveRegistry,
+    CurveAuth,
+    HashParams,
+    Config,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub(crate) enum AuthReply {
     Success,
+    Bans(Vec<Ban>),
+    Subnets(Vec<Subnet>),
+    PlainRegistry(Vec<PlainRegistryEntry>),
+    CurveRegistry(Vec<CurveRegistryEntry>),
+    CurveAuth(bool),
+    HashParams(HashParams),
+    Config(AuthConfig),
 }
 
-fn into_ipv6(ip: IpAddr) -> Ipv6Addr {
-    match ip {
-        IpAddr::V4(ipv4) => ipv4.to_ipv6_mapped(),
-        IpAddr::V6(ipv6) => ipv6,
+fn check_ban_policy(policy: &BanPolicy) -> Result<(), Error> {
+    if policy.max_failures() == 0 {
+        return Err(Error::new(ErrorKind::InvalidInput(
+            "ban policy cannot have zero max failures",
+        )));
     }
+    Ok(())
 }
 
 /// A client to configure the `AuthServer`.
@@ -88,6 +116,7 @@ fn into_ipv6(ip: IpAddr) -> Ipv6Addr {
 /// ```
 pub struct AuthClient {
     client: Client,
+    domain: String,
 }
 
 impl AuthClient {
@@ -105,31 +134,115 @@ impl AuthClient {
             .with_ctx(handle)
             .map_err(Error::cast)?;
 
-        Ok(AuthClient { client })
+        Ok(AuthClient {
+            client,
+            domain: DEFAULT_ZAP_DOMAIN.to_owned(),
+        })
+    }
+
+    /// Returns the `ZAP` domain configured by the `AuthClient`.
+    ///
+    /// Defaults to "global".
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Set the `ZAP` domain configured by the `AuthClient`.
+    ///
+    /// Subsequent requests only affect the sockets of the `ZAP` domain.
+    ///
+    /// # Example
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::{prelude::*, auth::*, *};
+    /// use std::time::Duration;
+    ///
+    /// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+    ///
+    /// let server = ServerBuilder::new()
+    ///     .bind(addr)
+    ///     .zap_domain("internal")
+    ///     .recv_timeout(Duration::from_millis(200))
+    ///     .build()?;
+    ///
+    /// let mut auth = AuthClient::new()?;
+    /// auth.set_domain("internal");
+    /// // Only affects the sockets in the "internal" domain.
+    /// auth.set_blacklist("127.0.0.1".parse::<std::net::IpAddr>()?)?;
+    ///
+    /// let bound = server.last_endpoint()?;
+    /// let client = ClientBuilder::new().connect(bound).build()?;
+    ///
+    /// client.send("")?;
+    /// assert!(server.recv_msg().is_err());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn set_domain<S>(&mut self, domain: S)
+    where
+        S: Into<String>,
+    {
+        self.domain = domain.into();
     }
 
     fn request(&self, request: &AuthRequest) -> Result<(), Error> {
-        let ser = bincode::serialize(request).unwrap();
+        self.request_domain(&self.domain, request)
+    }
 
-        self.client.send(ser).map_err(Error::cast)?;
-        let msg = self.client.recv_msg()?;
-        let reply: AuthReply = bincode::deserialize(msg.as_bytes()).unwrap();
+    fn query(&self, request: &AuthRequest) -> Result<AuthReply, Error> {
+        self.query_domain(&self.domain, request)
+    }
+
+    fn request_domain(
+        &self,
+        domain: &str,
+        request: &AuthRequest,
+    ) -> Result<(), Error> {
+        let reply = self.query_domain(domain, request)?;
 
         assert_eq!(reply, AuthReply::Success);
         Ok(())
     }
 
-    /// Add the ips to the `AuthServer`'s blacklist.
+    fn query_domain(
+        &self,
+        domain: &str,
+        request: &AuthRequest,
+    ) -> Result<AuthReply, Error> {
+        let ser = bincode::serialize(&(domain, request)).unwrap();
+
+        self.client.send(ser).map_err(Error::cast)?;
+        let msg = self.client.recv_msg()?;
+
+        Ok(bincode::deserialize(msg.as_bytes()).unwrap())
+    }
+
+    /// Returns the networks in the `AuthServer`'s blacklist.
+    pub fn blacklist(&self) -> Result<Vec<Subnet>, Error> {
+        match self.query(&AuthRequest::Blacklist)? {
+            AuthReply::Subnets(subnets) => Ok(subnets),
+            reply => panic!("unexpected reply: {:?}", reply),
+        }
+    }
+
+    /// Add the networks to the `AuthServer`'s blacklist.
     ///
-    /// Blacklisted ips will be denied access.
+    /// Blacklisted ips will be denied access. See [`Subnet`] for the
+    /// network format and the [`auth`] module for the precedence rules.
+    ///
+    /// [`Subnet`]: ../addr/struct.Subnet.html
+    /// [`auth`]: index.html#address-filtering
     pub fn add_blacklist<I>(&self, ips: I) -> Result<(), Error<usize>>
     where
-        I: IntoIpAddrs,
+        I: IntoSubnets,
     {
         let mut count = 0;
 
-        for ipv6 in ips.into_ip_addrs().map(into_ipv6) {
-            self.request(&AuthRequest::AddBlacklist(ipv6))
+        for subnet in ips.into_subnets() {
+            self.request(&AuthRequest::AddBlacklist(subnet))
                 .map_err(|err| Error::with_content(err.kind(), count))?;
 
             count += 1;
@@ -137,16 +250,16 @@ impl AuthClient {
         Ok(())
     }
 
-    /// Remove the ips from the `AuthServer`'s blacklist, if
+    /// Remove the networks from the `AuthServer`'s blacklist, if
     /// they are present.
     pub fn remove_blacklist<I>(&self, ips: I) -> Result<(), Error<usize>>
     where
-        I: IntoIpAddrs,
+        I: IntoSubnets,
     {
         let mut count = 0;
 
-        for ipv6 in ips.into_ip_addrs().map(into_ipv6) {
-            self.request(&AuthRequest::RemoveBlacklist(ipv6))
+        for subnet in ips.into_subnets() {
+            self.request(&AuthRequest::RemoveBlacklist(subnet))
                 .map_err(|err| Error::with_content(err.kind(), count))?;
 
             count += 1;
@@ -154,32 +267,43 @@ impl AuthClient {
         Ok(())
     }
 
-    /// Set the ips in the `AuthServer`'s blacklist.
+    /// Set the networks in the `AuthServer`'s blacklist.
     ///
     /// Blacklisted ips will be denied access.
     pub fn set_blacklist<I>(&self, ips: I) -> Result<(), Error>
     where
-        I: IntoIpAddrs,
+        I: IntoSubnets,
     {
-        let ips: Vec<Ipv6Addr> = ips.into_ip_addrs().map(into_ipv6).collect();
+        let subnets: Vec<Subnet> = ips.into_subnets().collect();
 
-        self.request(&AuthRequest::SetBlacklist(ips))
+        self.request(&AuthRequest::SetBlacklist(subnets))
             .map_err(Error::cast)
     }
 
-    /// Add the ips to the `AuthServer`'s whitelist.
+    /// Returns the networks in the `AuthServer`'s whitelist.
+    pub fn whitelist(&self) -> Result<Vec<Subnet>, Error> {
+        match self.query(&AuthRequest::Whitelist)? {
+            AuthReply::Subnets(subnets) => Ok(subnets),
+            reply => panic!("unexpected reply: {:?}", reply),
+        }
+    }
+
+    /// Add the networks to the `AuthServer`'s whitelist.
+    ///
+    /// If the whitelist is not empty, only ips present in the whitelist
+    /// are allowed. See [`Subnet`] for the network format and the [`auth`]
+    /// module for the precedence rules.
     ///
-    /// If the whitelist is not empty, only ips in present
-    /// in the whitelist are allowed. The whitelist takes precedence
-    /// over the blacklist.
+    /// [`Subnet`]: ../addr/struct.Subnet.html
+    /// [`auth`]: index.html#address-filtering
     pub fn add_whitelist<I>(&self, ips: I) -> Result<(), Error<usize>>
     where
-        I: IntoIpAddrs,
+        I: IntoSubnets,
     {
         let mut count = 0;
 
-        for ipv6 in ips.into_ip_addrs().map(into_ipv6) {
-            self.request(&AuthRequest::AddWhitelist(ipv6))
+        for subnet in ips.into_subnets() {
+            self.request(&AuthRequest::AddWhitelist(subnet))
                 .map_err(|err| Error::with_content(err.kind(), count))?;
 
             count += 1;
@@ -187,16 +311,16 @@ impl AuthClient {
         Ok(())
     }
 
-    /// Remove the ips from the `AuthServer`'s whitelist, if it
-    /// is present.
+    /// Remove the networks from the `AuthServer`'s whitelist, if they
+    /// are present.
     pub fn remove_whitelist<I>(&self, ips: I) -> Result<(), Error<usize>>
     where
-        I: IntoIpAddrs,
+        I: IntoSubnets,
     {
         let mut count = 0;
 
-        for ipv6 in ips.into_ip_addrs().map(into_ipv6) {
-            self.request(&AuthRequest::RemoveWhitelist(ipv6))
+        for subnet in ips.into_subnets() {
+            self.request(&AuthRequest::RemoveWhitelist(subnet))
                 .map_err(|err| Error::with_content(err.kind(), count))?;
 
             count += 1;
@@ -204,32 +328,102 @@ impl AuthClient {
         Ok(())
     }
 
-    /// Set the ips in the `AuthServer`'s whitelist.
+    /// Set the networks in the `AuthServer`'s whitelist.
     ///
-    /// If the whitelist is not empty, only ips in present
-    /// in the whitelist are allowed. The whitelist takes precedence
-    /// over the blacklist.
+    /// If the whitelist is not empty, only ips present in the whitelist
+    /// are allowed.
     pub fn set_whitelist<I>(&self, ips: I) -> Result<(), Error>
     where
-        I: IntoIpAddrs,
+        I: IntoSubnets,
     {
-        let ips: Vec<Ipv6Addr> = ips.into_ip_addrs().map(into_ipv6).collect();
+        let subnets: Vec<Subnet> = ips.into_subnets().collect();
+
+        self.request(&AuthRequest::SetWhitelist(subnets))
+    }
+
+    /// Returns the parameters used to hash the cleartext passwords of the
+    /// plain registry.
+    pub fn hash_params(&self) -> Result<HashParams, Error> {
+        match self.query(&AuthRequest::HashParams)? {
+            AuthReply::HashParams(params) => Ok(params),
+            reply => panic!("unexpected reply: {:?}", reply),
+        }
+    }
 
-        self.request(&AuthRequest::SetWhitelist(ips))
+    /// Set the parameters used to hash the cleartext passwords of the
+    /// plain registry.
+    ///
+    /// Only the passwords added afterwards are affected. The passwords of
+    /// unknown users are verified against a hash with the same parameters,
+    /// so that the response time does not reveal which users exist.
+    ///
+    /// Each `PLAIN` handshake verifies a hash on the `AuthServer` thread,
+    /// so these parameters bound the rate of `PLAIN` handshakes. See
+    /// [`HashParams`].
+    ///
+    /// # Example
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::{prelude::*, auth::*, *};
+    ///
+    /// // Use a new context to avoid impacting other sockets.
+    /// let ctx = Ctx::new();
+    /// let auth = AuthClient::with_ctx(ctx.handle())?;
+    /// assert_eq!(auth.hash_params()?, HashParams::default());
+    ///
+    /// let params = HashParams::new(8 * 1024, 1, 1)?;
+    /// auth.set_hash_params(params)?;
+    /// auth.set_plain_registry(&[PlainClientCreds::new("user", "pwd")])?;
+    ///
+    /// let registry = auth.plain_registry()?;
+    /// match registry[0].password() {
+    ///     PlainPassword::Hash(hash) => {
+    ///         assert!(hash.as_str().contains("$m=8192,t=1,p=1$"))
+    ///     }
+    ///     PlainPassword::Clear(_) => unreachable!(),
+    /// }
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`HashParams`]: struct.HashParams.html
+    pub fn set_hash_params(&self, params: HashParams) -> Result<(), Error> {
+        self.request(&AuthRequest::SetHashParams(params))
+    }
+
+    /// Returns the entries of the `AuthServer`'s plain registry, sorted
+    /// by username.
+    pub fn plain_registry(&self) -> Result<Vec<PlainRegistryEntry>, Error> {
+        match self.query(&AuthRequest::PlainRegistry)? {
+            AuthReply::PlainRegistry(entries) => Ok(entries),
+            reply => panic!("unexpected reply: {:?}", reply),
+        }
     }
 
     /// Add the credentials to the `AuthServer`'s plain registry.
     ///
     /// Only credentials present in the registry can successfully authenticate.
+    /// A [`PlainRegistryEntry`] can be used to customize the user id and
+    /// metadata assigned to the peer.
+    ///
+    /// The `AuthServer` hashes the cleartext passwords on its thread, which
+    /// delays the handshakes in the meantime. Use
+    /// [`PlainRegistryEntry::hashed_with`] to hash them beforehand.
+    ///
+    /// [`PlainRegistryEntry`]: struct.PlainRegistryEntry.html
+    /// [`PlainRegistryEntry::hashed_with`]: struct.PlainRegistryEntry.html#method.hashed_with
     pub fn add_plain_registry<I, E>(&self, iter: I) -> Result<(), Error<usize>>
     where
         I: IntoIterator<Item = E>,
-        E: Into<PlainClientCreds>,
+        E: Into<PlainRegistryEntry>,
     {
         let mut count = 0;
 
-        for creds in iter.into_iter().map(E::into) {
-            self.request(&AuthRequest::AddPlainRegistry(creds))
+        for entry in iter.into_iter().map(E::into) {
+            self.request(&AuthRequest::AddPlainRegistry(entry))
                 .map_err(|err| Error::with_content(err.kind(), count))?;
 
             count += 1;
@@ -263,27 +457,39 @@ impl AuthClient {
     pub fn set_plain_registry<I, E>(&self, creds: I) -> Result<(), Error>
     where
         I: IntoIterator<Item = E>,
-        E: Into<PlainClientCreds>,
+        E: Into<PlainRegistryEntry>,
     {
-        let plain_creds: Vec<PlainClientCreds> =
+        let entries: Vec<PlainRegistryEntry> =
             creds.into_iter().map(E::into).collect();
 
-        self.request(&AuthRequest::SetPlainRegistry(plain_creds))
+        self.request(&AuthRequest::SetPlainRegistry(entries))
+    }
+
+    /// Returns the entries of the `AuthServer`'s curve registry, sorted
+    /// by public key.
+    pub fn curve_registry(&self) -> Result<Vec<CurveRegistryEntry>, Error> {
+        match self.query(&AuthRequest::CurveRegistry)? {
+            AuthReply::CurveRegistry(entries) => Ok(entries),
+            reply => panic!("unexpected reply: {:?}", reply),
+        }
     }
 
     /// Add the curve keys to the curve registry.
     ///
     /// Only public keys present in the whitelist are allowed to authenticate
-    /// via the `CURVE` mechanism.
+    /// via the `CURVE` mechanism. A [`CurveRegistryEntry`] can be used to
+    /// customize the user id and metadata assigned to the peer.
+    ///
+    /// [`CurveRegistryEntry`]: struct.CurveRegistryEntry.html
     pub fn add_curve_registry<I, E>(&self, keys: I) -> Result<(), Error<usize>>
     where
         I: IntoIterator<Item = E>,
-        E: Into<CurvePublicKey>,
+        E: Into<CurveRegistryEntry>,
     {
         let mut count = 0;
 
-        for key in keys.into_iter().map(E::into) {
-            self.request(&AuthRequest::AddCurveRegistry(key))
+        for entry in keys.into_iter().map(E::into) {
+            self.request(&AuthRequest::AddCurveRegistry(entry))
                 .map_err(|err| Error::with_content(err.kind(), count))?;
 
             count += 1;
@@ -316,11 +522,20 @@ impl AuthClient {
     pub fn set_curve_registry<I, E>(&self, keys: I) -> Result<(), Error>
     where
         I: IntoIterator<Item = E>,
-        E: Into<CurvePublicKey>,
+        E: Into<CurveRegistryEntry>,
     {
-        let keys: Vec<CurvePublicKey> = keys.into_iter().map(E::into).collect();
+        let entries: Vec<CurveRegistryEntry> =
+            keys.into_iter().map(E::into).collect();
+
+        self.request(&AuthRequest::SetCurveRegistry(entries))
+    }
 
-        self.request(&AuthRequest::SetCurveRegistry(keys))
+    /// Returns whether authentication is used for the `CURVE` mechanism.
+    pub fn curve_auth(&self) -> Result<bool, Error> {
+        match self.query(&AuthRequest::CurveAuth)? {
+            AuthReply::CurveAuth(enabled) => Ok(enabled),
+            reply => panic!("unexpected reply: {:?}", reply),
+        }
     }
 
     /// Sets whether to use authentication for the `CURVE` mechanism.
@@ -331,19 +546,208 @@ impl AuthClient {
     pub fn set_curve_auth(&self, enabled: bool) -> Result<(), Error> {
         self.request(&AuthRequest::SetCurveAuth(enabled))
     }
+
+    /// Set the [`BanPolicy`] of the `AuthServer`, or disable bans if `None`.
+    ///
+    /// Disabling bans lifts all the active bans.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if the max failures is zero)
+    /// * [`InvalidCtx`]
+    ///
+    /// # Example
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::{prelude::*, auth::*, *};
+    /// use std::time::Duration;
+    ///
+    /// let auth = AuthClient::new()?;
+    /// let policy = BanPolicy::new(
+    ///     5,
+    ///     Duration::from_secs(60),
+    ///     Duration::from_secs(3600),
+    /// );
+    /// auth.set_ban_policy(Some(policy))?;
+    ///
+    /// assert!(auth.bans()?.is_empty());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`BanPolicy`]: struct.BanPolicy.html
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    pub fn set_ban_policy(
+        &self,
+        maybe: Option<BanPolicy>,
+    ) -> Result<(), Error> {
+        if let Some(ref policy) = maybe {
+            check_ban_policy(policy)?;
+        }
+        self.request(&AuthRequest::SetBanPolicy(maybe))
+    }
+
+    /// Returns the active bans of the `AuthServer`.
+    pub fn bans(&self) -> Result<Vec<Ban>, Error> {
+        match self.query(&AuthRequest::Bans)? {
+            AuthReply::Bans(bans) => Ok(bans),
+            reply => panic!("unexpected reply: {:?}", reply),
+        }
+    }
+
+    /// Lift the bans of the given ips, if they are banned.
+    pub fn remove_bans<I>(&self, ips: I) -> Result<(), Error<usize>>
+    where
+        I: IntoIpAddrs,
+    {
+        let mut count = 0;
+
+        for ip in ips.into_ip_addrs() {
+            self.request(&AuthRequest::RemoveBan(ip))
+                .map_err(|err| Error::with_content(err.kind(), count))?;
+
+            count += 1;
+        }
+        Ok(())
+    }
+
+    /// Lift all the active bans of the `AuthServer`.
+    pub fn clear_bans(&self) -> Result<(), Error> {
+        self.request(&AuthRequest::ClearBans)
+    }
+
+    /// Returns a snapshot of the configuration that the `AuthServer`
+    /// enforces for the `ZAP` domain of the `AuthClient`.
+    ///
+    /// Every field of the returned [`AuthConfig`] is set, so that applying
+    /// it restores the exact same configuration, for instance after
+    /// a restart. The `PLAIN` passwords are only part of the snapshot as
+    /// hashes.
+    ///
+    /// The active bans and the installed [`Authenticator`] are not part
+    /// of the snapshot.
+    ///
+    /// # Example
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::{prelude::*, auth::*, *};
+    ///
+    /// // Use a new context to avoid impacting other sockets.
+    /// let ctx = Ctx::new();
+    ///
+    /// let creds = PlainClientCreds::new("user", "pass");
+    /// let auth = AuthBuilder::new()
+    ///     .plain_registry(&creds)
+    ///     .no_curve_auth()
+    ///     .with_ctx(ctx.handle())?;
+    ///
+    /// let config = auth.config()?;
+    /// assert_eq!(config.domain(), Some("global"));
+    /// assert_eq!(config.curve_auth(), Some(false));
+    /// assert_eq!(config.plain_registry().unwrap().len(), 1);
+    ///
+    /// // Apply the same configuration to another context.
+    /// let other = Ctx::new();
+    /// let copy = config.with_ctx(other.handle())?;
+    /// assert_eq!(copy.config()?, config);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`AuthConfig`]: ../config/struct.AuthConfig.html
+    /// [`Authenticator`]: trait.Authenticator.html
+    pub fn config(&self) -> Result<AuthConfig, Error> {
+        match self.query(&AuthRequest::Config)? {
+            AuthReply::Config(config) => Ok(config),
+            reply => panic!("unexpected reply: {:?}", reply),
+        }
+    }
+
+    /// Install an [`Authenticator`] in place of the `AuthServer`'s `PLAIN`
+    /// and `CURVE` registries, running on a pool of `workers` threads.
+    ///
+    /// Requests are dispatched to the workers on a round-robin basis. Any
+    /// previously installed `Authenticator` is removed. The worker threads
+    /// terminate with the `Ctx`.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if `workers` is zero)
+    /// * [`InvalidCtx`]
+    ///
+    /// [`Authenticator`]: trait.Authenticator.html
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    pub fn set_authenticator<A>(
+        &self,
+        authenticator: A,
+        workers: usize,
+    ) -> Result<(), Error>
+    where
+        A: Authenticator,
+    {
+        if workers == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput(
+                "cannot have zero workers",
+            )));
+        }
+        self.clear_authenticator()?;
+        spawn_workers(self.client.ctx(), authenticator, workers)
+    }
+
+    /// Install an [`Authenticator`] in place of the `AuthServer`'s `PLAIN`
+    /// and `CURVE` registries, running on the current thread.
+    ///
+    /// Any previously installed `Authenticator` is removed. This blocks until
+    /// the `Authenticator` is removed via [`clear_authenticator`] or
+    /// replaced, in which case `Ok(())` is returned.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`]
+    ///
+    /// [`Authenticator`]: trait.Authenticator.html
+    /// [`clear_authenticator`]: #method.clear_authenticator
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    pub fn run_authenticator<A>(&self, authenticator: A) -> Result<(), Error>
+    where
+        A: Authenticator,
+    {
+        self.clear_authenticator()?;
+        let worker = AuthWorker::with_ctx(self.client.ctx())?;
+        worker.register()?;
+        worker.run(&authenticator)
+    }
+
+    /// Remove the installed [`Authenticator`], if any, so that the
+    /// `AuthServer` falls back to its `PLAIN` and `CURVE` registries.
+    ///
+    /// [`Authenticator`]: trait.Authenticator.html
+    pub fn clear_authenticator(&self) -> Result<(), Error> {
+        self.request(&AuthRequest::ClearAuthenticator)
+    }
 }
 
 /// A Configuration of the `AuthServer`.
 ///
 /// A `AuthClient` must be used to communicate this configuration with the
 /// server.
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
 pub struct AuthConfig {
-    blacklist: Option<Vec<IpAddr>>,
-    whitelist: Option<Vec<IpAddr>>,
-    plain_registry: Option<Vec<PlainClientCreds>>,
-    curve_registry: Option<Vec<CurvePublicKey>>,
+    domain: Option<String>,
+    blacklist: Option<Vec<Subnet>>,
+    whitelist: Option<Vec<Subnet>>,
+    hash_params: Option<HashParams>,
+    plain_registry: Option<Vec<PlainRegistryEntry>>,
+    curve_registry: Option<Vec<CurveRegistryEntry>>,
     curve_auth: Option<bool>,
+    ban_policy: Option<BanPolicy>,
 }
 
 impl AuthConfig {
@@ -362,7 +766,10 @@ impl AuthConfig {
     /// to the `AuthServer` associated with the context aliased by the
     /// `CtxHandle`.
     pub fn with_ctx(&self, handle: CtxHandle) -> Result<AuthClient, Error> {
-        let client = AuthClient::with_ctx(handle)?;
+        let mut client = AuthClient::with_ctx(handle)?;
+        if let Some(ref domain) = self.domain {
+            client.set_domain(domain.as_str());
+        }
         self.apply(&client)?;
 
         Ok(client)
@@ -370,74 +777,143 @@ impl AuthConfig {
 
     /// Apply the configuration to the `AuthClient` which will send
     /// it to its associated `AuthServer`.
+    ///
+    /// If the configuration specifies a `ZAP` domain, it takes precedence
+    /// over the domain of the `AuthClient`.
     pub fn apply(&self, client: &AuthClient) -> Result<(), Error> {
+        let domain = self.domain.as_deref().unwrap_or_else(|| client.domain());
+
         if let Some(ref blacklist) = self.blacklist {
-            client.set_blacklist(blacklist)?;
+            let request = AuthRequest::SetBlacklist(blacklist.to_owned());
+            client.request_domain(domain, &request)?;
         }
         if let Some(ref whitelist) = self.whitelist {
-            client.set_whitelist(whitelist)?;
+            let request = AuthRequest::SetWhitelist(whitelist.to_owned());
+            client.request_domain(domain, &request)?;
         }
-        if let Some(ref creds) = self.plain_registry {
-            client.set_plain_registry(creds)?;
+        // Must precede the registry for its passwords to use the params.
+        if let Some(params) = self.hash_params {
+            let request = AuthRequest::SetHashParams(params);
+            client.request_domain(domain, &request)?;
         }
-        if let Some(ref keys) = self.curve_registry {
-            client.set_curve_registry(keys)?;
+        if let Some(ref entries) = self.plain_registry {
+            let request = AuthRequest::SetPlainRegistry(entries.to_owned());
+            client.request_domain(domain, &request)?;
+        }
+        if let Some(ref entries) = self.curve_registry {
+            let request = AuthRequest::SetCurveRegistry(entries.to_owned());
+            client.request_domain(domain, &request)?;
         }
         if let Some(enabled) = self.curve_auth {
-            client.set_curve_auth(enabled).map_err(Error::cast)?;
+            let request = AuthRequest::SetCurveAuth(enabled);
+            client.request_domain(domain, &request)?;
+        }
+        if let Some(ref policy) = self.ban_policy {
+            check_ban_policy(policy)?;
+            let request = AuthRequest::SetBanPolicy(Some(policy.to_owned()));
+            client.request_domain(domain, &request)?;
         }
 
         Ok(())
     }
 
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+
+    pub fn set_domain<S>(&mut self, maybe: Option<S>)
+    where
+        S: Into<String>,
+    {
+        self.domain = maybe.map(S::into);
+    }
+
+    pub fn blacklist(&self) -> Option<&[Subnet]> {
+        self.blacklist.as_deref()
+    }
+
     pub fn set_blacklist<I>(&mut self, maybe: Option<I>)
     where
-        I: IntoIpAddrs,
+        I: IntoSubnets,
     {
-        let maybe: Option<Vec<IpAddr>> =
-            maybe.map(|i| i.into_ip_addrs().collect());
+        let maybe: Option<Vec<Subnet>> =
+            maybe.map(|i| i.into_subnets().collect());
         self.blacklist = maybe;
     }
 
+    pub fn whitelist(&self) -> Option<&[Subnet]> {
+        self.whitelist.as_deref()
+    }
+
     pub fn set_whitelist<I>(&mut self, maybe: Option<I>)
     where
-        I: IntoIpAddrs,
+        I: IntoSubnets,
     {
-        let maybe: Option<Vec<IpAddr>> =
-            maybe.map(|i| i.into_ip_addrs().collect());
+        let maybe: Option<Vec<Subnet>> =
+            maybe.map(|i| i.into_subnets().collect());
         self.whitelist = maybe;
     }
 
+    pub fn hash_params(&self) -> Option<HashParams> {
+        self.hash_params
+    }
+
+    pub fn set_hash_params(&mut self, maybe: Option<HashParams>) {
+        self.hash_params = maybe;
+    }
+
+    pub fn plain_registry(&self) -> Option<&[PlainRegistryEntry]> {
+        self.plain_registry.as_deref()
+    }
+
     pub fn set_plain_registry<I, E>(&mut self, maybe: Option<I>)
     where
         I: IntoIterator<Item = E>,
-        E: Into<PlainClientCreds>,
+        E: Into<PlainRegistryEntry>,
     {
-        let maybe: Option<Vec<PlainClientCreds>> =
+        let maybe: Option<Vec<PlainRegistryEntry>> =
             maybe.map(|e| e.into_iter().map(E::into).collect());
         self.plain_registry = maybe;
     }
 
+    pub fn curve_registry(&self) -> Option<&[CurveRegistryEntry]> {
+        self.curve_registry.as_deref()
+    }
+
     pub fn set_curve_registry<I, E>(&mut self, maybe: Option<I>)
     where
         I: IntoIterator<Item = E>,
-        E: Into<CurvePublicKey>,
+        E: Into<CurveRegistryEntry>,
     {
-        let maybe: Option<Vec<CurvePublicKey>> =
+        let maybe: Option<Vec<CurveRegistryEntry>> =
             maybe.map(|e| e.into_iter().map(E::into).collect());
         self.curve_registry = maybe;
     }
 
+    pub fn curve_auth(&self) -> Option<bool> {
+        self.curve_auth
+    }
+
     pub fn set_curve_auth(&mut self, maybe: Option<bool>) {
         self.curve_auth = maybe;
     }
+
+    pub fn ban_policy(&self) -> Option<&BanPolicy> {
+        self.ban_policy.as_ref()
+    }
+
+    pub fn set_ban_policy(&mut self, maybe: Option<BanPolicy>) {
+        self.ban_policy = maybe;
+    }
 }
 
 /// A builder for a `AuthClient`.
 ///
 /// Creates a `AuthClient` and sends the configuration to the associated
 /// `AuthServer`.
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
 pub struct AuthBuilder {
     inner: AuthConfig,
 }
@@ -455,9 +931,18 @@ impl AuthBuilder {
         self.inner.with_ctx(handle)
     }
 
+    /// Set the `ZAP` domain that the configuration applies to.
+    pub fn domain<S>(&mut self, domain: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.inner.set_domain(Some(domain));
+        self
+    }
+
     pub fn blacklist<I>(&mut self, ips: I) -> &mut Self
     where
-        I: IntoIpAddrs,
+        I: IntoSubnets,
     {
         self.inner.set_blacklist(Some(ips));
         self
@@ -465,16 +950,25 @@ impl AuthBuilder {
 
     pub fn whitelist<I>(&mut self, ips: I) -> &mut Self
     where
-        I: IntoIpAddrs,
+        I: IntoSubnets,
     {
         self.inner.set_whitelist(Some(ips));
         self
     }
 
+    /// Set the parameters used to hash the cleartext passwords of the
+    /// `PLAIN` registry. See [`HashParams`].
+    ///
+    /// [`HashParams`]: struct.HashParams.html
+    pub fn hash_params(&mut self, params: HashParams) -> &mut Self {
+        self.inner.set_hash_params(Some(params));
+        self
+    }
+
     pub fn plain_registry<I, E>(&mut self, iter: I) -> &mut Self
     where
         I: IntoIterator<Item = E>,
-        E: Into<PlainClientCreds>,
+        E: Into<PlainRegistryEntry>,
     {
         self.inner.set_plain_registry(Some(iter));
         self
@@ -483,7 +977,7 @@ impl AuthBuilder {
     pub fn curve_registry<I, E>(&mut self, keys: I) -> &mut Self
     where
         I: IntoIterator<Item = E>,
-        E: Into<CurvePublicKey>,
+        E: Into<CurveRegistryEntry>,
     {
         self.inner.set_curve_registry(Some(keys));
         self
@@ -493,6 +987,15 @@ impl AuthBuilder {
         self.inner.set_curve_auth(Some(false));
         self
     }
+
+    /// Temporarily ban the addresses of peers that repeatedly fail to
+    /// authenticate. See [`BanPolicy`].
+    ///
+    /// [`BanPolicy`]: struct.BanPolicy.html
+    pub fn ban_policy(&mut self, policy: BanPolicy) -> &mut Self {
+        self.inner.set_ban_policy(Some(policy));
+        self
+    }
 }
 
 #[cfg(test)]
@@ -556,6 +1059,377 @@ mod test {
         server.recv_msg().unwrap();
     }
 
+    #[test]
+    fn test_subnet_precedence() {
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(handle)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap();
+
+        // The more specific blacklisted network wins.
+        let net: Subnet = "127.0.0.0/8".try_into().unwrap();
+        let ip: Subnet = "127.0.0.1".try_into().unwrap();
+        let auth = AuthBuilder::new()
+            .whitelist(net)
+            .blacklist(ip)
+            .with_ctx(handle)
+            .unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .with_ctx(handle)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap_err();
+
+        // The more specific whitelisted network wins.
+        auth.set_whitelist(ip).unwrap();
+        auth.set_blacklist(net).unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .with_ctx(handle)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap();
+    }
+
+    #[test]
+    fn test_domain() {
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let creds = PlainClientCreds::new("user", "pwd");
+        let _ = AuthBuilder::new()
+            .domain("internal")
+            .hash_params(HashParams::new(8, 1, 1).unwrap())
+            .plain_registry(&creds)
+            .with_ctx(handle)
+            .unwrap();
+
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let _ = AuthBuilder::new()
+            .domain("public")
+            .blacklist(ip)
+            .with_ctx(handle)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let internal = ServerBuilder::new()
+            .bind(&addr)
+            .zap_domain("internal")
+            .mechanism(Mechanism::PlainServer)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(handle)
+            .unwrap();
+
+        let public = ServerBuilder::new()
+            .bind(&addr)
+            .zap_domain("public")
+            .mechanism(Mechanism::PlainServer)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(handle)
+            .unwrap();
+
+        // The global domain has an empty registry.
+        let global = ServerBuilder::new()
+            .bind(&addr)
+            .mechanism(Mechanism::PlainServer)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(handle)
+            .unwrap();
+
+        for (server, allowed) in
+            &[(internal, true), (public, false), (global, false)]
+        {
+            let bound = server.last_endpoint().unwrap();
+            let client = ClientBuilder::new()
+                .connect(bound)
+                .mechanism(&creds)
+                .with_ctx(handle)
+                .unwrap();
+
+            client.try_send("").unwrap();
+            assert_eq!(server.recv_msg().is_ok(), *allowed);
+        }
+    }
+
+    #[test]
+    fn test_ban() {
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let monitor = AuthMonitor::with_ctx(handle).unwrap();
+        monitor
+            .set_recv_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let creds = PlainClientCreds::new("user", "pwd");
+        let policy =
+            BanPolicy::new(2, Duration::from_secs(60), Duration::from_secs(60));
+        let auth = AuthBuilder::new()
+            .hash_params(HashParams::new(8, 1, 1).unwrap())
+            .plain_registry(&creds)
+            .ban_policy(policy)
+            .with_ctx(handle)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .mechanism(Mechanism::PlainServer)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(handle)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap();
+
+        let bad_creds = PlainClientCreds::new("user", "nope");
+        let reasons = vec![
+            DenyReason::BadPassword,
+            DenyReason::BadPassword,
+            DenyReason::Banned,
+        ];
+
+        for reason in reasons {
+            let _client = ClientBuilder::new()
+                .connect(&bound)
+                .mechanism(&bad_creds)
+                .with_ctx(handle)
+                .unwrap();
+
+            let event = monitor.recv_event().unwrap();
+            assert_eq!(event.reason(), Some(reason));
+        }
+
+        let bans = auth.bans().unwrap();
+        assert_eq!(bans.len(), 1);
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        assert_eq!(bans[0].addr(), ip);
+
+        // Even valid credentials are denied.
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .mechanism(&creds)
+            .with_ctx(handle)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap_err();
+        drop(client);
+
+        auth.clear_bans().unwrap();
+        assert!(auth.bans().unwrap().is_empty());
+
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .mechanism(&creds)
+            .with_ctx(handle)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap();
+    }
+
+    #[test]
+    fn test_query() {
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let auth = AuthClient::with_ctx(handle).unwrap();
+        assert!(auth.blacklist().unwrap().is_empty());
+        assert!(auth.whitelist().unwrap().is_empty());
+        assert!(auth.plain_registry().unwrap().is_empty());
+        assert!(auth.curve_registry().unwrap().is_empty());
+        assert!(auth.curve_auth().unwrap());
+        assert_eq!(auth.hash_params().unwrap(), HashParams::default());
+
+        let blacklist: Vec<Subnet> = vec![
+            "10.1.0.0/16".try_into().unwrap(),
+            "10.0.0.1/32".try_into().unwrap(),
+        ];
+        let whitelist: Subnet = "10.0.0.0/8".try_into().unwrap();
+        let bob = PlainRegistryEntry::new(PlainClientCreds::new("bob", "b"))
+            .with_user_id("bob@tenant");
+        let alice =
+            PlainRegistryEntry::new(PlainClientCreds::new("alice", "a"));
+        let cert = CurveCert::new_unique();
+        let key = CurveRegistryEntry::new(cert.public()).add_metadata("k", "v");
+        let policy =
+            BanPolicy::new(3, Duration::from_secs(10), Duration::from_secs(60));
+
+        let mut auth = AuthBuilder::new()
+            .domain("internal")
+            .blacklist(&blacklist)
+            .whitelist(whitelist)
+            .hash_params(HashParams::new(8, 1, 1).unwrap())
+            .plain_registry(vec![&bob, &alice])
+            .curve_registry(&key)
+            .no_curve_auth()
+            .ban_policy(policy.clone())
+            .with_ctx(handle)
+            .unwrap();
+
+        // The entries are sorted.
+        assert_eq!(auth.blacklist().unwrap(), vec![blacklist[1], blacklist[0]]);
+        assert_eq!(auth.whitelist().unwrap(), vec![whitelist]);
+        let registry = auth.plain_registry().unwrap();
+        let users: Vec<&str> = registry.iter().map(|e| e.username()).collect();
+        assert_eq!(users, vec!["alice", "bob"]);
+        assert_eq!(registry[1].user_id(), "bob@tenant");
+        // The passwords are hashed.
+        for (entry, password) in registry.iter().zip(&["a", "b"]) {
+            match entry.password() {
+                PlainPassword::Hash(hash) => assert!(hash.verify(password)),
+                PlainPassword::Clear(_) => panic!("cleartext password"),
+            }
+        }
+        assert_eq!(auth.curve_registry().unwrap(), vec![key.clone()]);
+        assert!(!auth.curve_auth().unwrap());
+
+        let config = auth.config().unwrap();
+        assert_eq!(config.domain(), Some("internal"));
+        assert_eq!(config.whitelist(), Some(&[whitelist][..]));
+        assert_eq!(
+            config.hash_params(),
+            Some(HashParams::new(8, 1, 1).unwrap())
+        );
+        assert_eq!(config.plain_registry(), Some(&registry[..]));
+        assert_eq!(config.curve_registry(), Some(&[key][..]));
+        assert_eq!(config.curve_auth(), Some(false));
+        assert_eq!(config.ban_policy(), Some(&policy));
+
+        // Other domains are unaffected.
+        auth.set_domain("global");
+        assert!(auth.plain_registry().unwrap().is_empty());
+        assert_eq!(auth.config().unwrap().ban_policy(), None);
+
+        // The snapshot restores the same configuration.
+        let other = Ctx::new();
+        let restored = config.with_ctx(other.handle()).unwrap();
+        assert_eq!(restored.domain(), "internal");
+        assert_eq!(restored.config().unwrap(), config);
+    }
+
+    #[test]
+    fn test_plain_metadata() {
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let creds = PlainClientCreds::new("user", "pwd");
+        let entry = PlainRegistryEntry::new(&creds)
+            .with_user_id("user@tenant")
+            .add_metadata("Tenant", "tenant")
+            .add_metadata("Role", "admin");
+        let _ = AuthBuilder::new()
+            .hash_params(HashParams::new(8, 1, 1).unwrap())
+            .plain_registry(entry)
+            .with_ctx(handle)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .mechanism(Mechanism::PlainServer)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(handle)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(bound)
+            .mechanism(creds)
+            .with_ctx(handle)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.user_id(), Some("user@tenant"));
+        assert_eq!(msg.property("Tenant"), Some("tenant"));
+        assert_eq!(msg.property("Role"), Some("admin"));
+    }
+
+    #[test]
+    fn test_ser_de() {
+        let mut config = AuthConfig::new();
+        let net: Subnet = "10.0.0.0/8".try_into().unwrap();
+        config.set_domain(Some("internal"));
+        config.set_whitelist(Some(net));
+        let ip: IpAddr = "10.1.2.3".parse().unwrap();
+        config.set_blacklist(Some(ip));
+
+        let creds = PlainClientCreds::new("user", "pwd");
+        let params = HashParams::new(8, 1, 1).unwrap();
+        let hash = PasswordHash::with_params("pwd", params);
+        config.set_plain_registry(Some(vec![
+            PlainRegistryEntry::new(&creds),
+            PlainRegistryEntry::new(creds).add_metadata("Role", "admin"),
+            PlainRegistryEntry::with_hash("other", hash),
+        ]));
+        config.set_hash_params(Some(params));
+        let cert = CurveCert::new_unique();
+        config.set_curve_registry(Some(vec![
+            CurveRegistryEntry::new(cert.public()),
+            CurveRegistryEntry::new(cert.public()).with_user_id("user"),
+        ]));
+        config.set_ban_policy(Some(BanPolicy::new(
+            5,
+            Duration::from_secs(60),
+            Duration::from_secs(3600),
+        )));
+
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        let de: AuthConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(config, de);
+
+        // Bare addresses are still accepted.
+        let yaml = "whitelist:\n  - 127.0.0.1\n  - \"::1\"\n";
+        let de: AuthConfig = serde_yaml::from_str(yaml).unwrap();
+        let mut config = AuthConfig::new();
+        let ips: Vec<IpAddr> =
+            vec!["127.0.0.1".parse().unwrap(), "::1".parse().unwrap()];
+        config.set_whitelist(Some(ips));
+        assert_eq!(config, de);
+
+        // Plain credentials and bare public keys are still accepted.
+        let yaml = format!(
+            "plain_registry:\n  - username: user\n    password: pwd\n\
+             curve_registry:\n  - \"{}\"\n",
+            cert.public()
+        );
+        let de: AuthConfig = serde_yaml::from_str(&yaml).unwrap();
+        let mut config = AuthConfig::new();
+        config.set_plain_registry(Some(PlainClientCreds::new("user", "pwd")));
+        config.set_curve_registry(Some(cert.public()));
+        assert_eq!(config, de);
+
+        let request =
+            AuthRequest::SetCurveRegistry(vec![CurveRegistryEntry::new(
+                cert.public(),
+            )
+            .add_metadata("Role", "admin")]);
+        let ser = bincode::serialize(&request).unwrap();
+        let de: AuthRequest = bincode::deserialize(&ser).unwrap();
+        assert_eq!(request, de);
+    }
+
     #[test]
     fn test_null() {
         let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
@@ -576,18 +1450,27 @@ mod test {
 
     #[test]
     fn test_plain_denied() {
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let _ = AuthBuilder::new()
+            .hash_params(HashParams::new(8, 1, 1).unwrap())
+            .with_ctx(handle)
+            .unwrap();
+
         let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
 
         let server = ServerBuilder::new()
             .bind(&addr)
             .mechanism(Mechanism::PlainServer)
             .recv_timeout(Duration::from_millis(200))
-            .build()
+            .with_ctx(handle)
             .unwrap();
 
         let bound = server.last_endpoint().unwrap();
 
-        let client = Client::new().unwrap();
+        let client = Client::with_ctx(handle).unwrap();
 
         let creds = PlainClientCreds::new("user", "pwd");
         client.set_mechanism(Mechanism::PlainClient(creds)).unwrap();
@@ -597,6 +1480,67 @@ mod test {
         server.recv_msg().unwrap_err();
     }
 
+    #[test]
+    fn test_plain_invalid_utf8() {
+        use crate::core::{
+            sockopt::{setsockopt_bytes, SocketOption},
+            GetRawSocket,
+        };
+
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let monitor = AuthMonitor::with_ctx(handle).unwrap();
+        monitor
+            .set_recv_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let creds = PlainClientCreds::new("user", "pwd");
+        let _ = AuthBuilder::new()
+            .hash_params(HashParams::new(8, 1, 1).unwrap())
+            .plain_registry(&creds)
+            .with_ctx(handle)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .mechanism(Mechanism::PlainServer)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(handle)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap();
+
+        // The password is not valid UTF-8.
+        let client = Client::with_ctx(handle).unwrap();
+        client
+            .set_mechanism(Mechanism::PlainClient(creds.clone()))
+            .unwrap();
+        setsockopt_bytes(
+            client.raw_socket().as_mut_ptr(),
+            SocketOption::PlainPassword,
+            Some(&[0xff, 0xfe]),
+        )
+        .unwrap();
+        client.connect(&bound).unwrap();
+
+        let event = monitor.recv_event().unwrap();
+        assert_eq!(event.reason(), Some(DenyReason::InvalidCredentials));
+        drop(client);
+
+        // The `AuthServer` still handles the following handshakes.
+        let client = ClientBuilder::new()
+            .connect(bound)
+            .mechanism(creds)
+            .with_ctx(handle)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap();
+    }
+
     #[test]
     fn test_plain() {
         // Create a new context to use a disctinct auth handler.
@@ -605,6 +1549,7 @@ mod test {
 
         let creds = PlainClientCreds::new("user", "pwd");
         let _ = AuthBuilder::new()
+            .hash_params(HashParams::new(8, 1, 1).unwrap())
             .plain_registry(&creds)
             .with_ctx(handle)
             .unwrap();
@@ -673,6 +1618,50 @@ mod test {
         server.recv_msg().unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "curve")]
+    fn test_curve_metadata() {
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let server_cert = CurveCert::new_unique();
+        let client_cert = CurveCert::new_unique();
+
+        let entry = CurveRegistryEntry::new(client_cert.public())
+            .add_metadata("Role", "worker");
+        let _ = AuthBuilder::new()
+            .curve_registry(entry)
+            .with_ctx(handle)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .mechanism(CurveServerCreds::new(server_cert.secret()))
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(handle)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap();
+
+        let public = client_cert.public().to_owned();
+        let client_creds =
+            CurveClientCreds::new(server_cert.public()).add_cert(client_cert);
+
+        let client = ClientBuilder::new()
+            .mechanism(client_creds)
+            .connect(bound)
+            .with_ctx(handle)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.user_id(), Some(public.as_str()));
+        assert_eq!(msg.property("Role"), Some("worker"));
+    }
+
     #[test]
     #[cfg(feature = "curve")]
     fn test_curve_denied() {
@@ -734,4 +1723,133 @@ mod test {
         client.try_send("").unwrap();
         server.recv_msg().unwrap();
     }
+
+    #[test]
+    fn test_authenticator() {
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let auth = AuthClient::with_ctx(handle).unwrap();
+        auth.set_authenticator(
+            |request: &ZapRequest| match request.credentials() {
+                Credentials::Plain(creds) if creds.password() == "pwd" => {
+                    Verdict::allow()
+                        .with_user_id(creds.username())
+                        .add_metadata("Role", "admin")
+                }
+                _ => Verdict::deny(),
+            },
+            2,
+        )
+        .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .mechanism(Mechanism::PlainServer)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(handle)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .mechanism(PlainClientCreds::new("user", "pwd"))
+            .with_ctx(handle)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.user_id(), Some("user"));
+        assert_eq!(msg.property("Role"), Some("admin"));
+
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .mechanism(PlainClientCreds::new("user", "nope"))
+            .with_ctx(handle)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap_err();
+
+        // Fallback to the empty plain registry.
+        auth.clear_authenticator().unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .mechanism(PlainClientCreds::new("user", "pwd"))
+            .with_ctx(handle)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap_err();
+    }
+
+    #[test]
+    fn test_run_authenticator() {
+        use std::thread;
+
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let worker = thread::spawn(move || {
+            let auth = AuthClient::with_ctx(handle).unwrap();
+            auth.run_authenticator(|request: &ZapRequest| {
+                assert_eq!(request.addr(), Some("127.0.0.1".parse().unwrap()));
+                Verdict::allow().with_user_id("null")
+            })
+        });
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(handle)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap();
+
+        // Poll until the worker is registered, since the user id is only
+        // set by the authenticator.
+        let mut registered = false;
+        for _ in 0..100 {
+            let client = ClientBuilder::new()
+                .connect(&bound)
+                .with_ctx(handle)
+                .unwrap();
+
+            client.try_send("").unwrap();
+            let msg = server.recv_msg().unwrap();
+            if msg.user_id() == Some("null") {
+                registered = true;
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(registered);
+
+        let auth = AuthClient::with_ctx(handle).unwrap();
+        auth.clear_authenticator().unwrap();
+        worker.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_zero_workers() {
+        let ctx = Ctx::new();
+        let auth = AuthClient::with_ctx(ctx.handle()).unwrap();
+
+        let err = auth
+            .set_authenticator(|_: &ZapRequest| Verdict::allow(), 0)
+            .unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput(_) => (),
+            _ => panic!("unexpected error kind"),
+        }
+    }
 }
@@ -1,36 +1,18 @@
-// The z85 codec logic is largely based on https://github.com/decafbad/z85
-use super::Mechanism;
-use crate::prelude::TryFrom;
+#[cfg(feature = "curve")]
+use super::encrypted::EncryptedSecretConfig;
+use super::{secret, Mechanism};
+use crate::{
+    prelude::TryFrom,
+    z85::{self, Z85Error},
+};
 
 use libzmq_sys as sys;
 
-use byteorder::{BigEndian, ByteOrder};
 use failure::Fail;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
 
-use std::{ffi::CString, fmt, option, os::raw::c_char};
-
-static LETTERS: [u8; 85] = [
-    0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x61, 0x62,
-    0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x6B, 0x6C, 0x6D, 0x6E,
-    0x6F, 0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A,
-    0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x4B, 0x4C,
-    0x4D, 0x4E, 0x4F, 0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58,
-    0x59, 0x5A, 0x2E, 0x2D, 0x3A, 0x2B, 0x3D, 0x5E, 0x21, 0x2F, 0x2A, 0x3F,
-    0x26, 0x3C, 0x3E, 0x28, 0x29, 0x5B, 0x5D, 0x7B, 0x7D, 0x40, 0x25, 0x24,
-    0x23,
-];
-
-static OCTETS: [u8; 96] = [
-    0xFF, 0x44, 0xFF, 0x54, 0x53, 0x52, 0x48, 0xFF, 0x4B, 0x4C, 0x46, 0x41,
-    0xFF, 0x3F, 0x3E, 0x45, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
-    0x08, 0x09, 0x40, 0xFF, 0x49, 0x42, 0x4A, 0x47, 0x51, 0x24, 0x25, 0x26,
-    0x27, 0x28, 0x29, 0x2A, 0x2B, 0x2C, 0x2D, 0x2E, 0x2F, 0x30, 0x31, 0x32,
-    0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x4D,
-    0xFF, 0x4E, 0x43, 0xFF, 0xFF, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x10,
-    0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x1B, 0x1C,
-    0x1D, 0x1E, 0x1F, 0x20, 0x21, 0x22, 0x23, 0x4F, 0xFF, 0x50, 0xFF, 0xFF,
-];
+use std::{collections::BTreeMap, ffi::CString, fmt, option, os::raw::c_char};
 
 // The size of a curve key in the z85 format.
 const CURVE_CURVE_KEY_SIZE: usize = 40;
@@ -45,79 +27,8 @@ pub enum CurveError {
         byte, pos
     )]
     InvalidByte { pos: usize, byte: u8 },
-}
-
-fn z85_encode_chunk(input: &[u8]) -> [u8; 5] {
-    let mut num = BigEndian::read_u32(input) as usize;
-    let mut out = [0_u8; 5];
-
-    for i in (0..5).rev() {
-        out[i] = LETTERS[num % 85];
-        num /= 85;
-    }
-
-    out
-}
-
-fn z85_encode(input: &[u8]) -> Result<String, CurveError> {
-    let len = input.len();
-    if len % 4 != 0 {
-        panic!("input lenght must be div by 4");
-    }
-
-    let mut out = Vec::with_capacity(len / 4 * 5);
-    for chunk in input.chunks(4) {
-        out.extend_from_slice(&z85_encode_chunk(chunk));
-    }
-
-    unsafe { Ok(String::from_utf8_unchecked(out)) }
-}
-
-fn z85_decode_chunk(input: &[u8]) -> Result<[u8; 4], usize> {
-    let mut num: u32 = 0;
-
-    for (i, &byte) in input.iter().enumerate().take(5) {
-        num *= 85;
-
-        if byte < 0x20 || 0x7F < byte {
-            return Err(i);
-        }
-
-        let b = OCTETS[byte as usize - 32];
-        if b == 0xFF {
-            return Err(i);
-        }
-
-        num += u32::from(b);
-    }
-
-    let mut out = [0_u8; 4];
-    BigEndian::write_u32(&mut out, num);
-
-    Ok(out)
-}
-
-fn z85_decode(input: &str) -> Result<Vec<u8>, CurveError> {
-    let input = input.as_bytes();
-    let len = input.len();
-    if len % 5 != 0 {
-        panic!("input length must be div by 5");
-    }
-
-    let mut out = Vec::with_capacity(len / 5 * 4);
-    for (i, chunk) in input.chunks(5).enumerate() {
-        match z85_decode_chunk(chunk) {
-            Err(pos) => {
-                return Err(CurveError::InvalidByte {
-                    pos: i * 5 + pos,
-                    byte: chunk[pos],
-                });
-            }
-            Ok(out_chunk) => out.extend_from_slice(&out_chunk),
-        }
-    }
-
-    Ok(out)
+    #[fail(display = "input string chunk at offset {} overflows", pos)]
+    Overflow { pos: usize },
 }
 
 /// A public `CURVE` cryptographic key in the printable [`Z85`] representation.
@@ -262,9 +173,14 @@ impl<'a> IntoIterator for &'a CurvePublicKey {
 ///
 /// Can be generated by [`CurveCert::new_unique()`].
 ///
+/// The key is wiped from memory when dropped and is redacted from the
+/// `Debug` output. With the `mlock` feature, the memory of the key is also
+/// locked so that it is never swapped to disk nor, on Linux, included in
+/// core dumps.
+///
 /// [`Z85`]: https://rfc.zeromq.org/spec:32/Z85/
 /// [`CurveCert::new_unique()`]: struct.CurveCert.html#method.new_unique
-#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Hash, Serialize)]
 #[serde(transparent)]
 pub struct CurveSecretKey {
     inner: CurveKey,
@@ -278,7 +194,12 @@ impl CurveSecretKey {
     {
         let inner = CurveKey::new(text)?;
 
-        Ok(Self { inner })
+        Ok(Self::from_inner(inner))
+    }
+
+    fn from_inner(inner: CurveKey) -> Self {
+        secret::lock(inner.as_str());
+        Self { inner }
     }
 
     /// Returns the key in `Z85` encoded string.
@@ -289,15 +210,79 @@ impl CurveSecretKey {
 
 impl fmt::Debug for CurveSecretKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("CurveServerKey")
-            .field("key", &self.as_str())
+        f.debug_struct("CurveSecretKey")
+            .field("key", &secret::REDACTED)
             .finish()
     }
 }
 
+impl Clone for CurveSecretKey {
+    fn clone(&self) -> Self {
+        Self::from_inner(self.inner.clone())
+    }
+}
+
+impl Drop for CurveSecretKey {
+    fn drop(&mut self) {
+        secret::wipe(&mut self.inner.text);
+    }
+}
+
+impl<'de> Deserialize<'de> for CurveSecretKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct SecretVisitor;
+
+        impl<'de> de::Visitor<'de> for SecretVisitor {
+            type Value = CurveSecretKey;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a Z85 key or an encrypted key")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                CurveSecretKey::new(value).map_err(de::Error::custom)
+            }
+
+            #[cfg(feature = "curve")]
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                let config = EncryptedSecretConfig::deserialize(
+                    de::value::MapAccessDeserializer::new(map),
+                )?;
+                config.decrypt().map_err(de::Error::custom)
+            }
+
+            #[cfg(not(feature = "curve"))]
+            fn visit_map<A>(self, _map: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                Err(de::Error::custom(
+                    "encrypted keys require enabling feature flag 'curve'",
+                ))
+            }
+        }
+
+        // Encrypted keys are only used in configuration files.
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(SecretVisitor)
+        } else {
+            CurveKey::deserialize(deserializer).map(Self::from_inner)
+        }
+    }
+}
+
 impl From<CurveSecretKey> for CurveKey {
-    fn from(public: CurveSecretKey) -> Self {
-        public.inner
+    fn from(secret: CurveSecretKey) -> Self {
+        secret.inner.clone()
     }
 }
 
@@ -311,7 +296,7 @@ impl From<BinCurveKey> for CurveSecretKey {
     fn from(key: BinCurveKey) -> Self {
         let inner: CurveKey = key.into();
 
-        Self { inner }
+        Self::from_inner(inner)
     }
 }
 
@@ -319,7 +304,7 @@ impl<'a> From<&'a BinCurveKey> for CurveSecretKey {
     fn from(key: &'a BinCurveKey) -> Self {
         let inner: CurveKey = key.into();
 
-        Self { inner }
+        Self::from_inner(inner)
     }
 }
 
@@ -376,12 +361,15 @@ impl CurveKey {
             return Err(CurveError::InvalidSize);
         }
 
-        let bytes = text.as_bytes();
-
-        for (pos, &byte) in bytes.iter().enumerate() {
-            if !LETTERS.contains(&byte) {
-                return Err(CurveError::InvalidByte { pos, byte });
+        match z85::decode(&text) {
+            Ok(mut bytes) => secret::wipe_bytes(&mut bytes),
+            Err(Z85Error::InvalidByte { pos, byte }) => {
+                return Err(CurveError::InvalidByte { pos, byte })
             }
+            Err(Z85Error::Overflow { pos }) => {
+                return Err(CurveError::Overflow { pos })
+            }
+            Err(Z85Error::InvalidLength) => unreachable!(),
         }
 
         Ok(Self { text })
@@ -395,7 +383,9 @@ impl CurveKey {
         let public = unsafe {
             CString::from_vec_unchecked(vec![0u8; CURVE_CURVE_KEY_SIZE])
         };
-        let secret = unsafe { CString::from_vec_unchecked(secret.text.into()) };
+        let secret = unsafe {
+            CString::from_vec_unchecked(secret.as_str().as_bytes().to_vec())
+        };
 
         let rc = unsafe {
             sys::zmq_curve_public(
@@ -403,6 +393,7 @@ impl CurveKey {
                 secret.as_ptr() as *mut c_char,
             )
         };
+        secret::wipe_bytes(&mut secret.into_bytes());
 
         assert_eq!(rc, 0, "curve not supported");
 
@@ -416,6 +407,13 @@ impl CurveKey {
     }
 }
 
+// Public keys are also wiped since the same type is used for secret keys.
+impl Drop for CurveKey {
+    fn drop(&mut self) {
+        self.text.zeroize();
+    }
+}
+
 impl<'a> From<&'a CurveKey> for CurveKey {
     fn from(key: &'a CurveKey) -> Self {
         key.to_owned()
@@ -445,7 +443,7 @@ impl<'a> TryFrom<&'a String> for CurveKey {
 
 impl From<BinCurveKey> for CurveKey {
     fn from(key: BinCurveKey) -> Self {
-        let text = z85_encode(key.as_bytes()).unwrap();
+        let text = z85::encode(key.as_bytes());
 
         // No need to validate.
         Self { text }
@@ -454,7 +452,7 @@ impl From<BinCurveKey> for CurveKey {
 
 impl<'a> From<&'a BinCurveKey> for CurveKey {
     fn from(key: &'a BinCurveKey) -> Self {
-        let text = z85_encode(key.as_bytes()).unwrap();
+        let text = z85::encode(key.as_bytes());
 
         // No need to validate.
         Self { text }
@@ -506,6 +504,9 @@ impl<'a> IntoIterator for &'a CurveKey {
 
 /// A `CURVE` certificate containing a public and secret `CurveKey`.
 ///
+/// A certificate can also hold metadata properties, which are stored
+/// alongside the keys when it is saved to disk in the `zcert` format.
+///
 /// # Example
 /// ```
 /// use libzmq::auth::CurveCert;
@@ -515,8 +516,10 @@ impl<'a> IntoIterator for &'a CurveKey {
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CurveCert {
-    public: CurvePublicKey,
-    secret: CurveSecretKey,
+    pub(crate) public: CurvePublicKey,
+    pub(crate) secret: CurveSecretKey,
+    #[serde(default)]
+    pub(crate) metadata: BTreeMap<String, String>,
 }
 
 impl CurveCert {
@@ -547,10 +550,14 @@ impl CurveCert {
             let inner = CurveKey {
                 text: secret.into_string().unwrap(),
             };
-            CurveSecretKey { inner }
+            CurveSecretKey::from_inner(inner)
         };
 
-        Self { public, secret }
+        Self {
+            public,
+            secret,
+            metadata: BTreeMap::new(),
+        }
     }
 
     /// Returns a reference to the certificate's public key.
@@ -562,15 +569,44 @@ impl CurveCert {
     pub fn secret(&self) -> &CurveSecretKey {
         &self.secret
     }
+
+    /// Add a metadata property to the certificate.
+    pub fn add_metadata<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.metadata.insert(name.into(), value.into());
+        self
+    }
+
+    /// Returns the metadata properties of the certificate.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
 }
 
 // Binary representation of the `CURVE` key. This is what is sent
 // down the wire.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub(crate) struct BinCurveKey {
     bytes: Vec<u8>,
 }
 
+impl fmt::Debug for BinCurveKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BinCurveKey")
+            .field("bytes", &secret::REDACTED)
+            .finish()
+    }
+}
+
+impl Drop for BinCurveKey {
+    fn drop(&mut self) {
+        secret::wipe_bytes(&mut self.bytes);
+    }
+}
+
 impl BinCurveKey {
     pub(crate) fn new_unchecked(bytes: Vec<u8>) -> Self {
         BinCurveKey { bytes }
@@ -583,7 +619,7 @@ impl BinCurveKey {
 
 impl From<CurveKey> for BinCurveKey {
     fn from(key: CurveKey) -> Self {
-        let bytes = z85_decode(key.as_str()).unwrap();
+        let bytes = z85::decode(key.as_str()).unwrap();
 
         BinCurveKey { bytes }
     }
@@ -591,7 +627,7 @@ impl From<CurveKey> for BinCurveKey {
 
 impl<'a> From<&'a CurveKey> for BinCurveKey {
     fn from(key: &'a CurveKey) -> Self {
-        let bytes = z85_decode(key.as_str()).unwrap();
+        let bytes = z85::decode(key.as_str()).unwrap();
 
         BinCurveKey { bytes }
     }
@@ -599,7 +635,7 @@ impl<'a> From<&'a CurveKey> for BinCurveKey {
 
 impl From<CurvePublicKey> for BinCurveKey {
     fn from(key: CurvePublicKey) -> Self {
-        let bytes = z85_decode(key.as_str()).unwrap();
+        let bytes = z85::decode(key.as_str()).unwrap();
 
         BinCurveKey { bytes }
     }
@@ -607,7 +643,7 @@ impl From<CurvePublicKey> for BinCurveKey {
 
 impl<'a> From<&'a CurvePublicKey> for BinCurveKey {
     fn from(key: &'a CurvePublicKey) -> Self {
-        let bytes = z85_decode(key.as_str()).unwrap();
+        let bytes = z85::decode(key.as_str()).unwrap();
 
         BinCurveKey { bytes }
     }
@@ -615,7 +651,7 @@ impl<'a> From<&'a CurvePublicKey> for BinCurveKey {
 
 impl From<CurveSecretKey> for BinCurveKey {
     fn from(key: CurveSecretKey) -> Self {
-        let bytes = z85_decode(key.as_str()).unwrap();
+        let bytes = z85::decode(key.as_str()).unwrap();
 
         BinCurveKey { bytes }
     }
@@ -623,7 +659,7 @@ impl From<CurveSecretKey> for BinCurveKey {
 
 impl<'a> From<&'a CurveSecretKey> for BinCurveKey {
     fn from(key: &'a CurveSecretKey) -> Self {
-        let bytes = z85_decode(key.as_str()).unwrap();
+        let bytes = z85::decode(key.as_str()).unwrap();
 
         BinCurveKey { bytes }
     }
@@ -750,10 +786,9 @@ impl From<CurveServerCreds> for Mechanism {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use quickcheck::quickcheck;
+    use crate::auth::{PlainClientCreds, PlainRegistryEntry};
 
     const Z85_RFC: &str = "HelloWorld";
-    const BIN_RFC: [u8; 8] = [0x86, 0x4F, 0xD2, 0x6F, 0xB5, 0x59, 0xF7, 0x5B];
     const CURVE_KEY_INVALID_BYTE: &str =
         "AAAAAAAAAAAAAAAAAAAA~AAAAAAAAAAAAAAAAAAA";
     const CURVE_KEY_SECRET: &str = "sqe2ZQ%<<?*(MV2Shf%9=CtldI@T^^pgrML1S.F/";
@@ -795,69 +830,24 @@ mod tests {
     }
 
     #[test]
-    fn z85_encode_chunk_rfc() {
-        let curve_chunk_1 = z85_decode_chunk(&Z85_RFC.as_bytes()[..5]).unwrap();
-        let curve_chunk_2 = z85_decode_chunk(&Z85_RFC.as_bytes()[5..]).unwrap();
-        assert_eq!(curve_chunk_1, BIN_RFC[..4]);
-        assert_eq!(curve_chunk_2, BIN_RFC[4..]);
-    }
-
-    #[test]
-    fn z85_decode_chunk_rfc() {
-        let z85_chunk_1 = z85_encode_chunk(&BIN_RFC[..4]);
-        let z85_chunk_2 = z85_encode_chunk(&BIN_RFC[4..]);
-        assert_eq!(z85_chunk_1, Z85_RFC.as_bytes()[..5]);
-        assert_eq!(z85_chunk_2, Z85_RFC.as_bytes()[5..]);
-    }
-
-    #[test]
-    fn z85_encode_rfc() {
-        let curve_key = z85_decode(&Z85_RFC).unwrap();
-        assert_eq!(curve_key, BIN_RFC);
-    }
-
-    #[test]
-    fn z85_decode_rfc() {
-        let curve_key = z85_encode(&BIN_RFC).unwrap();
-        assert_eq!(curve_key, Z85_RFC);
-    }
-
-    quickcheck! {
-        fn codec_chunk_quickcheck(num: u32) -> bool {
-            let mut buf = [0_u8; 4];
-            BigEndian::write_u32(&mut buf, num);
-
-            let z85_chunk = z85_encode_chunk(&buf);
-            if let Ok(curve_chunk) = z85_decode_chunk(&z85_chunk) {
-                if curve_chunk == buf {
-                    return true;
-                }
-            }
-
-            false
-        }
-    }
-
-    quickcheck! {
-        fn codec_quickcheck(input: Vec<u8>) -> bool {
-            let mut input = input;
-            input.extend_from_slice(&input.clone());
-            input.extend_from_slice(&input.clone());
-
-            if let Ok(z85) = z85_encode(&input) {
-                if let Ok(curve) = z85_decode(&z85) {
-                    return curve == input;
-                }
-            }
-
-            false
-        }
-    }
-
-    #[test]
-    fn seven_bit_letters() {
-        for &l in LETTERS.iter() {
-            assert!(l < 0x80)
-        }
+    fn secrets_redacted() {
+        let creds = PlainClientCreds::new("user", "hunter2");
+        let entry = PlainRegistryEntry::new(&creds);
+        let debug = format!("{:?} {:?}", creds, entry);
+        assert!(!debug.contains("hunter2"));
+
+        let secret = CurveSecretKey::new(CURVE_KEY_SECRET).unwrap();
+        let creds = CurveServerCreds::new(&secret);
+        let debug = format!("{:?}", creds);
+        assert!(!debug.contains(CURVE_KEY_SECRET));
+
+        let bin: BinCurveKey = (&secret).into();
+        let debug = format!("{:?}", bin);
+        assert!(!debug.contains(&format!("{:?}", bin.as_bytes())));
+
+        // The copies are unaffected by the wiping.
+        let clone = secret.clone();
+        drop(secret);
+        assert_eq!(clone.as_str(), CURVE_KEY_SECRET);
     }
 }
@@ -0,0 +1,560 @@
+use super::{curve::BinCurveKey, secret, CurveSecretKey};
+use crate::z85;
+
+use byteorder::{BigEndian, ByteOrder};
+use failure::Fail;
+use libsodium_sys as sodium;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+use std::{
+    env, fmt, fs, io,
+    os::raw::{c_char, c_int, c_ulonglong},
+    path::PathBuf,
+    str::FromStr,
+};
+
+// Identifies the format of the encrypted key.
+const MAGIC: &[u8; 4] = b"zsk\x01";
+const KEY_SIZE: usize = 32;
+const SALT_SIZE: usize = sodium::crypto_pwhash_SALTBYTES as usize;
+const NONCE_SIZE: usize = sodium::crypto_secretbox_NONCEBYTES as usize;
+const MAC_SIZE: usize = sodium::crypto_secretbox_MACBYTES as usize;
+
+// The binary layout is the magic, the `argon2id` opslimit and memlimit (in
+// KiB) as big endian `u32`, the salt, the nonce and the ciphertext.
+const SALT_OFFSET: usize = MAGIC.len() + 8;
+const NONCE_OFFSET: usize = SALT_OFFSET + SALT_SIZE;
+const CIPHER_OFFSET: usize = NONCE_OFFSET + NONCE_SIZE;
+const ENCRYPTED_SIZE: usize = CIPHER_OFFSET + KEY_SIZE + MAC_SIZE;
+
+/// An error when encrypting or decrypting an [`EncryptedSecretKey`].
+///
+/// [`EncryptedSecretKey`]: struct.EncryptedSecretKey.html
+#[derive(Debug, Fail)]
+pub enum SecretKeyError {
+    #[fail(display = "invalid encrypted secret key: {}", msg)]
+    Invalid { msg: String },
+    #[fail(display = "wrong passphrase or corrupted secret key")]
+    Decryption,
+    #[fail(display = "key derivation failed, likely out of memory")]
+    Derivation,
+    #[fail(display = "environment variable {} is not set", var)]
+    MissingEnv { var: String },
+    #[fail(display = "unable to read passphrase from {}: {}", path, error)]
+    Io { path: String, error: io::Error },
+}
+
+impl SecretKeyError {
+    fn invalid<M>(msg: M) -> Self
+    where
+        M: Into<String>,
+    {
+        SecretKeyError::Invalid { msg: msg.into() }
+    }
+}
+
+/// The source of the passphrase of an [`EncryptedSecretKey`].
+///
+/// The passphrase is read when the key is decrypted. Trailing newlines are
+/// stripped from the content of passphrase files.
+///
+/// [`EncryptedSecretKey`]: struct.EncryptedSecretKey.html
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Passphrase {
+    /// The passphrase itself.
+    Text(String),
+    /// The name of an environment variable that holds the passphrase.
+    Env(String),
+    /// The path of a file that holds the passphrase.
+    File(PathBuf),
+}
+
+impl Passphrase {
+    fn read(&self) -> Result<String, SecretKeyError> {
+        match self {
+            Passphrase::Text(text) => Ok(text.to_owned()),
+            Passphrase::Env(var) => {
+                env::var(var).map_err(|_| SecretKeyError::MissingEnv {
+                    var: var.to_owned(),
+                })
+            }
+            Passphrase::File(path) => {
+                let mut text = fs::read_to_string(path).map_err(|error| {
+                    SecretKeyError::Io {
+                        path: path.display().to_string(),
+                        error,
+                    }
+                })?;
+                let len = text.trim_end_matches(&['\r', '\n'][..]).len();
+                text[len..].zeroize();
+                text.truncate(len);
+
+                Ok(text)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Passphrase::Text(_) => {
+                f.debug_tuple("Text").field(&secret::REDACTED).finish()
+            }
+            Passphrase::Env(var) => f.debug_tuple("Env").field(var).finish(),
+            Passphrase::File(path) => {
+                f.debug_tuple("File").field(path).finish()
+            }
+        }
+    }
+}
+
+impl Drop for Passphrase {
+    fn drop(&mut self) {
+        if let Passphrase::Text(text) = self {
+            secret::wipe(text);
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Passphrase {
+    fn from(text: &'a str) -> Self {
+        Passphrase::Text(text.to_owned())
+    }
+}
+
+impl From<String> for Passphrase {
+    fn from(text: String) -> Self {
+        Passphrase::Text(text)
+    }
+}
+
+impl<'a> From<&'a Passphrase> for Passphrase {
+    fn from(passphrase: &'a Passphrase) -> Self {
+        passphrase.to_owned()
+    }
+}
+
+/// A [`CurveSecretKey`] encrypted with a passphrase, so that it can be
+/// stored at rest.
+///
+/// The encryption key is derived from the passphrase using `argon2id` and
+/// a random salt, then the secret key is encrypted with the
+/// `XSalsa20-Poly1305` cipher of `libsodium`. The result is printed in
+/// the [`Z85`] representation.
+///
+/// Requires the `curve` feature.
+///
+/// # Serde
+/// Wherever a `CurveSecretKey` is deserialized from a human readable
+/// format, such as in a [`CurveCert`] or [`CurveServerCreds`], an encrypted
+/// key can be specified instead of the `Z85` key, along with exactly one
+/// source for its passphrase. The key is then decrypted at load time.
+/// ```yml
+/// secret:
+///   encrypted: "<encrypted key>"
+///   # Or `passphrase` or `passphrase_file`.
+///   passphrase_env: SERVER_KEY_PASSPHRASE
+/// ```
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::auth::*;
+///
+/// let cert = CurveCert::new_unique();
+/// let encrypted = cert.secret().encrypt("passphrase")?;
+///
+/// std::env::set_var("SERVER_KEY_PASSPHRASE", "passphrase");
+/// let yaml = format!(
+///     "secret:\n  encrypted: \"{}\"\n  passphrase_env: SERVER_KEY_PASSPHRASE",
+///     encrypted
+/// );
+///
+/// let creds: CurveServerCreds = serde_yaml::from_str(&yaml)?;
+/// assert_eq!(creds.secret(), cert.secret());
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`CurveSecretKey`]: struct.CurveSecretKey.html
+/// [`Z85`]: https://rfc.zeromq.org/spec:32/Z85/
+/// [`CurveCert`]: struct.CurveCert.html
+/// [`CurveServerCreds`]: struct.CurveServerCreds.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EncryptedSecretKey {
+    text: String,
+}
+
+impl EncryptedSecretKey {
+    /// Create a new `EncryptedSecretKey` from the string returned by
+    /// [`as_str`].
+    ///
+    /// [`as_str`]: #method.as_str
+    pub fn new<S>(text: S) -> Result<Self, SecretKeyError>
+    where
+        S: Into<String>,
+    {
+        let text = text.into();
+        if text.len() != ENCRYPTED_SIZE / 4 * 5 {
+            return Err(SecretKeyError::invalid("invalid length"));
+        }
+
+        let bytes = z85::decode(&text)
+            .map_err(|err| SecretKeyError::invalid(err.to_string()))?;
+        if !bytes.starts_with(MAGIC) {
+            return Err(SecretKeyError::invalid("unknown format"));
+        }
+        params(&bytes)?;
+
+        Ok(Self { text })
+    }
+
+    /// Returns the encrypted key in the `Z85` representation.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Decrypt the secret key with the passphrase.
+    ///
+    /// Returns `SecretKeyError::Decryption` if the passphrase is wrong.
+    pub fn decrypt<P>(
+        &self,
+        passphrase: P,
+    ) -> Result<CurveSecretKey, SecretKeyError>
+    where
+        P: Into<Passphrase>,
+    {
+        let mut passphrase = passphrase.into().read()?;
+
+        // The string was validated on creation.
+        let bytes = z85::decode(&self.text).unwrap();
+        let (ops, mem) = params(&bytes).unwrap();
+        let salt = &bytes[SALT_OFFSET..NONCE_OFFSET];
+        let nonce = &bytes[NONCE_OFFSET..CIPHER_OFFSET];
+        let cipher = &bytes[CIPHER_OFFSET..];
+
+        let result = derive_key(&passphrase, salt, ops, mem);
+        secret::wipe(&mut passphrase);
+        let mut key = result?;
+
+        let mut plain = vec![0; KEY_SIZE];
+        let rc = unsafe {
+            sodium::crypto_secretbox_open_easy(
+                plain.as_mut_ptr(),
+                cipher.as_ptr(),
+                cipher.len() as c_ulonglong,
+                nonce.as_ptr(),
+                key.as_ptr(),
+            )
+        };
+        key.zeroize();
+
+        let bin = BinCurveKey::new_unchecked(plain);
+        if rc == 0 {
+            Ok(bin.into())
+        } else {
+            Err(SecretKeyError::Decryption)
+        }
+    }
+}
+
+impl CurveSecretKey {
+    /// Encrypt the key with the passphrase so that it can be stored at rest.
+    ///
+    /// The key is derived from the passphrase using `argon2id` with the
+    /// moderate parameters of `libsodium`, which require 256 MiB of memory.
+    /// Returns `SecretKeyError::Derivation` if the derivation fails.
+    ///
+    /// See [`EncryptedSecretKey`].
+    ///
+    /// [`EncryptedSecretKey`]: struct.EncryptedSecretKey.html
+    pub fn encrypt<S>(
+        &self,
+        passphrase: S,
+    ) -> Result<EncryptedSecretKey, SecretKeyError>
+    where
+        S: AsRef<str>,
+    {
+        self.encrypt_with_params(
+            passphrase,
+            sodium::crypto_pwhash_OPSLIMIT_MODERATE,
+            sodium::crypto_pwhash_MEMLIMIT_MODERATE,
+        )
+    }
+
+    // Takes the opslimit and the memlimit in bytes.
+    pub(crate) fn encrypt_with_params<S>(
+        &self,
+        passphrase: S,
+        ops: u32,
+        mem: u32,
+    ) -> Result<EncryptedSecretKey, SecretKeyError>
+    where
+        S: AsRef<str>,
+    {
+        init();
+
+        let mut bytes = vec![0; ENCRYPTED_SIZE];
+        bytes[..MAGIC.len()].copy_from_slice(MAGIC);
+        BigEndian::write_u32(&mut bytes[MAGIC.len()..], ops);
+        BigEndian::write_u32(&mut bytes[MAGIC.len() + 4..], mem / 1024);
+        unsafe {
+            sodium::randombytes_buf(
+                bytes[SALT_OFFSET..].as_mut_ptr() as *mut _,
+                SALT_SIZE + NONCE_SIZE,
+            );
+        }
+
+        let (header, cipher) = bytes.split_at_mut(CIPHER_OFFSET);
+        let salt = &header[SALT_OFFSET..NONCE_OFFSET];
+        let nonce = &header[NONCE_OFFSET..];
+
+        let mut key = derive_key(passphrase.as_ref(), salt, ops, mem as usize)?;
+        let plain: BinCurveKey = self.into();
+        let rc = unsafe {
+            sodium::crypto_secretbox_easy(
+                cipher.as_mut_ptr(),
+                plain.as_bytes().as_ptr(),
+                KEY_SIZE as c_ulonglong,
+                nonce.as_ptr(),
+                key.as_ptr(),
+            )
+        };
+        key.zeroize();
+        assert_eq!(rc, 0, "encryption failed");
+
+        Ok(EncryptedSecretKey {
+            text: z85::encode(&bytes),
+        })
+    }
+}
+
+fn init() {
+    let rc = unsafe { sodium::sodium_init() };
+    assert!(rc >= 0, "unable to initialize libsodium");
+}
+
+// Returns the opslimit and the memlimit in bytes. Since they are read from
+// the key, they are bounded so that a crafted key cannot exhaust the
+// resources of the host.
+fn params(bytes: &[u8]) -> Result<(u32, usize), SecretKeyError> {
+    let ops = BigEndian::read_u32(&bytes[MAGIC.len()..]);
+    let mem = u64::from(BigEndian::read_u32(&bytes[MAGIC.len() + 4..])) * 1024;
+
+    let ops_range = sodium::crypto_pwhash_OPSLIMIT_MIN
+        ..=sodium::crypto_pwhash_OPSLIMIT_SENSITIVE;
+    if !ops_range.contains(&ops) {
+        return Err(SecretKeyError::invalid("unsupported opslimit"));
+    }
+    let mem_range = u64::from(sodium::crypto_pwhash_MEMLIMIT_MIN)
+        ..=u64::from(sodium::crypto_pwhash_MEMLIMIT_SENSITIVE);
+    if !mem_range.contains(&mem) {
+        return Err(SecretKeyError::invalid("unsupported memlimit"));
+    }
+
+    Ok((ops, mem as usize))
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    ops: u32,
+    mem: usize,
+) -> Result<Vec<u8>, SecretKeyError> {
+    init();
+    let mut key = vec![0; sodium::crypto_secretbox_KEYBYTES as usize];
+    let rc = unsafe {
+        sodium::crypto_pwhash(
+            key.as_mut_ptr(),
+            key.len() as c_ulonglong,
+            passphrase.as_ptr() as *const c_char,
+            passphrase.len() as c_ulonglong,
+            salt.as_ptr(),
+            c_ulonglong::from(ops),
+            mem,
+            sodium::crypto_pwhash_ALG_ARGON2ID13 as c_int,
+        )
+    };
+
+    if rc == 0 {
+        Ok(key)
+    } else {
+        Err(SecretKeyError::Derivation)
+    }
+}
+
+impl fmt::Display for EncryptedSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl FromStr for EncryptedSecretKey {
+    type Err = SecretKeyError;
+
+    fn from_str(s: &str) -> Result<Self, SecretKeyError> {
+        Self::new(s)
+    }
+}
+
+impl Serialize for EncryptedSecretKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.text)
+    }
+}
+
+impl<'de> Deserialize<'de> for EncryptedSecretKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Self::new(s).map_err(serde::de::Error::custom)
+    }
+}
+
+// The configuration of an encrypted `CurveSecretKey`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct EncryptedSecretConfig {
+    encrypted: EncryptedSecretKey,
+    passphrase: Option<String>,
+    passphrase_env: Option<String>,
+    passphrase_file: Option<PathBuf>,
+}
+
+impl EncryptedSecretConfig {
+    pub(crate) fn decrypt(mut self) -> Result<CurveSecretKey, SecretKeyError> {
+        let passphrase = match (
+            self.passphrase.take(),
+            self.passphrase_env.take(),
+            self.passphrase_file.take(),
+        ) {
+            (Some(text), None, None) => Passphrase::Text(text),
+            (None, Some(var), None) => Passphrase::Env(var),
+            (None, None, Some(path)) => Passphrase::File(path),
+            _ => {
+                return Err(SecretKeyError::invalid(
+                    "expected exactly one of passphrase, passphrase_env \
+                     or passphrase_file",
+                ))
+            }
+        };
+
+        self.encrypted.decrypt(passphrase)
+    }
+}
+
+impl Drop for EncryptedSecretConfig {
+    fn drop(&mut self) {
+        if let Some(text) = &mut self.passphrase {
+            secret::wipe(text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::auth::{CurveCert, CurveServerCreds};
+
+    // The moderate cost allocates 256 MiB per key.
+    fn encrypt(cert: &CurveCert, passphrase: &str) -> EncryptedSecretKey {
+        cert.secret()
+            .encrypt_with_params(
+                passphrase,
+                sodium::crypto_pwhash_OPSLIMIT_MIN,
+                sodium::crypto_pwhash_MEMLIMIT_MIN,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let cert = CurveCert::new_unique();
+        let encrypted = encrypt(&cert, "passphrase");
+        assert!(!encrypted.as_str().contains(cert.secret().as_str()));
+
+        let parsed = EncryptedSecretKey::new(encrypted.as_str()).unwrap();
+        let secret = parsed.decrypt("passphrase").unwrap();
+        assert_eq!(&secret, cert.secret());
+
+        match parsed.decrypt("wrong") {
+            Err(SecretKeyError::Decryption) => (),
+            res => panic!("unexpected {:?}", res),
+        }
+        match EncryptedSecretKey::new(cert.secret().as_str()) {
+            Err(SecretKeyError::Invalid { .. }) => (),
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_crafted_params() {
+        let cert = CurveCert::new_unique();
+        let encrypted = encrypt(&cert, "passphrase");
+        let bytes = z85::decode(encrypted.as_str()).unwrap();
+
+        // The opslimit and memlimit (in KiB) are capped to the sensitive
+        // parameters.
+        let crafted = [(5, 1024), (0, 1024), (4, 1024 * 1024 + 1), (4, 1)];
+        for &(ops, mem) in crafted.iter() {
+            let mut bytes = bytes.clone();
+            BigEndian::write_u32(&mut bytes[MAGIC.len()..], ops);
+            BigEndian::write_u32(&mut bytes[MAGIC.len() + 4..], mem);
+
+            match EncryptedSecretKey::new(z85::encode(&bytes)) {
+                Err(SecretKeyError::Invalid { .. }) => (),
+                res => panic!("unexpected {:?}", res),
+            }
+        }
+    }
+
+    #[test]
+    fn test_de_passphrase_sources() {
+        let cert = CurveCert::new_unique();
+        let encrypted = encrypt(&cert, "passphrase");
+
+        let path = std::env::temp_dir()
+            .join(format!("libzmq-passphrase-{}", std::process::id()));
+        fs::write(&path, "passphrase\n").unwrap();
+        env::set_var("LIBZMQ_TEST_PASSPHRASE", "passphrase");
+
+        let sources = [
+            "passphrase: passphrase".to_owned(),
+            "passphrase_env: LIBZMQ_TEST_PASSPHRASE".to_owned(),
+            format!("passphrase_file: {}", path.display()),
+        ];
+        for source in sources.iter() {
+            let yaml = format!(
+                "secret:\n  encrypted: \"{}\"\n  {}",
+                encrypted, source
+            );
+            let creds: CurveServerCreds = serde_yaml::from_str(&yaml).unwrap();
+            assert_eq!(creds.secret(), cert.secret());
+        }
+        fs::remove_file(&path).unwrap();
+
+        let invalid = [
+            "passphrase: wrong".to_owned(),
+            "passphrase_env: LIBZMQ_TEST_PASSPHRASE_UNSET".to_owned(),
+            format!("passphrase_file: {}", path.display()),
+            "passphrase: passphrase\n  passphrase_env: A".to_owned(),
+        ];
+        for source in invalid.iter() {
+            let yaml = format!(
+                "secret:\n  encrypted: \"{}\"\n  {}",
+                encrypted, source
+            );
+            assert!(serde_yaml::from_str::<CurveServerCreds>(&yaml).is_err());
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use super::{server::AUTH_EVENT_ENDPOINT, *};
+use crate::{poll::Pollable, prelude::*, socket::*, *};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use std::net::{IpAddr, Ipv6Addr};
+
+lazy_static! {
+    pub(crate) static ref AUTH_EVENT_GROUP: Group = "auth".try_into().unwrap();
+}
+
+// Converts IPv4-mapped IPv6 addresses back to IPv4.
+pub(crate) fn unmap_addr(ipv6: Ipv6Addr) -> IpAddr {
+    match ipv6.to_ipv4() {
+        Some(ipv4) if ipv6.segments()[5] == 0xffff => IpAddr::V4(ipv4),
+        _ => IpAddr::V6(ipv6),
+    }
+}
+
+/// The reason why the `AuthServer` denied a peer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DenyReason {
+    /// The address of the peer is blacklisted.
+    Blacklisted,
+    /// The address of the peer is temporarily banned following repeated
+    /// authentication failures. See [`BanPolicy`].
+    ///
+    /// [`BanPolicy`]: struct.BanPolicy.html
+    Banned,
+    /// The whitelist is not empty and does not contain the address
+    /// of the peer.
+    NotWhitelisted,
+    /// The username is not in the `PLAIN` registry.
+    UnknownUser,
+    /// The password does not match the one in the `PLAIN` registry.
+    BadPassword,
+    /// The public key is not in the `CURVE` registry.
+    UnknownKey,
+    /// The `PLAIN` credentials are not valid UTF-8.
+    InvalidCredentials,
+    /// The installed [`Authenticator`] did not allow the peer.
+    ///
+    /// [`Authenticator`]: trait.Authenticator.html
+    Authenticator,
+}
+
+/// An event emitted by the `AuthServer` for each authentication attempt.
+///
+/// It is received via an [`AuthMonitor`].
+///
+/// [`AuthMonitor`]: struct.AuthMonitor.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthEvent {
+    pub(crate) domain: String,
+    pub(crate) addr: Option<Ipv6Addr>,
+    pub(crate) mechanism: MechanismName,
+    pub(crate) user_id: Option<String>,
+    pub(crate) status_code: StatusCode,
+    pub(crate) reason: Option<DenyReason>,
+}
+
+impl AuthEvent {
+    pub(crate) fn new(
+        request: &ZapRequest,
+        verdict: &Verdict,
+        reason: Option<DenyReason>,
+    ) -> Self {
+        let allowed = verdict.status_code() == StatusCode::Allowed;
+        let user_id = if allowed && !verdict.user_id().is_empty() {
+            Some(verdict.user_id().to_owned())
+        } else {
+            match request.credentials() {
+                Credentials::Null => None,
+                Credentials::Plain(creds) => Some(creds.username.to_owned()),
+                Credentials::Curve(key) => Some(key.as_str().to_owned()),
+            }
+        };
+
+        let mechanism = match request.credentials() {
+            Credentials::Null => MechanismName::Null,
+            Credentials::Plain(_) => MechanismName::Plain,
+            Credentials::Curve(_) => MechanismName::Curve,
+        };
+
+        Self {
+            domain: request.domain.to_owned(),
+            addr: request.addr,
+            mechanism,
+            user_id,
+            status_code: verdict.status_code(),
+            reason,
+        }
+    }
+
+    /// The `ZAP` domain of the socket that accepted the connection.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// The IP address of the peer, if it connected using an IP based
+    /// transport.
+    pub fn addr(&self) -> Option<IpAddr> {
+        self.addr.map(unmap_addr)
+    }
+
+    /// The mechanism used by the peer.
+    pub fn mechanism(&self) -> MechanismName {
+        self.mechanism
+    }
+
+    /// The user id assigned to the peer if it was allowed, otherwise the
+    /// username or public key that it presented.
+    ///
+    /// Peers using the `NULL` mechanism have no user id unless one is
+    /// assigned by an [`Authenticator`].
+    ///
+    /// [`Authenticator`]: trait.Authenticator.html
+    pub fn user_id(&self) -> Option<&str> {
+        self.user_id.as_deref()
+    }
+
+    /// The status code sent back to the socket.
+    pub fn status_code(&self) -> StatusCode {
+        self.status_code
+    }
+
+    /// Whether the peer was allowed to connect.
+    pub fn is_allowed(&self) -> bool {
+        self.status_code == StatusCode::Allowed
+    }
+
+    /// The reason why the peer was denied, if it was.
+    pub fn reason(&self) -> Option<DenyReason> {
+        self.reason
+    }
+}
+
+/// A handle that receives the [`AuthEvent`] emitted by the `AuthServer`.
+///
+/// Any number of `AuthMonitor` can be associated with the same `AuthServer`.
+/// Events emitted before the `AuthMonitor` is created are not received.
+/// Events are dropped if the `AuthMonitor` does not keep up.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::{prelude::*, auth::*, *};
+///
+/// // Use a new context to avoid impacting other sockets.
+/// let ctx = Ctx::new();
+/// let handle = ctx.handle();
+///
+/// let monitor = AuthMonitor::with_ctx(handle)?;
+///
+/// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+///
+/// let server = ServerBuilder::new()
+///     .bind(addr)
+///     .mechanism(Mechanism::PlainServer)
+///     .with_ctx(handle)?;
+///
+/// let bound = server.last_endpoint()?;
+///
+/// let _client = ClientBuilder::new()
+///     .connect(bound)
+///     .mechanism(PlainClientCreds::new("mallory", "guess"))
+///     .with_ctx(handle)?;
+///
+/// let event = monitor.recv_event()?;
+/// assert!(!event.is_allowed());
+/// assert_eq!(event.mechanism(), MechanismName::Plain);
+/// assert_eq!(event.user_id(), Some("mallory"));
+/// assert_eq!(event.reason(), Some(DenyReason::UnknownUser));
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`AuthEvent`]: struct.AuthEvent.html
+pub struct AuthMonitor {
+    dish: Dish,
+}
+
+impl AuthMonitor {
+    /// Create a `AuthMonitor` connected to the `AuthServer` associated
+    /// with the default global `Ctx`.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_ctx(Ctx::global())
+    }
+
+    /// Create a `AuthMonitor` connected to the `AuthServer` associated
+    /// with the context aliased by the `CtxHandle`.
+    pub fn with_ctx(handle: CtxHandle) -> Result<Self, Error> {
+        let dish = DishBuilder::new()
+            .connect(&*AUTH_EVENT_ENDPOINT)
+            .join(&*AUTH_EVENT_GROUP)
+            .with_ctx(handle)
+            .map_err(Error::cast)?;
+
+        Ok(Self { dish })
+    }
+
+    /// Set the timeout of [`recv_event`].
+    ///
+    /// [`recv_event`]: #method.recv_event
+    pub fn set_recv_timeout<P>(&self, period: P) -> Result<(), Error>
+    where
+        P: Into<Period>,
+    {
+        self.dish.set_recv_timeout(period)
+    }
+
+    /// Receive the next event, blocking until one is available.
+    ///
+    /// # Returned Errors
+    /// * [`WouldBlock`] (if the receive timeout expired)
+    /// * [`InvalidCtx`]
+    /// * [`Interrupted`]
+    ///
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    pub fn recv_event(&self) -> Result<AuthEvent, Error> {
+        let msg = self.dish.recv_msg()?;
+        Ok(bincode::deserialize(msg.as_bytes()).unwrap())
+    }
+
+    /// Try to receive the next event without blocking.
+    ///
+    /// # Returned Errors
+    /// * [`WouldBlock`] (no event is pending)
+    /// * [`InvalidCtx`]
+    /// * [`Interrupted`]
+    ///
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    pub fn try_recv_event(&self) -> Result<AuthEvent, Error> {
+        let msg = self.dish.try_recv_msg()?;
+        Ok(bincode::deserialize(msg.as_bytes()).unwrap())
+    }
+}
+
+impl<'a> From<&'a AuthMonitor> for Pollable<'a> {
+    fn from(monitor: &'a AuthMonitor) -> Self {
+        Pollable::from(&monitor.dish)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_auth_monitor() {
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let monitor = AuthMonitor::with_ctx(handle).unwrap();
+        monitor
+            .set_recv_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+
+        let creds = PlainClientCreds::new("user", "pwd");
+        let entry = PlainRegistryEntry::new(&creds).with_user_id("id");
+        let auth = AuthBuilder::new()
+            .hash_params(HashParams::new(8, 1, 1).unwrap())
+            .plain_registry(entry)
+            .with_ctx(handle)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .mechanism(Mechanism::PlainServer)
+            .with_ctx(handle)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap();
+
+        let attempts = vec![
+            (creds, None),
+            (
+                PlainClientCreds::new("user", "nope"),
+                Some(DenyReason::BadPassword),
+            ),
+            (
+                PlainClientCreds::new("other", "pwd"),
+                Some(DenyReason::UnknownUser),
+            ),
+        ];
+
+        for (creds, reason) in attempts {
+            let _client = ClientBuilder::new()
+                .connect(&bound)
+                .mechanism(&creds)
+                .with_ctx(handle)
+                .unwrap();
+
+            let event = monitor.recv_event().unwrap();
+            assert_eq!(event.domain(), "global");
+            assert_eq!(event.addr(), Some("127.0.0.1".parse().unwrap()));
+            assert_eq!(event.mechanism(), MechanismName::Plain);
+            assert_eq!(event.reason(), reason);
+            assert_eq!(event.is_allowed(), reason.is_none());
+            if event.is_allowed() {
+                assert_eq!(event.user_id(), Some("id"));
+            } else {
+                assert_eq!(event.user_id(), Some(creds.username()));
+            }
+        }
+
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        auth.set_blacklist(ip).unwrap();
+
+        let _client = ClientBuilder::new()
+            .connect(&bound)
+            .mechanism(PlainClientCreds::new("user", "pwd"))
+            .with_ctx(handle)
+            .unwrap();
+
+        let event = monitor.recv_event().unwrap();
+        assert_eq!(event.status_code(), StatusCode::Denied);
+        assert_eq!(event.reason(), Some(DenyReason::Blacklisted));
+    }
+}
@@ -0,0 +1,485 @@
+use super::{client::AuthConfig, *};
+use crate::{
+    addr::{AddrParseError, Subnet},
+    *,
+};
+
+use failure::Fail;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// An error when reading or writing an authentication related file, such
+/// as the files of an [`AuthFiles`] or a certificate.
+///
+/// [`AuthFiles`]: struct.AuthFiles.html
+#[derive(Debug, Fail)]
+pub enum AuthFileError {
+    #[fail(display = "unable to access {}: {}", path, error)]
+    Io { path: String, error: io::Error },
+    #[fail(display = "{}:{}: {}", path, line, msg)]
+    Parse {
+        path: String,
+        line: usize,
+        msg: String,
+    },
+    #[fail(display = "{}: {}", path, msg)]
+    Invalid { path: String, msg: String },
+}
+
+impl AuthFileError {
+    pub(crate) fn io(path: &Path, error: io::Error) -> Self {
+        AuthFileError::Io {
+            path: path.display().to_string(),
+            error,
+        }
+    }
+
+    pub(crate) fn parse<M>(path: &Path, line: usize, msg: M) -> Self
+    where
+        M: Into<String>,
+    {
+        AuthFileError::Parse {
+            path: path.display().to_string(),
+            line,
+            msg: msg.into(),
+        }
+    }
+
+    pub(crate) fn invalid<M>(path: &Path, msg: M) -> Self
+    where
+        M: Into<String>,
+    {
+        AuthFileError::Invalid {
+            path: path.display().to_string(),
+            msg: msg.into(),
+        }
+    }
+}
+
+// Returns the non empty lines that are not comments along with their
+// line number.
+fn read_lines(path: &Path) -> Result<Vec<(usize, String)>, AuthFileError> {
+    let text =
+        fs::read_to_string(path).map_err(|err| AuthFileError::io(path, err))?;
+
+    let lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| (i, line.to_owned()))
+        .collect();
+
+    Ok(lines)
+}
+
+fn load_subnets(path: &Path) -> Result<Vec<Subnet>, AuthFileError> {
+    read_lines(path)?
+        .into_iter()
+        .map(|(i, line)| {
+            line.parse().map_err(|err: AddrParseError| {
+                AuthFileError::parse(path, i, err.msg())
+            })
+        })
+        .collect()
+}
+
+fn load_plain_registry(
+    path: &Path,
+) -> Result<Vec<PlainRegistryEntry>, AuthFileError> {
+    read_lines(path)?
+        .into_iter()
+        .map(|(i, line)| {
+            // The password may contain colons, but not the username.
+            match line.find(':') {
+                Some(mid) if mid > 0 => {
+                    let (username, password) = (&line[..mid], &line[mid + 1..]);
+                    if password.starts_with('$') {
+                        let hash = PasswordHash::from_phc(password).map_err(
+                            |err| AuthFileError::parse(path, i, err.msg()),
+                        )?;
+                        Ok(PlainRegistryEntry::with_hash(username, hash))
+                    } else {
+                        let creds = PlainClientCreds::new(username, password);
+                        Ok(PlainRegistryEntry::new(creds))
+                    }
+                }
+                _ => Err(AuthFileError::parse(
+                    path,
+                    i,
+                    "expected \"username:password\"",
+                )),
+            }
+        })
+        .collect()
+}
+
+/// A set of files from which the configuration of the `AuthServer`
+/// is loaded.
+///
+/// * The `CURVE` directory contains the public certificates of the allowed
+///   peers. See [`CertStore`].
+/// * The `PLAIN` file contains one `username:password` entry per line.
+///   A password starting with `$` is read as a [`PasswordHash`].
+/// * The whitelist and blacklist files contain one network per line, in the
+///   format accepted by [`Subnet`].
+///
+/// Empty lines and lines starting with `#` are ignored. Files that are not
+/// specified leave the corresponding configuration untouched.
+///
+/// The files can either be loaded once into an [`AuthConfig`] via [`load`],
+/// or watched via [`watch`] so that changes are applied to the running
+/// `AuthServer`.
+///
+/// # Example
+/// ```no_run
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::auth::*;
+/// use std::time::Duration;
+///
+/// let watcher = AuthFiles::new()
+///     .curve_dir("/etc/app/authorized_keys")
+///     .blacklist_file("/etc/app/blacklist")
+///     .watch(Duration::from_secs(1))?;
+///
+/// // The changes to the files are applied until `watcher` is dropped.
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`CertStore`]: struct.CertStore.html
+/// [`PasswordHash`]: struct.PasswordHash.html
+/// [`Subnet`]: ../addr/struct.Subnet.html
+/// [`AuthConfig`]: ../config/struct.AuthConfig.html
+/// [`load`]: #method.load
+/// [`watch`]: #method.watch
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+pub struct AuthFiles {
+    domain: Option<String>,
+    curve_dir: Option<PathBuf>,
+    plain_file: Option<PathBuf>,
+    whitelist_file: Option<PathBuf>,
+    blacklist_file: Option<PathBuf>,
+}
+
+impl AuthFiles {
+    /// Create an empty `AuthFiles`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `ZAP` domain that the files apply to.
+    pub fn domain<S>(&mut self, domain: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Set the directory of the `CURVE` public certificates.
+    pub fn curve_dir<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.curve_dir = Some(path.into());
+        self
+    }
+
+    /// Set the file of the `PLAIN` credentials.
+    pub fn plain_file<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.plain_file = Some(path.into());
+        self
+    }
+
+    /// Set the whitelist file.
+    pub fn whitelist_file<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.whitelist_file = Some(path.into());
+        self
+    }
+
+    /// Set the blacklist file.
+    pub fn blacklist_file<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.blacklist_file = Some(path.into());
+        self
+    }
+
+    /// Load the files into an `AuthConfig`.
+    pub fn load(&self) -> Result<AuthConfig, AuthFileError> {
+        let mut config = AuthConfig::new();
+        config.set_domain(self.domain.as_ref());
+
+        if let Some(ref path) = self.curve_dir {
+            config.set_curve_registry(Some(CertStore::load(path)?));
+        }
+        if let Some(ref path) = self.plain_file {
+            config.set_plain_registry(Some(load_plain_registry(path)?));
+        }
+        if let Some(ref path) = self.whitelist_file {
+            config.set_whitelist(Some(load_subnets(path)?));
+        }
+        if let Some(ref path) = self.blacklist_file {
+            config.set_blacklist(Some(load_subnets(path)?));
+        }
+
+        Ok(config)
+    }
+
+    /// Apply the files to the `AuthServer` associated with the default
+    /// global `Ctx`, then reload them every `period`.
+    ///
+    /// See [`watch_with_ctx`].
+    ///
+    /// [`watch_with_ctx`]: #method.watch_with_ctx
+    pub fn watch(&self, period: Duration) -> Result<AuthWatcher, Error> {
+        self.watch_with_ctx(Ctx::global(), period)
+    }
+
+    /// Apply the files to the `AuthServer` associated with the context
+    /// aliased by the `CtxHandle`, then reload them every `period`.
+    ///
+    /// Changes are applied whenever the loaded configuration differs from
+    /// the last one applied. Since each list is replaced as a whole, the
+    /// `AuthServer` never enforces a partially updated list. If the files
+    /// cannot be loaded, the error is logged and the last configuration
+    /// applied remains in effect.
+    ///
+    /// The files are watched until the returned [`AuthWatcher`] is dropped.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`]
+    ///
+    /// [`AuthWatcher`]: struct.AuthWatcher.html
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    pub fn watch_with_ctx(
+        &self,
+        handle: CtxHandle,
+        period: Duration,
+    ) -> Result<AuthWatcher, Error> {
+        let mut last = None;
+        self.reload(handle, &mut last)?;
+
+        let files = self.to_owned();
+        let (sender, receiver) = mpsc::channel::<()>();
+
+        let thread = thread::spawn(move || loop {
+            match receiver.recv_timeout(period) {
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Err(err) = files.reload(handle, &mut last) {
+                        if err.kind() == ErrorKind::InvalidCtx {
+                            return;
+                        }
+                    }
+                }
+                _ => return,
+            }
+        });
+
+        Ok(AuthWatcher {
+            sender: Some(sender),
+            thread: Some(thread),
+        })
+    }
+
+    // Applies the files if they changed since the last configuration applied.
+    fn reload(
+        &self,
+        handle: CtxHandle,
+        last: &mut Option<AuthConfig>,
+    ) -> Result<(), Error> {
+        let config = match self.load() {
+            Ok(config) => config,
+            Err(err) => {
+                error!("unable to load auth files: {}", err);
+                return Ok(());
+            }
+        };
+
+        if last.as_ref() != Some(&config) {
+            // The client is not kept around since it would otherwise
+            // prevent the `Ctx` from terminating.
+            config.with_ctx(handle)?;
+            info!("applied auth files");
+            *last = Some(config);
+        }
+
+        Ok(())
+    }
+}
+
+/// A handle to the thread that watches an [`AuthFiles`].
+///
+/// The thread stops when the `AuthWatcher` is dropped or when the `Ctx`
+/// is terminated.
+///
+/// [`AuthFiles`]: struct.AuthFiles.html
+#[derive(Debug)]
+pub struct AuthWatcher {
+    sender: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for AuthWatcher {
+    fn drop(&mut self) {
+        // Wakes up the thread.
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    use std::{ops::Deref, time::Instant};
+
+    // A temporary directory that is removed on drop.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("libzmq-auth-{}", uuid::Uuid::new_v4()));
+            fs::create_dir(&dir).unwrap();
+            TestDir(dir)
+        }
+    }
+
+    impl Deref for TestDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = TestDir::new();
+        let keys = dir.join("keys");
+        fs::create_dir(&keys).unwrap();
+
+        let cert = CurveCert::new_unique();
+        cert.save(keys.join("client.key")).unwrap();
+
+        let params = HashParams::new(8, 1, 1).unwrap();
+        let hash = PasswordHash::with_params("pwd", params);
+        let plain = format!("# comment\nuser:p:ss\n\nother:{}\n", hash);
+        fs::write(dir.join("plain"), plain).unwrap();
+        fs::write(dir.join("blacklist"), "10.0.0.0/8\n::1\n").unwrap();
+
+        let config = AuthFiles::new()
+            .domain("internal")
+            .curve_dir(&keys)
+            .plain_file(dir.join("plain"))
+            .blacklist_file(dir.join("blacklist"))
+            .load()
+            .unwrap();
+
+        let mut expected = AuthConfig::new();
+        expected.set_domain(Some("internal"));
+        expected.set_curve_registry(Some(cert.public()));
+        expected.set_plain_registry(Some(vec![
+            PlainRegistryEntry::new(PlainClientCreds::new("user", "p:ss")),
+            PlainRegistryEntry::with_hash("other", hash),
+        ]));
+        let blacklist: Vec<Subnet> =
+            vec!["10.0.0.0/8".try_into().unwrap(), "::1".try_into().unwrap()];
+        expected.set_blacklist(Some(blacklist));
+        assert_eq!(config, expected);
+
+        for text in &["user:pwd\nnocolon\n", "user:pwd\nother:$md5$abc\n"] {
+            fs::write(dir.join("plain"), text).unwrap();
+            let err = AuthFiles::new()
+                .plain_file(dir.join("plain"))
+                .load()
+                .unwrap_err();
+            match err {
+                AuthFileError::Parse { line, .. } => assert_eq!(line, 2),
+                _ => panic!("unexpected error: {}", err),
+            }
+        }
+
+        let err = AuthFiles::new()
+            .whitelist_file(dir.join("missing"))
+            .load()
+            .unwrap_err();
+        match err {
+            AuthFileError::Io { error, .. } => {
+                assert_eq!(error.kind(), io::ErrorKind::NotFound)
+            }
+            _ => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_watch() {
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let dir = TestDir::new();
+        let path = dir.join("plain");
+        fs::write(&path, "user:pwd\n").unwrap();
+
+        let auth = AuthClient::with_ctx(handle).unwrap();
+        auth.set_hash_params(HashParams::new(8, 1, 1).unwrap())
+            .unwrap();
+        let files = AuthFiles::new().plain_file(&path).to_owned();
+        let watcher = files
+            .watch_with_ctx(handle, Duration::from_millis(10))
+            .unwrap();
+
+        // The files are applied right away.
+        let registry = auth.plain_registry().unwrap();
+        assert_eq!(registry.len(), 1);
+        assert!(registry[0].password().verify("pwd"));
+
+        // Invalid files are ignored.
+        fs::write(&path, "invalid\n").unwrap();
+        let mut last = None;
+        files.reload(handle, &mut last).unwrap();
+        assert!(last.is_none());
+        assert_eq!(auth.plain_registry().unwrap(), registry);
+
+        fs::write(&path, "user:pwd\nother:pwd\n").unwrap();
+        let start = Instant::now();
+        while auth.plain_registry().unwrap().len() != 2 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        drop(watcher);
+    }
+}
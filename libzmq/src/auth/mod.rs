@@ -9,13 +9,89 @@
 //! performed depending on the configuration of the `AuthHandler`. This
 //! configuration can be modified by using a `AuthClient` which send commands
 //! to the handler.
+//!
+//! # Address Filtering
+//! Before any credentials are checked, the peer's IP address is matched
+//! against the whitelist and the blacklist, which contain networks in CIDR
+//! notation. The most specific network that contains the address decides
+//! whether the peer is allowed (whitelist) or denied (blacklist). If the
+//! same network is in both lists, the whitelist wins.
+//!
+//! If no network contains the address, the peer is denied if the whitelist
+//! is not empty and allowed otherwise.
+//!
+//! Additionally, a [`BanPolicy`] can be used to temporarily ban the addresses
+//! of peers that repeatedly fail to authenticate.
+//!
+//! [`BanPolicy`]: struct.BanPolicy.html
+//!
+//! # ZAP Domains
+//! Each socket belongs to a `ZAP` domain, which defaults to "global". The
+//! `AuthHandler` keeps an independent whitelist, blacklist, and registries
+//! for each domain, so that sockets with different security requirements
+//! can share the same `Ctx`. A `AuthClient` configures a single domain,
+//! specified via [`AuthClient::set_domain`] or [`AuthBuilder::domain`].
+//!
+//! [`AuthClient::set_domain`]: struct.AuthClient.html#method.set_domain
+//! [`AuthBuilder::domain`]: struct.AuthBuilder.html#method.domain
+//!
+//! # Events
+//! Every authentication attempt is published as an [`AuthEvent`], which
+//! states whether the peer was allowed and why it was denied. The events
+//! can be received via an [`AuthMonitor`].
+//!
+//! [`AuthEvent`]: struct.AuthEvent.html
+//! [`AuthMonitor`]: struct.AuthMonitor.html
+//!
+//! # Configuration Files
+//! The registries and the address lists can be stored on disk and
+//! described by an [`AuthFiles`]. The files are either loaded once or
+//! watched, in which case changes are applied to the running
+//! `AuthHandler` without restarting the process.
+//!
+//! `CURVE` certificates can be saved and loaded in the `czmq` `zcert`
+//! format, which allows keys to be shared with applications using `czmq`.
+//! A directory of public certificates can be loaded via a [`CertStore`].
+//!
+//! [`AuthFiles`]: struct.AuthFiles.html
+//! [`CertStore`]: struct.CertStore.html
+//!
+//! # Secrets
+//! `CURVE` secret keys and `PLAIN` passwords are wiped from memory when
+//! dropped and are redacted from the `Debug` output. With the `mlock`
+//! feature, their memory is also locked so that it is never swapped to disk
+//! and, on Linux, excluded from core dumps.
+//!
+//! With the `curve` feature, `CURVE` secret keys can be stored at rest
+//! encrypted with a passphrase, see [`EncryptedSecretKey`].
+//!
+//! [`EncryptedSecretKey`]: struct.EncryptedSecretKey.html
 
+mod authenticator;
+mod ban;
+mod cert;
 pub(crate) mod client;
 mod curve;
+#[cfg(feature = "curve")]
+mod encrypted;
+mod event;
+mod files;
+mod password;
+mod registry;
+mod secret;
 pub(crate) mod server;
 
+pub use authenticator::{Authenticator, Credentials, Verdict, ZapRequest};
+pub use ban::{Ban, BanPolicy};
+pub use cert::CertStore;
 pub use client::{AuthBuilder, AuthClient};
 pub use curve::*;
+#[cfg(feature = "curve")]
+pub use encrypted::{EncryptedSecretKey, Passphrase, SecretKeyError};
+pub use event::{AuthEvent, AuthMonitor, DenyReason};
+pub use files::{AuthFileError, AuthFiles, AuthWatcher};
+pub use password::{HashParams, PasswordHash, PasswordHashError};
+pub use registry::{CurveRegistryEntry, PlainPassword, PlainRegistryEntry};
 pub use server::{StatusCode, StatusCodeParseError};
 
 use super::*;
@@ -24,16 +100,21 @@ use crate::prelude::TryFrom;
 use failure::Fail;
 use serde::{Deserialize, Serialize};
 
-use std::option;
+use std::{fmt, mem, option};
 
 /// Credentials for a `PLAIN` client.
+///
+/// The password is wiped from memory when dropped and is redacted from the
+/// `Debug` output. With the `mlock` feature, its memory is also locked.
+///
 /// # Example
 /// ```
 /// use libzmq::auth::*;
 ///
 /// let creds = PlainClientCreds::new("user", "pass");
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "PlainCredsFields")]
 pub struct PlainClientCreds {
     pub(crate) username: String,
     pub(crate) password: String,
@@ -46,10 +127,11 @@ impl PlainClientCreds {
         U: Into<String>,
         P: Into<String>,
     {
-        Self {
+        PlainCredsFields {
             username: username.into(),
             password: password.into(),
         }
+        .into()
     }
 
     /// Returns a reference to the username.
@@ -61,6 +143,32 @@ impl PlainClientCreds {
     pub fn password(&self) -> &str {
         &self.password
     }
+
+    // Moves the password out of the credentials.
+    pub(crate) fn take_password(&mut self) -> String {
+        mem::take(&mut self.password)
+    }
+}
+
+impl fmt::Debug for PlainClientCreds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PlainClientCreds")
+            .field("username", &self.username)
+            .field("password", &secret::REDACTED)
+            .finish()
+    }
+}
+
+impl Clone for PlainClientCreds {
+    fn clone(&self) -> Self {
+        Self::new(self.username.as_str(), self.password.as_str())
+    }
+}
+
+impl Drop for PlainClientCreds {
+    fn drop(&mut self) {
+        secret::wipe(&mut self.password);
+    }
 }
 
 impl<'a> From<&'a PlainClientCreds> for PlainClientCreds {
@@ -69,6 +177,24 @@ impl<'a> From<&'a PlainClientCreds> for PlainClientCreds {
     }
 }
 
+#[derive(Deserialize)]
+struct PlainCredsFields {
+    username: String,
+    password: String,
+}
+
+impl From<PlainCredsFields> for PlainClientCreds {
+    fn from(fields: PlainCredsFields) -> Self {
+        let password = fields.password;
+        secret::lock(&password);
+
+        Self {
+            username: fields.username,
+            password,
+        }
+    }
+}
+
 impl<'a> From<&'a PlainClientCreds> for Mechanism {
     fn from(creds: &'a PlainClientCreds) -> Self {
         Self::from(creds.to_owned())
@@ -153,8 +279,9 @@ impl Default for Mechanism {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub(crate) enum MechanismName {
+/// The name of the mechanism used by a peer during a `ZAP` handshake.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MechanismName {
     Null,
     Plain,
     Curve,
@@ -162,7 +289,8 @@ pub(crate) enum MechanismName {
 
 #[derive(Debug, Fail)]
 #[fail(display = "unsupported mechanism")]
-pub(crate) struct InvalidMechanismName;
+#[doc(hidden)]
+pub struct InvalidMechanismName;
 
 impl<'a> TryFrom<&'a str> for MechanismName {
     type Error = InvalidMechanismName;
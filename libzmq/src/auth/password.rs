@@ -0,0 +1,351 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use failure::Fail;
+use password_hash::{
+    rand_core::OsRng, PasswordHash as PhcHash, PasswordHasher,
+    PasswordVerifier, SaltString,
+};
+use scrypt::Scrypt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+// The algorithms that can verify a `PasswordHash`.
+const ALGORITHMS: &[&str] = &["argon2id", "argon2i", "argon2d", "scrypt"];
+
+/// An error that occurs when a password hash cannot be parsed.
+#[derive(Debug, Fail)]
+#[fail(display = "invalid password hash: {}", msg)]
+pub struct PasswordHashError {
+    msg: String,
+}
+
+impl PasswordHashError {
+    fn new<M>(msg: M) -> Self
+    where
+        M: Into<String>,
+    {
+        Self { msg: msg.into() }
+    }
+
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+}
+
+/// The `argon2id` cost parameters used to hash new passwords.
+///
+/// The `AuthServer` verifies the password of every `PLAIN` handshake on
+/// its own thread, one handshake at a time. The cost of the hashes thus
+/// bounds the rate at which `PLAIN` handshakes are processed. With the
+/// default parameters, each verification fills 19 MiB of memory twice.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::auth::{HashParams, PasswordHash};
+///
+/// // 8 MiB of memory, 1 iteration and 1 lane.
+/// let params = HashParams::new(8 * 1024, 1, 1)?;
+/// let hash = PasswordHash::with_params("pwd", params);
+/// assert!(hash.as_str().starts_with("$argon2id$v=19$m=8192,t=1,p=1$"));
+///
+/// // The parameters must be supported by `argon2`.
+/// assert!(HashParams::new(8 * 1024, 0, 1).is_err());
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "FlatHashParams")]
+pub struct HashParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl HashParams {
+    /// Create new `HashParams` from the memory size in KiB, the number of
+    /// iterations and the degree of parallelism.
+    pub fn new(
+        m_cost: u32,
+        t_cost: u32,
+        p_cost: u32,
+    ) -> Result<Self, PasswordHashError> {
+        Params::new(m_cost, t_cost, p_cost, None)
+            .map_err(|err| PasswordHashError::new(err.to_string()))?;
+
+        Ok(Self {
+            m_cost,
+            t_cost,
+            p_cost,
+        })
+    }
+
+    /// Returns the memory size in KiB.
+    pub fn m_cost(&self) -> u32 {
+        self.m_cost
+    }
+
+    /// Returns the number of iterations.
+    pub fn t_cost(&self) -> u32 {
+        self.t_cost
+    }
+
+    /// Returns the degree of parallelism.
+    pub fn p_cost(&self) -> u32 {
+        self.p_cost
+    }
+
+    fn argon2(self) -> Argon2<'static> {
+        // The params were validated on creation.
+        let params =
+            Params::new(self.m_cost, self.t_cost, self.p_cost, None).unwrap();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+    }
+}
+
+impl Default for HashParams {
+    /// The default parameters of `argon2id`.
+    fn default() -> Self {
+        Self {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FlatHashParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl TryFrom<FlatHashParams> for HashParams {
+    type Error = PasswordHashError;
+
+    fn try_from(flat: FlatHashParams) -> Result<Self, PasswordHashError> {
+        Self::new(flat.m_cost, flat.t_cost, flat.p_cost)
+    }
+}
+
+/// A hash of a `PLAIN` password in the [`PHC string format`].
+///
+/// Hashes produced by the `argon2` and `scrypt` algorithms are supported.
+/// The verification of a password against the hash is done in constant
+/// time.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::auth::PasswordHash;
+///
+/// let hash = PasswordHash::new("pwd");
+/// assert!(hash.as_str().starts_with("$argon2id$"));
+/// assert!(hash.verify("pwd"));
+/// assert!(!hash.verify("other"));
+///
+/// // A hash generated by another tool.
+/// let hash = PasswordHash::from_phc(hash.as_str())?;
+/// assert!(hash.verify("pwd"));
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`PHC string format`]: https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PasswordHash {
+    phc: String,
+}
+
+impl PasswordHash {
+    /// Hash the password using `argon2id` with the default parameters and
+    /// a random salt.
+    pub fn new<S>(password: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self::with_params(password, HashParams::default())
+    }
+
+    /// Hash the password using `argon2id` with the given parameters and
+    /// a random salt.
+    ///
+    /// See [`HashParams`].
+    ///
+    /// [`HashParams`]: struct.HashParams.html
+    pub fn with_params<S>(password: S, params: HashParams) -> Self
+    where
+        S: AsRef<str>,
+    {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = params
+            .argon2()
+            .hash_password(password.as_ref().as_bytes(), &salt)
+            .expect("argon2 hashing with valid params failed");
+
+        Self {
+            phc: hash.to_string(),
+        }
+    }
+
+    /// Create a `PasswordHash` from a string in the `PHC` format.
+    pub fn from_phc<S>(phc: S) -> Result<Self, PasswordHashError>
+    where
+        S: Into<String>,
+    {
+        let phc = phc.into();
+        {
+            let hash = PhcHash::new(&phc)
+                .map_err(|err| PasswordHashError::new(err.to_string()))?;
+
+            if !ALGORITHMS.contains(&hash.algorithm.as_str()) {
+                return Err(PasswordHashError::new(format!(
+                    "unsupported algorithm {}",
+                    hash.algorithm
+                )));
+            }
+            if hash.hash.is_none() {
+                return Err(PasswordHashError::new("missing hash output"));
+            }
+        }
+
+        Ok(Self { phc })
+    }
+
+    /// Returns the hash in the `PHC` format.
+    pub fn as_str(&self) -> &str {
+        &self.phc
+    }
+
+    /// Returns `true` if the password matches the hash.
+    pub fn verify<S>(&self, password: S) -> bool
+    where
+        S: AsRef<str>,
+    {
+        // The string was validated on creation.
+        let hash = PhcHash::new(&self.phc).unwrap();
+        let verifiers: [&dyn PasswordVerifier; 2] =
+            [&Argon2::default(), &Scrypt];
+
+        hash.verify_password(&verifiers, password.as_ref()).is_ok()
+    }
+}
+
+impl fmt::Display for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.phc)
+    }
+}
+
+impl FromStr for PasswordHash {
+    type Err = PasswordHashError;
+
+    fn from_str(s: &str) -> Result<Self, PasswordHashError> {
+        Self::from_phc(s)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for PasswordHash {
+    type Error = PasswordHashError;
+
+    fn try_from(s: &'a str) -> Result<Self, PasswordHashError> {
+        Self::from_phc(s)
+    }
+}
+
+impl TryFrom<String> for PasswordHash {
+    type Error = PasswordHashError;
+
+    fn try_from(s: String) -> Result<Self, PasswordHashError> {
+        Self::from_phc(s)
+    }
+}
+
+impl<'a> From<&'a PasswordHash> for PasswordHash {
+    fn from(hash: &'a PasswordHash) -> Self {
+        hash.to_owned()
+    }
+}
+
+impl Serialize for PasswordHash {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.phc)
+    }
+}
+
+impl<'de> Deserialize<'de> for PasswordHash {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Self::from_phc(s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let params = HashParams::new(Params::MIN_M_COST, 1, 1).unwrap();
+        let hash = PasswordHash::with_params("pwd", params);
+        assert!(hash.verify("pwd"));
+        assert!(!hash.verify("pwd "));
+        // The salt is random.
+        assert_ne!(hash, PasswordHash::with_params("pwd", params));
+
+        let params = scrypt::Params::new(10, 8, 1, 32).unwrap();
+        let salt = SaltString::generate(&mut OsRng);
+        let scrypt = Scrypt
+            .hash_password_customized(b"pwd", None, None, params, &salt)
+            .unwrap()
+            .to_string();
+        let hash = PasswordHash::from_phc(scrypt).unwrap();
+        assert!(hash.verify("pwd"));
+        assert!(!hash.verify("other"));
+    }
+
+    #[test]
+    fn test_with_params() {
+        let params = HashParams::new(Params::MIN_M_COST, 1, 1).unwrap();
+        let hash = PasswordHash::with_params("pwd", params);
+        assert!(hash.as_str().starts_with("$argon2id$v=19$m=8,t=1,p=1$"));
+        assert!(hash.verify("pwd"));
+        assert!(!hash.verify("other"));
+
+        let yaml = serde_yaml::to_string(&params).unwrap();
+        let de: HashParams = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(params, de);
+
+        let yaml = "m_cost: 8\nt_cost: 0\np_cost: 1\n";
+        assert!(serde_yaml::from_str::<HashParams>(yaml).is_err());
+    }
+
+    #[test]
+    fn test_from_phc() {
+        assert!(PasswordHash::from_phc("pwd").is_err());
+        assert!(PasswordHash::from_phc("$md5$abc").is_err());
+        assert!(PasswordHash::from_phc(
+            "$argon2id$v=19$m=19456,t=2,p=1$c29tZXNhbHQ"
+        )
+        .is_err());
+
+        let params = HashParams::new(Params::MIN_M_COST, 1, 1).unwrap();
+        let hash = PasswordHash::with_params("pwd", params);
+        let yaml = serde_yaml::to_string(&hash).unwrap();
+        let de: PasswordHash = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(hash, de);
+    }
+}
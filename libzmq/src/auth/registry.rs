@@ -0,0 +1,516 @@
+use super::*;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::{collections::BTreeMap, convert::TryFrom, fmt, mem, option};
+
+/// The password of a [`PlainRegistryEntry`].
+///
+/// A cleartext password is wiped from memory when dropped and is redacted
+/// from the `Debug` output.
+///
+/// [`PlainRegistryEntry`]: struct.PlainRegistryEntry.html
+#[derive(PartialEq, Eq, Hash)]
+pub enum PlainPassword {
+    /// A password in cleartext.
+    ///
+    /// The `AuthServer` hashes the password with its [`HashParams`] as soon
+    /// as it receives the entry.
+    ///
+    /// [`HashParams`]: struct.HashParams.html
+    Clear(String),
+    /// A hash of the password.
+    Hash(PasswordHash),
+}
+
+impl PlainPassword {
+    /// Returns `true` if the password matches.
+    pub fn verify<S>(&self, password: S) -> bool
+    where
+        S: AsRef<str>,
+    {
+        match self {
+            PlainPassword::Clear(clear) => {
+                // Compares every byte to avoid leaking the length of the
+                // common prefix.
+                let (a, b) = (clear.as_bytes(), password.as_ref().as_bytes());
+                a.len() == b.len()
+                    && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+            }
+            PlainPassword::Hash(hash) => hash.verify(password),
+        }
+    }
+}
+
+impl fmt::Debug for PlainPassword {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlainPassword::Clear(_) => {
+                f.debug_tuple("Clear").field(&secret::REDACTED).finish()
+            }
+            PlainPassword::Hash(hash) => {
+                f.debug_tuple("Hash").field(hash).finish()
+            }
+        }
+    }
+}
+
+impl Clone for PlainPassword {
+    fn clone(&self) -> Self {
+        match self {
+            PlainPassword::Clear(password) => {
+                let password = password.to_owned();
+                secret::lock(&password);
+                PlainPassword::Clear(password)
+            }
+            PlainPassword::Hash(hash) => PlainPassword::Hash(hash.to_owned()),
+        }
+    }
+}
+
+impl Drop for PlainPassword {
+    fn drop(&mut self) {
+        if let PlainPassword::Clear(password) = self {
+            secret::wipe(password);
+        }
+    }
+}
+
+impl From<PasswordHash> for PlainPassword {
+    fn from(hash: PasswordHash) -> Self {
+        PlainPassword::Hash(hash)
+    }
+}
+
+/// An entry of the `AuthServer`'s `PLAIN` registry.
+///
+/// On a successful authentication, the peer is assigned the entry's user
+/// id, which defaults to the username, as well as its metadata properties.
+/// Both can then be read from the messages received from the peer via
+/// [`Msg::user_id`] and [`Msg::property`].
+///
+/// The password is either given in cleartext or as a [`PasswordHash`].
+/// Either way, the `AuthServer` only stores the hash, so that the
+/// configuration snapshots never contain cleartext passwords. In human
+/// readable formats, a hash is stored under `password_hash` instead of
+/// `password`.
+///
+/// # Example
+/// ```
+/// use libzmq::auth::*;
+///
+/// let creds = PlainClientCreds::new("user", "pass");
+/// let entry = PlainRegistryEntry::new(creds)
+///     .with_user_id("user@tenant")
+///     .add_metadata("Tenant", "tenant")
+///     .add_metadata("Role", "admin");
+///
+/// // The entry can be hashed before being written to a config file.
+/// let entry = entry.hashed();
+/// assert!(entry.password().verify("pass"));
+/// ```
+///
+/// [`Msg::user_id`]: ../struct.Msg.html#method.user_id
+/// [`Msg::property`]: ../struct.Msg.html#method.property
+/// [`PasswordHash`]: struct.PasswordHash.html
+// We can't derive and use #[serde(flatten)] since it is not supported
+// by `bincode`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "FlatPlainRegistryEntry")]
+#[serde(try_from = "FlatPlainRegistryEntry")]
+pub struct PlainRegistryEntry {
+    pub(crate) username: String,
+    pub(crate) password: PlainPassword,
+    pub(crate) user_id: Option<String>,
+    pub(crate) metadata: BTreeMap<String, String>,
+}
+
+impl PlainRegistryEntry {
+    /// Create a new `PlainRegistryEntry` from the credentials.
+    pub fn new<C>(creds: C) -> Self
+    where
+        C: Into<PlainClientCreds>,
+    {
+        let mut creds = creds.into();
+        // The password remains locked.
+        let password = creds.take_password();
+        Self {
+            username: creds.username.clone(),
+            password: PlainPassword::Clear(password),
+            user_id: None,
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Create a new `PlainRegistryEntry` from the username and the hash of
+    /// the password.
+    pub fn with_hash<U, H>(username: U, hash: H) -> Self
+    where
+        U: Into<String>,
+        H: Into<PasswordHash>,
+    {
+        Self {
+            username: username.into(),
+            password: PlainPassword::Hash(hash.into()),
+            user_id: None,
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Set the user id assigned to the peer instead of the username.
+    pub fn with_user_id<U>(mut self, user_id: U) -> Self
+    where
+        U: Into<String>,
+    {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Add a metadata property assigned to the peer.
+    pub fn add_metadata<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.metadata.insert(name.into(), value.into());
+        self
+    }
+
+    /// Hash the password with the default parameters if it is in
+    /// cleartext.
+    pub fn hashed(self) -> Self {
+        self.hashed_with(HashParams::default())
+    }
+
+    /// Hash the password with the given parameters if it is in cleartext.
+    ///
+    /// See [`HashParams`].
+    ///
+    /// [`HashParams`]: struct.HashParams.html
+    pub fn hashed_with(mut self, params: HashParams) -> Self {
+        if let PlainPassword::Clear(ref password) = self.password {
+            let hash = PasswordHash::with_params(password, params);
+            self.password = PlainPassword::Hash(hash);
+        }
+        self
+    }
+
+    /// Returns the username.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Returns a reference to the password.
+    pub fn password(&self) -> &PlainPassword {
+        &self.password
+    }
+
+    /// Returns the user id assigned to the peer.
+    pub fn user_id(&self) -> &str {
+        self.user_id.as_deref().unwrap_or(&self.username)
+    }
+
+    /// Returns the metadata properties assigned to the peer.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+}
+
+impl From<PlainClientCreds> for PlainRegistryEntry {
+    fn from(creds: PlainClientCreds) -> Self {
+        Self::new(creds)
+    }
+}
+
+impl<'a> From<&'a PlainClientCreds> for PlainRegistryEntry {
+    fn from(creds: &'a PlainClientCreds) -> Self {
+        Self::new(creds)
+    }
+}
+
+impl<'a> From<&'a PlainRegistryEntry> for PlainRegistryEntry {
+    fn from(entry: &'a PlainRegistryEntry) -> Self {
+        entry.to_owned()
+    }
+}
+
+impl IntoIterator for PlainRegistryEntry {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+impl IntoIterator for &PlainRegistryEntry {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct FlatPlainRegistryEntry {
+    username: String,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    password_hash: Option<PasswordHash>,
+    #[serde(default)]
+    user_id: Option<String>,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+}
+
+impl Drop for FlatPlainRegistryEntry {
+    fn drop(&mut self) {
+        if let Some(password) = &mut self.password {
+            secret::wipe(password);
+        }
+    }
+}
+
+impl From<PlainRegistryEntry> for FlatPlainRegistryEntry {
+    fn from(entry: PlainRegistryEntry) -> Self {
+        let (password, password_hash) = match &entry.password {
+            PlainPassword::Clear(password) => (Some(password.to_owned()), None),
+            PlainPassword::Hash(hash) => (None, Some(hash.to_owned())),
+        };
+
+        Self {
+            username: entry.username,
+            password,
+            password_hash,
+            user_id: entry.user_id,
+            metadata: entry.metadata,
+        }
+    }
+}
+
+impl TryFrom<FlatPlainRegistryEntry> for PlainRegistryEntry {
+    type Error = &'static str;
+
+    fn try_from(
+        mut flat: FlatPlainRegistryEntry,
+    ) -> Result<Self, &'static str> {
+        let password = match (flat.password.take(), flat.password_hash.take()) {
+            (Some(password), None) => {
+                secret::lock(&password);
+                PlainPassword::Clear(password)
+            }
+            (None, Some(hash)) => PlainPassword::Hash(hash),
+            _ => return Err("expected either a password or a password_hash"),
+        };
+
+        Ok(Self {
+            username: mem::take(&mut flat.username),
+            password,
+            user_id: flat.user_id.take(),
+            metadata: mem::take(&mut flat.metadata),
+        })
+    }
+}
+
+/// An entry of the `AuthServer`'s `CURVE` registry.
+///
+/// On a successful authentication, the peer is assigned the entry's user
+/// id, which defaults to the public key, as well as its metadata properties.
+/// Both can then be read from the messages received from the peer via
+/// [`Msg::user_id`] and [`Msg::property`].
+///
+/// In human readable formats, an entry without a user id nor metadata is
+/// serialized as its public key.
+///
+/// # Example
+/// ```
+/// use libzmq::auth::*;
+///
+/// let cert = CurveCert::new_unique();
+/// let entry = CurveRegistryEntry::new(cert.public())
+///     .with_user_id("service-a")
+///     .add_metadata("Role", "worker");
+/// ```
+///
+/// [`Msg::user_id`]: ../struct.Msg.html#method.user_id
+/// [`Msg::property`]: ../struct.Msg.html#method.property
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CurveRegistryEntry {
+    pub(crate) public_key: CurvePublicKey,
+    pub(crate) user_id: Option<String>,
+    pub(crate) metadata: BTreeMap<String, String>,
+}
+
+impl CurveRegistryEntry {
+    /// Create a new `CurveRegistryEntry` from the public key.
+    pub fn new<K>(public_key: K) -> Self
+    where
+        K: Into<CurvePublicKey>,
+    {
+        Self {
+            public_key: public_key.into(),
+            user_id: None,
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Set the user id assigned to the peer instead of the public key.
+    pub fn with_user_id<U>(mut self, user_id: U) -> Self
+    where
+        U: Into<String>,
+    {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Add a metadata property assigned to the peer.
+    pub fn add_metadata<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.metadata.insert(name.into(), value.into());
+        self
+    }
+
+    /// Returns a reference to the public key.
+    pub fn public_key(&self) -> &CurvePublicKey {
+        &self.public_key
+    }
+
+    /// Returns the user id assigned to the peer.
+    pub fn user_id(&self) -> &str {
+        self.user_id
+            .as_deref()
+            .unwrap_or_else(|| self.public_key.as_str())
+    }
+
+    /// Returns the metadata properties assigned to the peer.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+}
+
+impl From<CurvePublicKey> for CurveRegistryEntry {
+    fn from(public_key: CurvePublicKey) -> Self {
+        Self::new(public_key)
+    }
+}
+
+impl<'a> From<&'a CurvePublicKey> for CurveRegistryEntry {
+    fn from(public_key: &'a CurvePublicKey) -> Self {
+        Self::new(public_key)
+    }
+}
+
+impl<'a> From<&'a CurveRegistryEntry> for CurveRegistryEntry {
+    fn from(entry: &'a CurveRegistryEntry) -> Self {
+        entry.to_owned()
+    }
+}
+
+impl IntoIterator for CurveRegistryEntry {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+impl IntoIterator for &CurveRegistryEntry {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+impl Serialize for CurveRegistryEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            FlatCurveRegistryEntry::from(self.to_owned()).serialize(serializer)
+        } else {
+            CurveRegistryFields::from(self.to_owned()).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CurveRegistryEntry {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            FlatCurveRegistryEntry::deserialize(deserializer).map(Self::from)
+        } else {
+            CurveRegistryFields::deserialize(deserializer).map(Self::from)
+        }
+    }
+}
+
+// The representation used by non self-describing formats such as `bincode`,
+// which do not support untagged enums.
+#[derive(Clone, Serialize, Deserialize)]
+struct CurveRegistryFields {
+    public_key: CurvePublicKey,
+    #[serde(default)]
+    user_id: Option<String>,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+}
+
+impl From<CurveRegistryEntry> for CurveRegistryFields {
+    fn from(entry: CurveRegistryEntry) -> Self {
+        Self {
+            public_key: entry.public_key,
+            user_id: entry.user_id,
+            metadata: entry.metadata,
+        }
+    }
+}
+
+impl From<CurveRegistryFields> for CurveRegistryEntry {
+    fn from(fields: CurveRegistryFields) -> Self {
+        Self {
+            public_key: fields.public_key,
+            user_id: fields.user_id,
+            metadata: fields.metadata,
+        }
+    }
+}
+
+// Allows a bare public key to be used in config files.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum FlatCurveRegistryEntry {
+    PublicKey(CurvePublicKey),
+    Entry(CurveRegistryFields),
+}
+
+impl From<CurveRegistryEntry> for FlatCurveRegistryEntry {
+    fn from(entry: CurveRegistryEntry) -> Self {
+        if entry.user_id.is_none() && entry.metadata.is_empty() {
+            FlatCurveRegistryEntry::PublicKey(entry.public_key)
+        } else {
+            FlatCurveRegistryEntry::Entry(entry.into())
+        }
+    }
+}
+
+impl From<FlatCurveRegistryEntry> for CurveRegistryEntry {
+    fn from(flat: FlatCurveRegistryEntry) -> Self {
+        match flat {
+            FlatCurveRegistryEntry::PublicKey(public_key) => {
+                Self::new(public_key)
+            }
+            FlatCurveRegistryEntry::Entry(fields) => fields.into(),
+        }
+    }
+}
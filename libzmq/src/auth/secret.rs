@@ -0,0 +1,191 @@
+// Limits the exposure of the secrets held in memory, such as `CURVE`
+// secret keys and `PLAIN` passwords.
+//
+// Secrets are always wiped when dropped. With the `mlock` feature, their
+// memory pages are also locked so that they are never swapped to disk and,
+// on Linux, excluded from core dumps, until the last secret they contain
+// is wiped.
+
+use zeroize::Zeroize;
+
+#[cfg(all(unix, feature = "mlock"))]
+mod imp {
+    use lazy_static::lazy_static;
+    use log::debug;
+
+    use std::{
+        collections::{hash_map::Entry, HashMap},
+        io,
+        sync::Mutex,
+    };
+
+    // Memory is locked per page, so a page can contain several secrets.
+    // It is only unlocked once none of its secrets remain.
+    #[derive(Default)]
+    pub(super) struct Locks {
+        // The length of each locked secret, by address.
+        pub(super) secrets: HashMap<usize, usize>,
+        // The number of locked secrets in each page, by address.
+        pub(super) pages: HashMap<usize, usize>,
+    }
+
+    lazy_static! {
+        pub(super) static ref LOCKS: Mutex<Locks> = Mutex::default();
+    }
+
+    fn page_size() -> usize {
+        unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+    }
+
+    // Returns the address of the pages that contain the buffer.
+    pub(super) fn pages(ptr: usize, len: usize) -> impl Iterator<Item = usize> {
+        let page = page_size();
+        let start = ptr & !(page - 1);
+
+        (start..ptr + len).step_by(page)
+    }
+
+    fn lock_page(page: usize) {
+        let addr = page as *mut libc::c_void;
+        if unsafe { libc::mlock(addr, page_size()) } == -1 {
+            // Usually caused by `RLIMIT_MEMLOCK`, in which case the secret
+            // is still wiped on drop.
+            debug!("unable to lock secret: {}", io::Error::last_os_error());
+        }
+
+        #[cfg(target_os = "linux")]
+        unsafe {
+            libc::madvise(addr, page_size(), libc::MADV_DONTDUMP);
+        }
+    }
+
+    fn unlock_page(page: usize) {
+        let addr = page as *mut libc::c_void;
+        unsafe {
+            libc::munlock(addr, page_size());
+        }
+
+        #[cfg(target_os = "linux")]
+        unsafe {
+            libc::madvise(addr, page_size(), libc::MADV_DODUMP);
+        }
+    }
+
+    pub(super) fn lock(ptr: *const u8, len: usize) {
+        let mut locks = LOCKS.lock().unwrap();
+        let Locks {
+            secrets,
+            pages: counts,
+        } = &mut *locks;
+
+        if let Entry::Vacant(entry) = secrets.entry(ptr as usize) {
+            entry.insert(len);
+            for page in pages(ptr as usize, len) {
+                let count = counts.entry(page).or_insert(0);
+                *count += 1;
+                if *count == 1 {
+                    lock_page(page);
+                }
+            }
+        }
+    }
+
+    // Only the secrets that were locked are unlocked.
+    pub(super) fn unlock(ptr: *const u8) {
+        let mut locks = LOCKS.lock().unwrap();
+        let Locks {
+            secrets,
+            pages: counts,
+        } = &mut *locks;
+
+        let len = match secrets.remove(&(ptr as usize)) {
+            Some(len) => len,
+            None => return,
+        };
+
+        for page in pages(ptr as usize, len) {
+            if let Entry::Occupied(mut entry) = counts.entry(page) {
+                *entry.get_mut() -= 1;
+                if *entry.get() == 0 {
+                    entry.remove();
+                    unlock_page(page);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(all(unix, feature = "mlock")))]
+mod imp {
+    pub(super) fn lock(_ptr: *const u8, _len: usize) {}
+
+    pub(super) fn unlock(_ptr: *const u8) {}
+}
+
+// Locks the memory of the secret, if the `mlock` feature is enabled.
+//
+// The secret must not be reallocated until it is wiped.
+pub(crate) fn lock(secret: &str) {
+    if !secret.is_empty() {
+        imp::lock(secret.as_ptr(), secret.len());
+    }
+}
+
+// Wipes the secret, then unlocks its memory if it was locked.
+pub(crate) fn wipe(secret: &mut String) {
+    let ptr = secret.as_ptr();
+    secret.zeroize();
+    imp::unlock(ptr);
+}
+
+// Wipes a binary secret, which is never locked.
+pub(crate) fn wipe_bytes(secret: &mut Vec<u8>) {
+    secret.zeroize();
+}
+
+// The placeholder used instead of secrets by `Debug` implementations.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lock_wipe() {
+        let mut secret = "hunter2".to_owned();
+        lock(&secret);
+        wipe(&mut secret);
+        assert!(secret.is_empty());
+
+        let mut secret = String::new();
+        lock(&secret);
+        wipe(&mut secret);
+
+        let mut bytes = vec![1, 2, 3];
+        wipe_bytes(&mut bytes);
+        assert!(bytes.is_empty());
+    }
+
+    #[cfg(all(unix, feature = "mlock"))]
+    #[test]
+    fn test_shared_page() {
+        let buf = [0u8; 16];
+        let (first, second) = (buf.as_ptr(), buf[8..].as_ptr());
+        let page = imp::pages(first as usize, 1).next().unwrap();
+        let count = || imp::LOCKS.lock().unwrap().pages.get(&page).cloned();
+
+        imp::lock(first, 8);
+        imp::lock(second, 8);
+
+        // The page remains locked as long as one of its secrets does.
+        imp::unlock(first);
+        assert!(count().is_some());
+
+        // Secrets that were never locked are not unlocked.
+        imp::unlock(first);
+        assert!(count().is_some());
+
+        imp::unlock(second);
+        assert!(count().is_none());
+    }
+}
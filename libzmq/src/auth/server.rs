@@ -1,16 +1,24 @@
-use super::{client::*, *};
-use crate::{old::*, poll::*, prelude::*, socket::*, *};
+use super::{
+    authenticator::*,
+    ban::{map_addr, BanTracker},
+    client::*,
+    event::AUTH_EVENT_GROUP,
+    *,
+};
+use crate::{addr::Subnet, old::*, poll::*, prelude::*, socket::*, *};
 
 use failure::Fail;
 use lazy_static::lazy_static;
-use log::info;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
 
 use libc::c_long;
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     fmt,
     net::Ipv6Addr,
+    time::Instant,
     vec,
 };
 
@@ -19,11 +27,14 @@ lazy_static! {
     static ref ZAP_ENDPOINT: InprocAddr = "zeromq.zap.01".try_into().unwrap();
     pub(crate) static ref COMMAND_ENDPOINT: InprocAddr =
         InprocAddr::new_unique();
-    static ref AUTH_EVENT_ENDPOINT: InprocAddr = InprocAddr::new_unique();
+    pub(crate) static ref WORKER_ENDPOINT: InprocAddr =
+        InprocAddr::new_unique();
+    pub(crate) static ref AUTH_EVENT_ENDPOINT: InprocAddr =
+        InprocAddr::new_unique();
 }
 
 /// The possible status code resulting from a `ZAP` handshake.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum StatusCode {
     Allowed = 200,
     TemporaryError = 300,
@@ -90,40 +101,93 @@ impl<'a> TryFrom<&'a [u8]> for StatusCode {
     }
 }
 
-#[derive(Clone, Debug)]
-struct ZapRequest {
-    version: String,
-    request_id: Msg,
-    domain: String,
-    addr: Ipv6Addr,
-    identity: Msg,
-    mechanism: String,
-    credentials: Vec<Msg>,
+// A `ZAP` request parsed from its frames.
+enum ParsedRequest {
+    // The mechanism is not supported.
+    Unsupported,
+    // The `PLAIN` credentials are not valid UTF-8, in which case the
+    // request holds the lossy username and an empty password.
+    Invalid(ZapRequest),
+    Valid(ZapRequest),
 }
 
-impl ZapRequest {
-    fn new(mut parts: Vec<Msg>) -> Self {
-        let version = parts.remove(0).to_str().unwrap().to_owned();
-        assert_eq!(version, ZAP_VERSION);
-
-        let request_id = parts.remove(0);
-        let domain = parts.remove(0).to_str().unwrap().to_owned();
-        let addr: Ipv6Addr = parts.remove(0).to_str().unwrap().parse().unwrap();
+// Parses the frames of a `ZAP` request into its request id and the typed
+// request.
+fn parse_zap_request(mut parts: Vec<Msg>) -> (Msg, ParsedRequest) {
+    let version = parts.remove(0).to_str().unwrap().to_owned();
+    assert_eq!(version, ZAP_VERSION);
+
+    let request_id = parts.remove(0);
+    let domain = parts.remove(0).to_str().unwrap().to_owned();
+    let addr: Option<Ipv6Addr> = parts.remove(0).to_str().unwrap().parse().ok();
+
+    let identity = parts.remove(0).as_bytes().to_owned();
+
+    let mechanism = parts.remove(0);
+    let mut valid = true;
+    let credentials = match MechanismName::try_from(mechanism.to_str().unwrap())
+    {
+        Ok(MechanismName::Null) => Credentials::Null,
+        Ok(MechanismName::Plain) => {
+            // The credentials are arbitrary bytes sent by the peer.
+            let username = parts.remove(0);
+            let password = parts.remove(0);
+            let creds = match (username.to_str(), password.to_str()) {
+                (Ok(username), Ok(password)) => PlainClientCreds {
+                    username: username.to_owned(),
+                    password: password.to_owned(),
+                },
+                _ => {
+                    valid = false;
+                    let username = String::from_utf8_lossy(username.as_bytes());
+                    PlainClientCreds {
+                        username: username.into_owned(),
+                        password: String::new(),
+                    }
+                }
+            };
 
-        let identity = parts.remove(0);
+            Credentials::Plain(creds)
+        }
+        Ok(MechanismName::Curve) => {
+            let bin_public_key = BinCurveKey::new_unchecked(
+                parts.remove(0).as_bytes().to_owned(),
+            );
 
-        let mechanism = parts.remove(0).to_str().unwrap().to_owned();
+            Credentials::Curve(bin_public_key.into())
+        }
+        Err(_) => return (request_id, ParsedRequest::Unsupported),
+    };
+
+    let request = ZapRequest {
+        domain,
+        addr,
+        identity,
+        credentials,
+    };
+
+    if valid {
+        (request_id, ParsedRequest::Valid(request))
+    } else {
+        (request_id, ParsedRequest::Invalid(request))
+    }
+}
 
-        Self {
-            version,
-            request_id,
-            domain,
-            addr,
-            identity,
-            mechanism,
-            credentials: parts,
+// Encodes the metadata properties in the ZMTP format.
+fn encode_metadata(metadata: &BTreeMap<String, String>) -> Vec<u8> {
+    let mut bytes = vec![];
+    for (name, value) in metadata {
+        if name.is_empty() || name.len() > u8::MAX as usize {
+            error!("ignored invalid metadata name: {:?}", name);
+            continue;
         }
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(value.as_bytes());
     }
+
+    bytes
 }
 
 #[derive(Clone, Debug)]
@@ -153,9 +217,57 @@ impl IntoIterator for ZapReply {
     }
 }
 
-struct AuthResult {
-    user_id: String,
-    metadata: Vec<u8>,
+impl ZapReply {
+    fn new(request_id: Msg, verdict: &Verdict) -> Self {
+        Self {
+            version: ZAP_VERSION.to_owned(),
+            request_id,
+            status_code: verdict.status_code(),
+            status_text: verdict.status_text().to_owned(),
+            user_id: verdict.user_id().to_owned(),
+            metadata: encode_metadata(verdict.metadata()),
+        }
+    }
+}
+
+// A ZAP request awaiting the verdict of an authenticator worker.
+struct PendingRequest {
+    routing_id: Msg,
+    request_id: Msg,
+    request: ZapRequest,
+}
+
+// The authentication policy of a `ZAP` domain.
+struct DomainPolicy {
+    whitelist: HashSet<Subnet>,
+    blacklist: HashSet<Subnet>,
+    // The passwords are always hashed.
+    plain_registry: HashMap<String, PlainRegistryEntry>,
+    // The parameters used to hash the cleartext passwords.
+    hash_params: HashParams,
+    // Verified against when the user is unknown, so that the response time
+    // does not reveal which users exist. Lazily hashed with `hash_params`.
+    dummy_hash: Option<PasswordHash>,
+    // Allowed public client keys.
+    curve_registry: HashMap<CurvePublicKey, CurveRegistryEntry>,
+    // Whether curve auth is enabled.
+    curve_auth: bool,
+    bans: BanTracker,
+}
+
+impl Default for DomainPolicy {
+    fn default() -> Self {
+        Self {
+            whitelist: HashSet::default(),
+            blacklist: HashSet::default(),
+            plain_registry: HashMap::default(),
+            hash_params: HashParams::default(),
+            dummy_hash: None,
+            curve_registry: HashMap::default(),
+            curve_auth: true,
+            bans: BanTracker::default(),
+        }
+    }
 }
 
 // A configurable ZAP handler.
@@ -163,13 +275,15 @@ pub(crate) struct AuthServer {
     //  ZAP handler socket
     handler: OldSocket,
     request: Server,
-    whitelist: HashSet<Ipv6Addr>,
-    blacklist: HashSet<Ipv6Addr>,
-    plain_registry: HashMap<String, String>,
-    // Allowed public client keys.
-    curve_registry: HashSet<CurvePublicKey>,
-    // Whether curve auth is enabled.
-    curve_auth: bool,
+    // Socket used to dispatch ZAP requests to the authenticator workers.
+    worker: Server,
+    workers: VecDeque<RoutingId>,
+    // Socket used to publish the `AuthEvent`s.
+    events: Radio,
+    pending: HashMap<u64, PendingRequest>,
+    next_pending: u64,
+    // The policies indexed by `ZAP` domain.
+    policies: HashMap<String, DomainPolicy>,
 }
 
 impl AuthServer {
@@ -180,14 +294,21 @@ impl AuthServer {
         let request = Server::with_ctx(handle)?;
         request.bind(&*COMMAND_ENDPOINT).map_err(Error::cast)?;
 
+        let worker = Server::with_ctx(handle)?;
+        worker.bind(&*WORKER_ENDPOINT).map_err(Error::cast)?;
+
+        let events = Radio::with_ctx(handle)?;
+        events.bind(&*AUTH_EVENT_ENDPOINT).map_err(Error::cast)?;
+
         Ok(AuthServer {
             handler,
             request,
-            whitelist: HashSet::default(),
-            blacklist: HashSet::default(),
-            plain_registry: HashMap::default(),
-            curve_registry: HashSet::default(),
-            curve_auth: true,
+            worker,
+            workers: VecDeque::default(),
+            events,
+            pending: HashMap::default(),
+            next_pending: 0,
+            policies: HashMap::default(),
         })
     }
 
@@ -195,6 +316,7 @@ impl AuthServer {
         let mut poller = Poller::new();
         poller.add(&self.handler, PollId(0), READABLE)?;
         poller.add(&self.request, PollId(1), READABLE)?;
+        poller.add(&self.worker, PollId(2), READABLE)?;
 
         let mut events = Events::new();
 
@@ -208,113 +330,239 @@ impl AuthServer {
                         let routing_id = parts.remove(0);
                         assert!(parts.remove(0).is_empty());
 
-                        let request = ZapRequest::new(parts);
-                        let reply = self.on_zap(request)?;
+                        let (request_id, request) = parse_zap_request(parts);
 
-                        self.handler.send(routing_id, true)?;
-                        self.handler.send("", true)?;
-                        self.handler.send_multipart(reply)?;
+                        if let Some(reply) = self.on_zap(
+                            routing_id.clone(),
+                            request_id,
+                            request,
+                        )? {
+                            self.send_zap_reply(routing_id, reply)?;
+                        }
                     }
                     PollId(1) => {
                         let msg = self.request.recv_msg()?;
                         let id = msg.routing_id().unwrap();
-                        let request: AuthRequest =
+                        let (domain, request): (String, AuthRequest) =
                             bincode::deserialize(msg.as_bytes()).unwrap();
 
-                        let reply = self.on_request(request);
+                        let reply = self.on_request(domain, request);
                         let ser = bincode::serialize(&reply).unwrap();
 
                         self.request.route(ser, id).map_err(Error::cast)?;
                     }
+                    PollId(2) => {
+                        let msg = self.worker.recv_msg()?;
+                        let id = msg.routing_id().unwrap();
+                        let request: WorkerRequest =
+                            bincode::deserialize(msg.as_bytes()).unwrap();
+
+                        self.on_worker(request, id)?;
+                    }
                     _ => unreachable!(),
                 }
             }
         }
     }
 
-    fn on_request(&mut self, request: AuthRequest) -> AuthReply {
+    fn send_zap_reply(
+        &mut self,
+        routing_id: Msg,
+        reply: ZapReply,
+    ) -> Result<(), Error> {
+        self.handler.send(routing_id, true)?;
+        self.handler.send("", true)?;
+        self.handler.send_multipart(reply)
+    }
+
+    fn route_worker(
+        &self,
+        reply: &WorkerReply,
+        id: RoutingId,
+    ) -> Result<(), Error> {
+        let ser = bincode::serialize(reply).unwrap();
+        self.worker.route(ser, id).map_err(Error::cast)
+    }
+
+    // Dispatches the request to the next reachable worker. Unreachable
+    // workers are dropped. Returns the request if no worker is left.
+    fn dispatch(
+        &mut self,
+        routing_id: Msg,
+        request_id: Msg,
+        request: ZapRequest,
+    ) -> Option<PendingRequest> {
+        let pending = PendingRequest {
+            routing_id,
+            request_id,
+            request,
+        };
+
+        while let Some(id) = self.workers.pop_front() {
+            let pending_id = self.next_pending;
+            self.next_pending = self.next_pending.wrapping_add(1);
+
+            let reply =
+                WorkerReply::Authenticate(pending_id, pending.request.clone());
+            match self.route_worker(&reply, id) {
+                Ok(()) => {
+                    self.workers.push_back(id);
+                    self.pending.insert(pending_id, pending);
+                    return None;
+                }
+                Err(err) => {
+                    info!("dropped authenticator worker: {}", err);
+                }
+            }
+        }
+
+        Some(pending)
+    }
+
+    fn on_worker(
+        &mut self,
+        request: WorkerRequest,
+        id: RoutingId,
+    ) -> Result<(), Error> {
+        match request {
+            WorkerRequest::Register => {
+                info!("registered authenticator worker");
+                self.workers.push_back(id);
+                self.route_worker(&WorkerReply::Registered, id)
+            }
+            WorkerRequest::Verdict(pending_id, verdict) => {
+                if let Some(pending) = self.pending.remove(&pending_id) {
+                    info!(
+                        "authenticator returned status code {}",
+                        verdict.status_code()
+                    );
+                    let reason = if verdict.status_code() == StatusCode::Allowed
+                    {
+                        None
+                    } else {
+                        Some(DenyReason::Authenticator)
+                    };
+                    self.on_verdict(&pending.request, &verdict, reason)?;
+
+                    let reply = ZapReply::new(pending.request_id, &verdict);
+                    self.send_zap_reply(pending.routing_id, reply)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn on_request(
+        &mut self,
+        domain: String,
+        request: AuthRequest,
+    ) -> AuthReply {
+        // The authenticator is shared by all domains.
+        if let AuthRequest::ClearAuthenticator = request {
+            info!("cleared authenticator");
+            for id in self.workers.drain(..) {
+                let ser = bincode::serialize(&WorkerReply::Stop).unwrap();
+                // The worker might already be gone.
+                let _ = self.worker.route(ser, id);
+            }
+
+            return AuthReply::Success;
+        }
+
+        let policy = self.policies.entry(domain.clone()).or_default();
+
         match request {
             AuthRequest::AddWhitelist(ip) => {
-                info!("added IP : {} to whitelist", &ip);
-                self.whitelist.insert(ip);
+                info!("added network: {} to whitelist", &ip);
+                policy.whitelist.insert(ip);
 
                 AuthReply::Success
             }
             AuthRequest::RemoveWhitelist(ip) => {
-                info!("remove IP : {} to whitelist", &ip);
-                self.whitelist.remove(&ip);
+                info!("remove network: {} to whitelist", &ip);
+                policy.whitelist.remove(&ip);
 
                 AuthReply::Success
             }
             AuthRequest::SetWhitelist(ips) => {
                 info!("reset whitelist");
-                self.whitelist.clear();
-                info!("added IPs: {:#?} to whitelist", &ips);
-                self.whitelist.extend(ips);
+                policy.whitelist.clear();
+                info!("added networks: {:#?} to whitelist", &ips);
+                policy.whitelist.extend(ips);
 
                 AuthReply::Success
             }
             AuthRequest::AddBlacklist(ip) => {
-                info!("added IP : {} to blacklist", &ip);
-                self.blacklist.insert(ip);
+                info!("added network: {} to blacklist", &ip);
+                policy.blacklist.insert(ip);
 
                 AuthReply::Success
             }
             AuthRequest::RemoveBlacklist(ip) => {
-                info!("removed IP : {} from blacklist", &ip);
-                self.blacklist.remove(&ip);
+                info!("removed network: {} from blacklist", &ip);
+                policy.blacklist.remove(&ip);
 
                 AuthReply::Success
             }
             AuthRequest::SetBlacklist(ips) => {
                 info!("reset blacklist");
-                self.blacklist.clear();
-                info!("added IPs: {:#?} to blacklist", &ips);
-                self.blacklist.extend(ips);
+                policy.blacklist.clear();
+                info!("added networks: {:#?} to blacklist", &ips);
+                policy.blacklist.extend(ips);
 
                 AuthReply::Success
             }
-            AuthRequest::AddPlainRegistry(creds) => {
-                info!("added user : {} to plain registry", &creds.username);
-                self.plain_registry.insert(creds.username, creds.password);
+            AuthRequest::AddPlainRegistry(entry) => {
+                let username = entry.username.clone();
+                info!("added user : {} to plain registry", &username);
+                let entry = entry.hashed_with(policy.hash_params);
+                policy.plain_registry.insert(username, entry);
 
                 AuthReply::Success
             }
             AuthRequest::RemovePlainRegistry(username) => {
                 info!("removed user: {} from plain registry", &username);
-                self.plain_registry.remove(&username);
+                policy.plain_registry.remove(&username);
 
                 AuthReply::Success
             }
-            AuthRequest::SetPlainRegistry(creds) => {
+            AuthRequest::SetPlainRegistry(entries) => {
                 info!("reset plain registry");
-                self.plain_registry.clear();
+                policy.plain_registry.clear();
                 let users: Vec<&str> =
-                    creds.iter().map(|c| c.username.as_str()).collect();
+                    entries.iter().map(|e| e.username.as_str()).collect();
                 info!("added users : {:#?} to plain registry", users);
-                self.plain_registry.extend(
-                    creds.into_iter().map(|c| (c.username, c.password)),
+                let params = policy.hash_params;
+                policy.plain_registry.extend(
+                    entries
+                        .into_iter()
+                        .map(|e| (e.username.clone(), e.hashed_with(params))),
                 );
 
                 AuthReply::Success
             }
-            AuthRequest::AddCurveRegistry(key) => {
+            AuthRequest::AddCurveRegistry(entry) => {
+                let key = entry.public_key.clone();
                 info!("added public key: {} to curve registry", key.as_str());
-                self.curve_registry.insert(key);
+                policy.curve_registry.insert(key, entry);
 
                 AuthReply::Success
             }
             AuthRequest::RemoveCurveRegistry(key) => {
                 info!("removed public key: {} to curve registry", key.as_str());
-                self.curve_registry.remove(&key);
+                policy.curve_registry.remove(&key);
 
                 AuthReply::Success
             }
-            AuthRequest::SetCurveRegistry(keys) => {
+            AuthRequest::SetCurveRegistry(entries) => {
                 info!("reset cerve registry");
-                self.curve_registry.clear();
-                info!("added public keys: {:#?} to curve registry", &keys);
-                self.curve_registry.extend(keys);
+                policy.curve_registry.clear();
+                let keys: Vec<&CurvePublicKey> =
+                    entries.iter().map(|e| &e.public_key).collect();
+                info!("added public keys: {:#?} to curve registry", keys);
+                policy.curve_registry.extend(
+                    entries.into_iter().map(|e| (e.public_key.clone(), e)),
+                );
 
                 AuthReply::Success
             }
@@ -324,136 +572,273 @@ impl AuthServer {
                 } else {
                     info!("disabled curve auth");
                 }
-                self.curve_auth = enabled;
+                policy.curve_auth = enabled;
+
+                AuthReply::Success
+            }
+            AuthRequest::SetHashParams(params) => {
+                info!("set hash params: {:?}", &params);
+                policy.hash_params = params;
+                policy.dummy_hash = None;
+
+                AuthReply::Success
+            }
+            AuthRequest::SetBanPolicy(ban_policy) => {
+                info!("set ban policy: {:?}", &ban_policy);
+                policy.bans.set_policy(ban_policy);
+
+                AuthReply::Success
+            }
+            AuthRequest::Bans => {
+                AuthReply::Bans(policy.bans.bans(Instant::now()))
+            }
+            AuthRequest::RemoveBan(addr) => {
+                info!("removed ban of addr {}", addr);
+                policy.bans.remove_ban(map_addr(addr));
 
                 AuthReply::Success
             }
+            AuthRequest::ClearBans => {
+                info!("cleared bans");
+                policy.bans.clear_bans();
+
+                AuthReply::Success
+            }
+            AuthRequest::Blacklist => AuthReply::Subnets(policy.blacklist()),
+            AuthRequest::Whitelist => AuthReply::Subnets(policy.whitelist()),
+            AuthRequest::PlainRegistry => {
+                AuthReply::PlainRegistry(policy.plain_registry())
+            }
+            AuthRequest::CurveRegistry => {
+                AuthReply::CurveRegistry(policy.curve_registry())
+            }
+            AuthRequest::CurveAuth => AuthReply::CurveAuth(policy.curve_auth),
+            AuthRequest::HashParams => {
+                AuthReply::HashParams(policy.hash_params)
+            }
+            AuthRequest::Config => {
+                let mut config = AuthConfig::new();
+                config.set_domain(Some(domain));
+                config.set_blacklist(Some(policy.blacklist()));
+                config.set_whitelist(Some(policy.whitelist()));
+                config.set_hash_params(Some(policy.hash_params));
+                config.set_plain_registry(Some(policy.plain_registry()));
+                config.set_curve_registry(Some(policy.curve_registry()));
+                config.set_curve_auth(Some(policy.curve_auth));
+                config.set_ban_policy(policy.bans.policy().cloned());
+
+                AuthReply::Config(config)
+            }
+            AuthRequest::ClearAuthenticator => unreachable!(),
+        }
+    }
+
+    // Publishes the outcome of the request to the `AuthMonitor`s.
+    fn publish(
+        &self,
+        request: &ZapRequest,
+        verdict: &Verdict,
+        reason: Option<DenyReason>,
+    ) -> Result<(), Error> {
+        let event = AuthEvent::new(request, verdict, reason);
+        let ser = bincode::serialize(&event).unwrap();
+
+        // The `Radio` drops the event if a `AuthMonitor` is too slow.
+        self.events
+            .transmit(ser, &*AUTH_EVENT_GROUP)
+            .map_err(Error::cast)
+    }
+
+    // Counts the failed `PLAIN` and `CURVE` authentications towards a ban,
+    // then publishes the outcome.
+    fn on_verdict(
+        &mut self,
+        request: &ZapRequest,
+        verdict: &Verdict,
+        reason: Option<DenyReason>,
+    ) -> Result<(), Error> {
+        if let (Some(addr), Some(_)) = (request.addr, reason) {
+            if request.credentials != Credentials::Null {
+                let policy =
+                    self.policies.entry(request.domain.clone()).or_default();
+                policy.bans.add_failure(addr, Instant::now());
+            }
         }
+
+        self.publish(request, verdict, reason)
     }
 
-    fn on_zap(&mut self, mut request: ZapRequest) -> Result<ZapReply, Error> {
-        let denied = {
-            if !self.whitelist.is_empty()
-                && !self.whitelist.contains(&request.addr)
-            {
-                info!("denied addr {}, not whitelisted", &request.addr);
-                true
-            } else if self.whitelist.is_empty()
-                && !self.blacklist.is_empty()
-                && self.blacklist.contains(&request.addr)
-            {
-                info!("denied addr {}, blacklisted", &request.addr);
-                true
-            } else {
-                false
+    fn on_zap(
+        &mut self,
+        routing_id: Msg,
+        request_id: Msg,
+        request: ParsedRequest,
+    ) -> Result<Option<ZapReply>, Error> {
+        let (request, valid) = match request {
+            ParsedRequest::Valid(request) => (request, true),
+            ParsedRequest::Invalid(request) => (request, false),
+            ParsedRequest::Unsupported => {
+                info!("denied unsupported mechanism");
+                return Ok(Some(ZapReply::new(request_id, &Verdict::deny())));
             }
         };
 
-        let mut result = None;
-
-        if !denied {
-            if let Ok(mechanism) =
-                MechanismName::try_from(request.mechanism.as_str())
-            {
-                result = {
-                    match mechanism {
-                        MechanismName::Null => Some(AuthResult {
-                            user_id: String::new(),
-                            metadata: vec![],
-                        }),
-                        MechanismName::Plain => {
-                            let username = request
-                                .credentials
-                                .remove(0)
-                                .to_str()
-                                .unwrap()
-                                .to_owned();
-
-                            let password = request
-                                .credentials
-                                .remove(0)
-                                .to_str()
-                                .unwrap()
-                                .to_owned();
-
-                            let creds = PlainClientCreds { username, password };
-                            self.auth_plain(creds)
-                        }
-                        MechanismName::Curve => {
-                            let bin_public_key = BinCurveKey::new_unchecked(
-                                request
-                                    .credentials
-                                    .remove(0)
-                                    .as_bytes()
-                                    .to_owned(),
-                            );
-                            let public_key: CurvePublicKey =
-                                bin_public_key.into();
-
-                            self.auth_curve(public_key)
-                        }
-                    }
-                };
+        let policy = self.policies.entry(request.domain.clone()).or_default();
+        let denied = match request.addr {
+            Some(addr) => policy.filter_addr(addr).or_else(|| {
+                if policy.bans.is_banned(addr, Instant::now()) {
+                    info!("denied addr {}, banned", addr);
+                    Some(DenyReason::Banned)
+                } else {
+                    None
+                }
+            }),
+            None if !policy.whitelist.is_empty() => {
+                Some(DenyReason::NotWhitelisted)
             }
+            None => None,
+        };
+
+        if let Some(reason) = denied {
+            let verdict = Verdict::deny();
+            self.publish(&request, &verdict, Some(reason))?;
+            return Ok(Some(ZapReply::new(request_id, &verdict)));
         }
 
-        if let Some(result) = result {
-            Ok(ZapReply {
-                request_id: request.request_id,
-                user_id: result.user_id,
-                metadata: result.metadata,
-                version: ZAP_VERSION.to_owned(),
-                status_code: StatusCode::Allowed,
-                status_text: "OK".to_owned(),
-            })
-        } else {
-            Ok(ZapReply {
-                request_id: request.request_id,
-                user_id: String::new(),
-                metadata: vec![],
-                version: ZAP_VERSION.to_owned(),
-                status_code: StatusCode::Denied,
-                status_text: "NOT OK".to_owned(),
-            })
+        if !valid {
+            info!("denied invalid plain credentials");
+            let verdict = Verdict::deny();
+            let reason = Some(DenyReason::InvalidCredentials);
+            self.on_verdict(&request, &verdict, reason)?;
+            return Ok(Some(ZapReply::new(request_id, &verdict)));
         }
+
+        // Dispatch the request to the authenticator workers, if any.
+        let (request_id, request) =
+            match self.dispatch(routing_id, request_id, request) {
+                Some(pending) => (pending.request_id, pending.request),
+                None => return Ok(None),
+            };
+
+        let policy = self.policies.get_mut(&request.domain).unwrap();
+        let (verdict, reason) = match &request.credentials {
+            Credentials::Null => (Verdict::allow(), None),
+            Credentials::Plain(creds) => policy.auth_plain(creds),
+            Credentials::Curve(public_key) => policy.auth_curve(public_key),
+        };
+
+        self.on_verdict(&request, &verdict, reason)?;
+        Ok(Some(ZapReply::new(request_id, &verdict)))
+    }
+}
+
+impl DomainPolicy {
+    // The getters return the entries in a deterministic order.
+    fn blacklist(&self) -> Vec<Subnet> {
+        let mut subnets: Vec<Subnet> = self.blacklist.iter().cloned().collect();
+        subnets.sort_by_key(|subnet| (subnet.addr(), subnet.prefix_len()));
+        subnets
+    }
+
+    fn whitelist(&self) -> Vec<Subnet> {
+        let mut subnets: Vec<Subnet> = self.whitelist.iter().cloned().collect();
+        subnets.sort_by_key(|subnet| (subnet.addr(), subnet.prefix_len()));
+        subnets
+    }
+
+    fn plain_registry(&self) -> Vec<PlainRegistryEntry> {
+        let mut entries: Vec<PlainRegistryEntry> =
+            self.plain_registry.values().cloned().collect();
+        entries.sort_by(|a, b| a.username.cmp(&b.username));
+        entries
+    }
+
+    fn curve_registry(&self) -> Vec<CurveRegistryEntry> {
+        let mut entries: Vec<CurveRegistryEntry> =
+            self.curve_registry.values().cloned().collect();
+        entries
+            .sort_by(|a, b| a.public_key.as_str().cmp(b.public_key.as_str()));
+        entries
     }
 
-    fn auth_plain(&mut self, creds: PlainClientCreds) -> Option<AuthResult> {
+    // Returns the reason why the address is denied, if it is, using the
+    // most specific matching network.
+    fn filter_addr(&self, addr: Ipv6Addr) -> Option<DenyReason> {
+        let longest_match = |list: &HashSet<Subnet>| {
+            list.iter()
+                .filter(|subnet| subnet.contains(addr))
+                .map(Subnet::prefix_len_v6)
+                .max()
+        };
+
+        let allow = longest_match(&self.whitelist);
+        let deny = longest_match(&self.blacklist);
+
+        match (allow, deny) {
+            (Some(allow), Some(deny)) if deny > allow => {
+                info!("denied addr {}, blacklisted", addr);
+                Some(DenyReason::Blacklisted)
+            }
+            (Some(_), _) => None,
+            (None, Some(_)) => {
+                info!("denied addr {}, blacklisted", addr);
+                Some(DenyReason::Blacklisted)
+            }
+            (None, None) if !self.whitelist.is_empty() => {
+                info!("denied addr {}, not whitelisted", addr);
+                Some(DenyReason::NotWhitelisted)
+            }
+            (None, None) => None,
+        }
+    }
+
+    fn auth_plain(
+        &mut self,
+        creds: &PlainClientCreds,
+    ) -> (Verdict, Option<DenyReason>) {
         match self.plain_registry.get(&creds.username) {
-            Some(password) => {
+            Some(entry) if entry.password.verify(&creds.password) => {
                 info!("allowed user: {}", &creds.username);
-                if password == &creds.password {
-                    Some(AuthResult {
-                        user_id: creds.username,
-                        metadata: vec![],
-                    })
-                } else {
-                    None
-                }
+                let verdict = Verdict::allow()
+                    .with_user_id(entry.user_id())
+                    .with_metadata(entry.metadata().clone());
+
+                (verdict, None)
+            }
+            Some(_) => {
+                info!("denied user: {}, bad password", &creds.username);
+                (Verdict::deny(), Some(DenyReason::BadPassword))
             }
             None => {
+                let params = self.hash_params;
+                self.dummy_hash
+                    .get_or_insert_with(|| {
+                        PasswordHash::with_params("", params)
+                    })
+                    .verify(&creds.password);
                 info!("denied user: {}", &creds.username);
-                None
+                (Verdict::deny(), Some(DenyReason::UnknownUser))
             }
         }
     }
 
-    fn auth_curve(&mut self, public_key: CurvePublicKey) -> Option<AuthResult> {
+    fn auth_curve(
+        &self,
+        public_key: &CurvePublicKey,
+    ) -> (Verdict, Option<DenyReason>) {
         if !self.curve_auth {
             info!("allowed curve public key {}", public_key);
-            Some(AuthResult {
-                user_id: String::new(),
-                metadata: vec![],
-            })
-        } else if self.curve_registry.contains(&public_key) {
+            (Verdict::allow(), None)
+        } else if let Some(entry) = self.curve_registry.get(public_key) {
             info!("allowed curve public key {}", public_key);
-            Some(AuthResult {
-                user_id: public_key.as_str().to_owned(),
-                metadata: vec![],
-            })
+            let verdict = Verdict::allow()
+                .with_user_id(entry.user_id())
+                .with_metadata(entry.metadata().clone());
+
+            (verdict, None)
         } else {
             info!("denied curve public key {}", public_key);
-            None
+            (Verdict::deny(), Some(DenyReason::UnknownKey))
         }
     }
 }
@@ -47,12 +47,12 @@ mod private {
     impl Sealed for OldSocket {}
 }
 
-use crate::{addr::Endpoint, auth::*, Error, ErrorKind};
+use crate::{addr::Endpoint, auth::*, monitor::Monitor, Error, ErrorKind};
 
 use humantime_serde::Serde;
 use serde::{Deserialize, Serialize};
 
-use std::{sync::MutexGuard, time::Duration};
+use std::{collections::BTreeMap, sync::MutexGuard, time::Duration};
 
 const DEFAULT_HWM: i32 = 1000;
 const DEFAULT_BATCH_SIZE: i32 = 8192;
@@ -200,7 +200,7 @@ impl From<Period> for Serde<Option<Duration>> {
 /// * All sockets have their linger period set to zero (`ZMQ_BLOCKY`).
 /// * All sockets have IPV6 enabled (`ZMQ_IPV6`).
 /// * All sockets have `ZMQ_ZAP_ENFORCE_DOMAIN` set to true.
-/// * All sockets have `ZMQ_ZAP_DOMAIN` hardcoded to "global".
+/// * All sockets have `ZMQ_ZAP_DOMAIN` set to "global" by default.
 pub trait Socket: GetRawSocket {
     /// Schedules a connection to a [`Endpoint`].
     ///
@@ -397,6 +397,48 @@ pub trait Socket: GetRawSocket {
         }
     }
 
+    /// Start monitoring the socket's events.
+    ///
+    /// Returns a [`Monitor`] that yields a [`SocketEvent`] for every
+    /// connection, disconnection and handshake that occurs on the socket.
+    ///
+    /// Only one monitor can be active at a time, calling this method again
+    /// stops the previous monitor.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`]
+    /// * [`SocketLimit`]
+    ///
+    /// # Example
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::{prelude::*, *, monitor::SocketEvent};
+    ///
+    /// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+    ///
+    /// let server = Server::new()?;
+    /// let mut monitor = server.monitor()?;
+    ///
+    /// server.bind(&addr)?;
+    ///
+    /// if let SocketEvent::Listening { endpoint } = monitor.recv_event()? {
+    ///     assert_eq!(endpoint, server.last_endpoint()?);
+    /// }
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Monitor`]: ../monitor/struct.Monitor.html
+    /// [`SocketEvent`]: ../monitor/enum.SocketEvent.html
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    /// [`SocketLimit`]: ../enum.ErrorKind.html#variant.SocketLimit
+    fn monitor(&self) -> Result<Monitor, Error> {
+        Monitor::new(self.raw_socket())
+    }
+
     /// Returns the socket's [`Mechanism`].
     ///
     /// # Example
@@ -466,6 +508,59 @@ pub trait Socket: GetRawSocket {
 
         set_mechanism(raw_socket, mechanism, mutex)
     }
+
+    /// Returns the socket's `ZAP` domain.
+    ///
+    /// # Example
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::{prelude::*, Server};
+    ///
+    /// let server = Server::new()?;
+    /// assert_eq!(server.zap_domain()?, "global");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    fn zap_domain(&self) -> Result<String, Error> {
+        self.raw_socket().zap_domain()
+    }
+
+    /// Set the socket's `ZAP` domain.
+    ///
+    /// The `AuthServer` authenticates the incoming connections of the socket
+    /// using the policy associated with its domain. This only affects
+    /// subsequent calls to [`bind`] and [`connect`].
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if the domain is empty or longer than 255 chars)
+    ///
+    /// # Example
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::{prelude::*, Server};
+    ///
+    /// let server = Server::new()?;
+    /// server.set_zap_domain("internal")?;
+    /// assert_eq!(server.zap_domain()?, "internal");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`bind`]: #method.bind
+    /// [`connect`]: #method.connect
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    fn set_zap_domain<S>(&self, domain: S) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        self.raw_socket().set_zap_domain(domain.as_ref())
+    }
 }
 
 fn set_mechanism(
@@ -561,6 +656,8 @@ pub struct SocketConfig {
     pub(crate) connect: Option<Vec<Endpoint>>,
     pub(crate) bind: Option<Vec<Endpoint>>,
     pub(crate) mechanism: Option<Mechanism>,
+    pub(crate) metadata: Option<BTreeMap<String, String>>,
+    pub(crate) zap_domain: Option<String>,
 }
 
 impl SocketConfig {
@@ -568,6 +665,15 @@ impl SocketConfig {
         if let Some(ref mechanism) = self.mechanism {
             socket.set_mechanism(mechanism)?;
         }
+        if let Some(ref metadata) = self.metadata {
+            let raw_socket = socket.raw_socket();
+            for (key, value) in metadata {
+                raw_socket.add_metadata(key, value)?;
+            }
+        }
+        if let Some(ref domain) = self.zap_domain {
+            socket.set_zap_domain(domain)?;
+        }
         // We connect as the last step because some socket options
         // only affect subsequent connections.
         if let Some(ref endpoints) = self.connect {
@@ -638,6 +744,33 @@ pub trait ConfigureSocket: GetSocketConfig {
     fn set_mechanism(&mut self, maybe: Option<Mechanism>) {
         self.socket_config_mut().mechanism = maybe;
     }
+
+    fn metadata(&self) -> Option<&BTreeMap<String, String>> {
+        self.socket_config().metadata.as_ref()
+    }
+
+    fn set_metadata<I, K, V>(&mut self, maybe: Option<I>)
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let maybe: Option<BTreeMap<String, String>> = maybe.map(|m| {
+            m.into_iter().map(|(k, v)| (k.into(), v.into())).collect()
+        });
+        self.socket_config_mut().metadata = maybe;
+    }
+
+    fn zap_domain(&self) -> Option<&str> {
+        self.socket_config().zap_domain.as_deref()
+    }
+
+    fn set_zap_domain<S>(&mut self, maybe: Option<S>)
+    where
+        S: Into<String>,
+    {
+        self.socket_config_mut().zap_domain = maybe.map(S::into);
+    }
 }
 
 impl ConfigureSocket for SocketConfig {}
@@ -670,6 +803,67 @@ pub trait BuildSocket: GetSocketConfig + Sized {
             .set_mechanism(Some(mechanism.into()));
         self
     }
+
+    /// Set the application metadata properties that the socket sends to its
+    /// peers during the connection handshake.
+    ///
+    /// ØMQ requires the property names to start with `X-`, which is
+    /// prepended if missing. The properties can then be read by the peer
+    /// from any received message via [`Msg::metadata`].
+    ///
+    /// # Example
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+    ///
+    /// let server = ServerBuilder::new()
+    ///     .bind(addr)
+    ///     .build()?;
+    ///
+    /// let bound = server.last_endpoint()?;
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .connect(bound)
+    ///     .metadata(vec![("Service", "echo")])
+    ///     .build()?;
+    ///
+    /// client.send("")?;
+    /// let msg = server.recv_msg()?;
+    /// assert_eq!(msg.metadata("Service"), Some("echo"));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Msg::metadata`]: ../struct.Msg.html#method.metadata
+    fn metadata<I, K, V>(&mut self, properties: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.socket_config_mut().set_metadata(Some(properties));
+        self
+    }
+
+    /// Set the `ZAP` domain of the socket.
+    ///
+    /// The `AuthServer` authenticates the incoming connections of the socket
+    /// using the policy associated with its domain, which defaults to
+    /// "global". See the [`auth`] module.
+    ///
+    /// [`auth`]: ../auth/index.html#zap-domains
+    fn zap_domain<S>(&mut self, domain: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.socket_config_mut().set_zap_domain(Some(domain));
+        self
+    }
 }
 
 #[cfg(test)]
@@ -761,4 +955,73 @@ mod test {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn test_metadata() {
+        use crate::{prelude::*, *};
+        use std::convert::TryInto;
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .bind(addr)
+            .metadata(vec![("X-Role", "backend")])
+            .build()
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(bound)
+            .metadata(vec![("Service", "echo"), ("X-Version", "1")])
+            .build()
+            .unwrap();
+
+        client.send("").unwrap();
+        let msg = server.recv_msg().unwrap();
+
+        assert_eq!(msg.metadata("Service"), Some("echo"));
+        assert_eq!(msg.metadata("X-Service"), Some("echo"));
+        assert_eq!(msg.metadata("Version"), Some("1"));
+        assert_eq!(msg.metadata("Role"), None);
+
+        server.route("", msg.routing_id().unwrap()).unwrap();
+        let msg = client.recv_msg().unwrap();
+        assert_eq!(msg.metadata("Role"), Some("backend"));
+    }
+
+    #[test]
+    fn test_invalid_zap_domain() {
+        use crate::{prelude::*, *};
+
+        let long = "a".repeat(256);
+        for domain in &["", "a\0b", long.as_str()] {
+            let err = ServerBuilder::new()
+                .zap_domain(*domain)
+                .build()
+                .unwrap_err();
+
+            match err.kind() {
+                ErrorKind::InvalidInput(_) => (),
+                _ => panic!("unexpected error kind"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_metadata() {
+        use crate::{prelude::*, *};
+
+        for (key, value) in &[("", "v"), ("X-", "v"), ("a:b", "v"), ("k", "")] {
+            let err = ClientBuilder::new()
+                .metadata(vec![(*key, *value)])
+                .build()
+                .unwrap_err();
+
+            match err.kind() {
+                ErrorKind::InvalidInput(_) => (),
+                _ => panic!("unexpected error kind"),
+            }
+        }
+    }
 }
@@ -13,6 +13,7 @@ use sys::errno;
 use log::error;
 
 use std::{
+    borrow::Cow,
     ffi::CString,
     os::raw::{c_int, c_void},
     sync::Mutex,
@@ -20,6 +21,20 @@ use std::{
 };
 
 const MAX_HB_TTL: i64 = 6_553_599;
+const MAX_METADATA_KEY_SIZE: usize = 255;
+const METADATA_PREFIX: &str = "X-";
+const MAX_ZAP_DOMAIN_SIZE: usize = 255;
+/// The `ZAP` domain of sockets that do not specify one.
+pub(crate) const DEFAULT_ZAP_DOMAIN: &str = "global";
+
+/// ØMQ requires application metadata properties to be prefixed by `X-`.
+pub(crate) fn metadata_key(key: &str) -> Cow<'_, str> {
+    if key.starts_with(METADATA_PREFIX) {
+        Cow::Borrowed(key)
+    } else {
+        Cow::Owned(format!("{}{}", METADATA_PREFIX, key))
+    }
+}
 
 #[doc(hidden)]
 pub trait GetRawSocket: super::private::Sealed {
@@ -159,6 +174,33 @@ fn unbind(socket_ptr: *mut c_void, c_string: CString) -> Result<(), Error> {
     }
 }
 
+fn monitor(socket_ptr: *mut c_void, c_string: CString) -> Result<(), Error> {
+    let rc = unsafe {
+        sys::zmq_socket_monitor_versioned(
+            socket_ptr,
+            c_string.as_ptr(),
+            u64::from(sys::ZMQ_EVENT_ALL_V1),
+            2,
+            sys::ZMQ_PAIR as c_int,
+        )
+    };
+
+    if rc == -1 {
+        let errno = unsafe { sys::zmq_errno() };
+        let err = match errno {
+            errno::ETERM => Error::new(ErrorKind::InvalidCtx),
+            errno::EPROTONOSUPPORT => panic!("transport not supported"),
+            errno::EINVAL => panic!("invalid monitor parameters"),
+            errno::EMFILE => Error::new(ErrorKind::SocketLimit),
+            _ => panic!("{}", msg_from_errno(errno)),
+        };
+
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
 fn assert_curve_enabled() {
     if cfg!(not(feature = "curve")) {
         panic!("CURVE support requires enabling feature flag 'curve'");
@@ -206,12 +248,11 @@ impl RawSocket {
             // Set ZAP domain handling to strictly adhere the RFC.
             // This will eventually be enabled by default by ØMQ.
             setsockopt_bool(socket_mut_ptr, SocketOption::EnforceDomain, true)?;
-            // We hardset the same domain name for each sockets because I don't
-            // see any use cases for them.
+            // Sockets share the same domain name unless specified otherwise.
             setsockopt_str(
                 socket_mut_ptr,
                 SocketOption::ZapDomain,
-                Some("global"),
+                Some(DEFAULT_ZAP_DOMAIN),
             )?;
 
             Ok(Self {
@@ -243,6 +284,11 @@ impl RawSocket {
         unbind(self.as_mut_ptr(), c_string)
     }
 
+    pub(crate) fn monitor(&self, endpoint: &Endpoint) -> Result<(), Error> {
+        let c_string = CString::new(endpoint.to_zmq()).unwrap();
+        monitor(self.as_mut_ptr(), c_string)
+    }
+
     pub(crate) fn ctx(&self) -> CtxHandle {
         self.ctx
     }
@@ -267,6 +313,28 @@ impl RawSocket {
         Ok(maybe.map(|s| Endpoint::from_zmq(s.as_str())))
     }
 
+    pub(crate) fn zap_domain(&self) -> Result<String, Error> {
+        let maybe =
+            getsockopt_string(self.as_mut_ptr(), SocketOption::ZapDomain)?;
+
+        Ok(maybe.unwrap_or_default())
+    }
+
+    pub(crate) fn set_zap_domain(&self, domain: &str) -> Result<(), Error> {
+        if domain.is_empty() || domain.len() > MAX_ZAP_DOMAIN_SIZE {
+            return Err(Error::new(ErrorKind::InvalidInput(
+                "zap domain must have between 1 and 255 chars",
+            )));
+        }
+        if domain.contains('\0') {
+            return Err(Error::new(ErrorKind::InvalidInput(
+                "zap domain cannot contain null bytes",
+            )));
+        }
+
+        setsockopt_str(self.as_mut_ptr(), SocketOption::ZapDomain, Some(domain))
+    }
+
     pub(crate) fn set_heartbeat_interval(
         &self,
         duration: Duration,
@@ -433,6 +501,47 @@ impl RawSocket {
         let key = key.map(BinCurveKey::as_bytes);
         setsockopt_bytes(self.as_mut_ptr(), SocketOption::CurveServerKey, key)
     }
+
+    /// Adds a `key:value` metadata property to the handshake of every
+    /// subsequent connection.
+    pub(crate) fn add_metadata(
+        &self,
+        key: &str,
+        value: &str,
+    ) -> Result<(), Error> {
+        let key = metadata_key(key);
+        if key.len() <= METADATA_PREFIX.len()
+            || key.len() > MAX_METADATA_KEY_SIZE
+        {
+            return Err(Error::new(ErrorKind::InvalidInput(
+                "metadata key must have between 1 and 253 chars",
+            )));
+        }
+        if key.contains(':') {
+            return Err(Error::new(ErrorKind::InvalidInput(
+                "metadata key cannot contain ':'",
+            )));
+        }
+        if value.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput(
+                "metadata value cannot be empty",
+            )));
+        }
+        // libzmq reads the option as a C string so we need the terminating
+        // zero byte here.
+        let property =
+            CString::new(format!("{}:{}", key, value)).map_err(|_| {
+                Error::new(ErrorKind::InvalidInput(
+                    "metadata cannot contain null bytes",
+                ))
+            })?;
+
+        setsockopt_bytes(
+            self.as_mut_ptr(),
+            SocketOption::Metadata,
+            Some(property.as_bytes_with_nul()),
+        )
+    }
 }
 
 impl PartialEq for RawSocket {
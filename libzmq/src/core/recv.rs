@@ -7,7 +7,9 @@ use libzmq_sys as sys;
 use sys::errno;
 
 use std::{
+    ops,
     os::raw::{c_int, c_void},
+    sync::{Arc, Mutex},
     time::Duration,
 };
 
@@ -21,26 +23,201 @@ fn recv(
     };
 
     if rc == -1 {
-        let errno = unsafe { sys::zmq_errno() };
-        let err = match errno {
-            errno::EAGAIN => Error::new(ErrorKind::WouldBlock),
-            errno::ENOTSUP => panic!("recv not supported by socket type"),
-            errno::EFSM => {
-                panic!("operation cannot be completed in current socket state")
-            }
-            errno::ETERM => Error::new(ErrorKind::InvalidCtx),
-            errno::ENOTSOCK => panic!("invalid socket"),
-            errno::EINTR => Error::new(ErrorKind::Interrupted),
-            errno::EFAULT => panic!("invalid message"),
-            _ => panic!(msg_from_errno(errno)),
-        };
-
-        Err(err)
+        Err(recv_error())
     } else {
+        msg.clear_shared();
         Ok(())
     }
 }
 
+fn recv_into(
+    socket_ptr: *mut c_void,
+    buf: &mut [u8],
+    no_block: bool,
+) -> Result<RecvLen, Error> {
+    let rc = unsafe {
+        sys::zmq_recv(
+            socket_ptr,
+            buf.as_mut_ptr() as *mut c_void,
+            buf.len(),
+            no_block as c_int,
+        )
+    };
+
+    if rc == -1 {
+        Err(recv_error())
+    } else {
+        Ok(RecvLen {
+            written: buf.len().min(rc as usize),
+            len: rc as usize,
+        })
+    }
+}
+
+fn recv_error() -> Error {
+    let errno = unsafe { sys::zmq_errno() };
+    match errno {
+        errno::EAGAIN => Error::new(ErrorKind::WouldBlock),
+        errno::ENOTSUP => panic!("recv not supported by socket type"),
+        errno::EFSM => {
+            panic!("operation cannot be completed in current socket state")
+        }
+        errno::ETERM => Error::new(ErrorKind::InvalidCtx),
+        errno::ENOTSOCK => panic!("invalid socket"),
+        errno::EINTR => Error::new(ErrorKind::Interrupted),
+        errno::EFAULT => panic!("invalid message"),
+        _ => panic!("{}", msg_from_errno(errno)),
+    }
+}
+
+/// The length of a message received in a buffer via [`recv_into`].
+///
+/// If the message is larger than the buffer, it is truncated.
+///
+/// [`recv_into`]: prelude/trait.RecvMsg.html#method.recv_into
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RecvLen {
+    written: usize,
+    len: usize,
+}
+
+impl RecvLen {
+    /// Returns the number of bytes written into the buffer.
+    pub fn written(self) -> usize {
+        self.written
+    }
+
+    /// Returns the length of the received message, which can be larger
+    /// than the buffer.
+    pub fn msg_len(self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the message did not fit in the buffer.
+    pub fn is_truncated(self) -> bool {
+        self.len > self.written
+    }
+}
+
+/// A pool of fixed size buffers used to receive messages via
+/// [`recv_pooled`].
+///
+/// The buffers are returned to the pool when the [`PooledBuf`] are dropped,
+/// so that their allocations are reused across calls. A `BufPool` can be
+/// cloned cheaply and shared between threads.
+///
+/// [`recv_pooled`]: prelude/trait.RecvMsg.html#method.recv_pooled
+/// [`PooledBuf`]: struct.PooledBuf.html
+#[derive(Debug, Clone)]
+pub struct BufPool {
+    inner: Arc<PoolInner>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    buf_size: usize,
+    bufs: Mutex<Vec<Box<[u8]>>>,
+}
+
+impl PoolInner {
+    fn put(&self, buf: Box<[u8]>) {
+        if let Ok(mut bufs) = self.bufs.lock() {
+            bufs.push(buf);
+        }
+    }
+}
+
+impl BufPool {
+    /// Create an empty pool of buffers of `buf_size` bytes.
+    ///
+    /// Received messages larger than `buf_size` are truncated.
+    pub fn new(buf_size: usize) -> Self {
+        let inner = PoolInner {
+            buf_size,
+            bufs: Mutex::default(),
+        };
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Returns the size of the buffers.
+    pub fn buf_size(&self) -> usize {
+        self.inner.buf_size
+    }
+
+    /// Returns the number of idle buffers in the pool.
+    pub fn idle(&self) -> usize {
+        self.inner.bufs.lock().unwrap().len()
+    }
+
+    fn take(&self) -> Box<[u8]> {
+        let buf = self.inner.bufs.lock().unwrap().pop();
+        buf.unwrap_or_else(|| vec![0; self.inner.buf_size].into_boxed_slice())
+    }
+}
+
+/// A message received in a buffer of a [`BufPool`].
+///
+/// Dereferences to the received bytes. The buffer is returned to its pool
+/// when dropped.
+///
+/// [`BufPool`]: struct.BufPool.html
+#[derive(Debug)]
+pub struct PooledBuf {
+    buf: Option<Box<[u8]>>,
+    len: RecvLen,
+    pool: Arc<PoolInner>,
+}
+
+impl PooledBuf {
+    /// Returns the length of the received message.
+    pub fn recv_len(&self) -> RecvLen {
+        self.len
+    }
+}
+
+impl ops::Deref for PooledBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf.as_ref().unwrap()[..self.len.written]
+    }
+}
+
+impl AsRef<[u8]> for PooledBuf {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Drop for PooledBuf {
+    fn drop(&mut self) {
+        let buf = self.buf.take().unwrap();
+        self.pool.put(buf);
+    }
+}
+
+fn recv_pooled(
+    socket_ptr: *mut c_void,
+    pool: &BufPool,
+    no_block: bool,
+) -> Result<PooledBuf, Error> {
+    let mut buf = pool.take();
+    match recv_into(socket_ptr, &mut buf, no_block) {
+        Ok(len) => Ok(PooledBuf {
+            buf: Some(buf),
+            len,
+            pool: Arc::clone(&pool.inner),
+        }),
+        Err(err) => {
+            pool.inner.put(buf);
+            Err(err)
+        }
+    }
+}
+
 /// Receive atomic messages in an immutable, thread-safe fashion.
 ///
 /// Does not support multipart messages.
@@ -110,6 +287,122 @@ pub trait RecvMsg: GetRawSocket {
         Ok(msg)
     }
 
+    /// Receive a message directly into the buffer, without allocating a
+    /// [`Msg`].
+    ///
+    /// If the message is larger than the buffer, it is truncated, which is
+    /// reported by the returned [`RecvLen`]. Since no `Msg` is returned, the
+    /// properties of the message, such as its `RoutingId` or group, are
+    /// not available.
+    ///
+    /// This operation might block until the socket receives a message or,
+    /// if it is set, until `recv_timeout` expires.
+    ///
+    /// See [`zmq_recv`].
+    ///
+    /// # Example
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// let addr = InprocAddr::new_unique();
+    ///
+    /// let server = ServerBuilder::new().bind(&addr).build()?;
+    /// let client = ClientBuilder::new().connect(&addr).build()?;
+    ///
+    /// let mut buf = [0; 8];
+    ///
+    /// client.send("frame")?;
+    /// let len = server.recv_into(&mut buf)?;
+    /// assert!(!len.is_truncated());
+    /// assert_eq!(&buf[..len.written()], b"frame");
+    ///
+    /// client.send("a larger frame")?;
+    /// let len = server.recv_into(&mut buf)?;
+    /// assert!(len.is_truncated());
+    /// assert_eq!(len.msg_len(), 14);
+    /// assert_eq!(&buf, b"a larger");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Possible Error Variants
+    /// * [`WouldBlock`] (if `recv_timeout` expires)
+    /// * [`InvalidCtx`]
+    /// * [`Interrupted`]
+    ///
+    /// [`Msg`]: ../msg/struct.Msg.html
+    /// [`RecvLen`]: ../struct.RecvLen.html
+    /// [`zmq_recv`]: http://api.zeromq.org/master:zmq-recv
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    fn recv_into(&self, buf: &mut [u8]) -> Result<RecvLen, Error> {
+        recv_into(self.raw_socket().as_mut_ptr(), buf, false)
+    }
+
+    /// Try to receive a message directly into the buffer without blocking.
+    ///
+    /// See [`recv_into`] and [`try_recv`].
+    ///
+    /// [`recv_into`]: #method.recv_into
+    /// [`try_recv`]: #method.try_recv
+    fn try_recv_into(&self, buf: &mut [u8]) -> Result<RecvLen, Error> {
+        recv_into(self.raw_socket().as_mut_ptr(), buf, true)
+    }
+
+    /// Receive a message into a buffer taken from the pool, with the same
+    /// properties as [`recv_into`].
+    ///
+    /// The buffer is returned to the pool once the [`PooledBuf`] is
+    /// dropped, which avoids an allocation per message.
+    ///
+    /// # Example
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// let addr = InprocAddr::new_unique();
+    ///
+    /// let server = ServerBuilder::new().bind(&addr).build()?;
+    /// let client = ClientBuilder::new().connect(&addr).build()?;
+    ///
+    /// let pool = BufPool::new(64);
+    ///
+    /// for _ in 0..3 {
+    ///     client.send("frame")?;
+    ///     let buf = server.recv_pooled(&pool)?;
+    ///     assert_eq!(&*buf, b"frame");
+    /// }
+    /// // A single buffer was allocated.
+    /// assert_eq!(pool.idle(), 1);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`recv_into`]: #method.recv_into
+    /// [`PooledBuf`]: ../struct.PooledBuf.html
+    fn recv_pooled(&self, pool: &BufPool) -> Result<PooledBuf, Error> {
+        recv_pooled(self.raw_socket().as_mut_ptr(), pool, false)
+    }
+
+    /// Try to receive a message into a buffer taken from the pool without
+    /// blocking.
+    ///
+    /// See [`recv_pooled`] and [`try_recv`].
+    ///
+    /// [`recv_pooled`]: #method.recv_pooled
+    /// [`try_recv`]: #method.try_recv
+    fn try_recv_pooled(&self, pool: &BufPool) -> Result<PooledBuf, Error> {
+        recv_pooled(self.raw_socket().as_mut_ptr(), pool, true)
+    }
+
     /// The high water mark for incoming messages on the specified socket.
     ///
     /// The high water mark is a hard limit on the maximum number of
@@ -238,3 +531,37 @@ pub trait BuildRecv: GetRecvConfig {
         self
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{prelude::*, *};
+
+    #[test]
+    fn test_recv_pooled() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let pool = BufPool::new(4);
+        let err = server.try_recv_pooled(&pool).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert_eq!(pool.idle(), 1);
+
+        client.send("ping").unwrap();
+        client.send("pong!").unwrap();
+        let first = server.recv_pooled(&pool).unwrap();
+        let second = server.recv_pooled(&pool).unwrap();
+        assert_eq!(pool.idle(), 0);
+
+        assert_eq!(&*first, b"ping");
+        assert!(!first.recv_len().is_truncated());
+        assert_eq!(&*second, b"pong");
+        assert!(second.recv_len().is_truncated());
+        assert_eq!(second.recv_len().msg_len(), 5);
+
+        drop(first);
+        drop(second);
+        assert_eq!(pool.idle(), 2);
+    }
+}
@@ -44,6 +44,7 @@ pub(crate) enum SocketOption {
     CurveServerKey = sys::ZMQ_CURVE_SERVERKEY as isize,
     InBatchSize = sys::ZMQ_IN_BATCH_SIZE as isize,
     OutBatchSize = sys::ZMQ_OUT_BATCH_SIZE as isize,
+    Metadata = sys::ZMQ_METADATA as isize,
 }
 
 impl From<SocketOption> for c_int {
@@ -93,6 +94,7 @@ impl From<SocketOption> for c_int {
             }
             SocketOption::InBatchSize => SocketOption::InBatchSize as c_int,
             SocketOption::OutBatchSize => SocketOption::OutBatchSize as c_int,
+            SocketOption::Metadata => SocketOption::Metadata as c_int,
         }
     }
 }
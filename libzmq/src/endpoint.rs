@@ -357,6 +357,251 @@ impl fmt::Display for Interface {
 
 serde_display_tryfrom!(Interface);
 
+/// A IPv4 or IPv6 network in CIDR notation.
+///
+/// The host bits of the address are zeroed. A bare IP address is parsed as a
+/// network that contains only this address.
+///
+/// IPv4 networks also match the IPv4-mapped IPv6 version of their addresses.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::{prelude::TryInto, addr::Subnet};
+/// use std::net::IpAddr;
+///
+/// let subnet: Subnet = "10.0.0.0/8".try_into()?;
+///
+/// let ip: IpAddr = "10.1.2.3".parse()?;
+/// assert!(subnet.contains(ip));
+/// let ip: IpAddr = "::ffff:10.1.2.3".parse()?;
+/// assert!(subnet.contains(ip));
+/// let ip: IpAddr = "11.1.2.3".parse()?;
+/// assert!(!subnet.contains(ip));
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Subnet {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl Subnet {
+    /// Create a new `Subnet` from an address and a prefix length.
+    ///
+    /// The prefix length cannot be greater than 32 for IPv4 and 128 for IPv6.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self, AddrParseError> {
+        let max_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if prefix_len > max_len {
+            return Err(AddrParseError::new("prefix length too large"));
+        }
+
+        let addr = match addr {
+            IpAddr::V4(ipv4) => {
+                let bits = u32::from(ipv4) & Self::mask(prefix_len, 32) as u32;
+                IpAddr::V4(bits.into())
+            }
+            IpAddr::V6(ipv6) => {
+                let bits = u128::from(ipv6) & Self::mask(prefix_len, 128);
+                IpAddr::V6(bits.into())
+            }
+        };
+
+        Ok(Self { addr, prefix_len })
+    }
+
+    /// Returns the network address.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Returns the prefix length.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns `true` if the network contains the address.
+    pub fn contains<I>(&self, ip: I) -> bool
+    where
+        I: Into<IpAddr>,
+    {
+        let (net, len) = Self::mapped(self.addr, self.prefix_len);
+        let (ip, _) = Self::mapped(ip.into(), 0);
+
+        ip & Self::mask(len, 128) == net
+    }
+
+    // Returns the prefix length in the IPv6 space, which allows IPv4 and
+    // IPv6 networks to be compared.
+    pub(crate) fn prefix_len_v6(&self) -> u8 {
+        Self::mapped(self.addr, self.prefix_len).1
+    }
+
+    // Returns the bits of the address in the IPv6 space and the
+    // corresponding prefix length.
+    fn mapped(addr: IpAddr, prefix_len: u8) -> (u128, u8) {
+        match addr {
+            IpAddr::V4(ipv4) => {
+                (u128::from(ipv4.to_ipv6_mapped()), prefix_len + 96)
+            }
+            IpAddr::V6(ipv6) => (u128::from(ipv6), prefix_len),
+        }
+    }
+
+    // Returns the mask of the `prefix_len` most significant bits of a
+    // `width` bits integer.
+    fn mask(prefix_len: u8, width: u32) -> u128 {
+        if prefix_len == 0 {
+            0
+        } else {
+            let mask = u128::MAX << (128 - u32::from(prefix_len));
+            mask >> (128 - width)
+        }
+    }
+}
+
+impl FromStr for Subnet {
+    type Err = AddrParseError;
+    fn from_str(s: &str) -> Result<Self, AddrParseError> {
+        match s.find('/') {
+            Some(mid) => {
+                let addr = IpAddr::from_str(&s[..mid])
+                    .map_err(|_| AddrParseError::new("invalid ip addr"))?;
+                let prefix_len = u8::from_str(&s[mid + 1..]).map_err(|_| {
+                    AddrParseError::new("invalid prefix length")
+                })?;
+
+                Self::new(addr, prefix_len)
+            }
+            None => {
+                let addr = IpAddr::from_str(s)
+                    .map_err(|_| AddrParseError::new("invalid ip addr"))?;
+
+                Ok(addr.into())
+            }
+        }
+    }
+}
+
+tryfrom_fromstr!(Subnet);
+
+impl fmt::Display for Subnet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+serde_display_tryfrom!(Subnet);
+
+impl From<IpAddr> for Subnet {
+    fn from(addr: IpAddr) -> Self {
+        let prefix_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+
+        Self { addr, prefix_len }
+    }
+}
+
+impl From<Ipv4Addr> for Subnet {
+    fn from(addr: Ipv4Addr) -> Self {
+        IpAddr::V4(addr).into()
+    }
+}
+
+impl From<Ipv6Addr> for Subnet {
+    fn from(addr: Ipv6Addr) -> Self {
+        IpAddr::V6(addr).into()
+    }
+}
+
+impl<'a> From<&'a Subnet> for Subnet {
+    fn from(subnet: &'a Subnet) -> Self {
+        subnet.to_owned()
+    }
+}
+
+/// A trait equivalent to `IntoIter<Item=Into<Subnet>>`.
+///
+/// This allows either single addresses, single networks or
+/// collections of them to be used wherever networks are expected.
+pub trait IntoSubnets {
+    /// Returned iterator over networks which this type may correspond to.
+    type IntoIter: Iterator<Item = Subnet>;
+
+    /// Converts this object to an iterator of `Subnet`s.
+    fn into_subnets(self) -> Self::IntoIter;
+}
+
+impl IntoSubnets for Subnet {
+    type IntoIter = option::IntoIter<Self>;
+    fn into_subnets(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+impl IntoSubnets for IpAddr {
+    type IntoIter = option::IntoIter<Subnet>;
+    fn into_subnets(self) -> Self::IntoIter {
+        Subnet::from(self).into_subnets()
+    }
+}
+
+impl IntoSubnets for Ipv4Addr {
+    type IntoIter = option::IntoIter<Subnet>;
+    fn into_subnets(self) -> Self::IntoIter {
+        Subnet::from(self).into_subnets()
+    }
+}
+
+impl IntoSubnets for Ipv6Addr {
+    type IntoIter = option::IntoIter<Subnet>;
+    fn into_subnets(self) -> Self::IntoIter {
+        Subnet::from(self).into_subnets()
+    }
+}
+
+impl<E> IntoSubnets for &[E]
+where
+    E: Into<Subnet> + Clone,
+{
+    type IntoIter = vec::IntoIter<Subnet>;
+    fn into_subnets(self) -> Self::IntoIter {
+        let subnets: Vec<Subnet> =
+            self.iter().map(|e| e.to_owned().into()).collect();
+        subnets.into_iter()
+    }
+}
+
+impl<T> IntoSubnets for &T
+where
+    T: IntoSubnets + Clone,
+{
+    type IntoIter = T::IntoIter;
+    fn into_subnets(self) -> Self::IntoIter {
+        (*self).clone().into_subnets()
+    }
+}
+
+impl<E> IntoSubnets for Vec<E>
+where
+    E: Into<Subnet>,
+{
+    type IntoIter = vec::IntoIter<Subnet>;
+    fn into_subnets(self) -> Self::IntoIter {
+        let subnets: Vec<Subnet> = self.into_iter().map(E::into).collect();
+        subnets.into_iter()
+    }
+}
+
 /// A socket address with an [`Interface`] and a [`Port`].
 ///
 /// # Example
@@ -1187,6 +1432,124 @@ impl<'a> From<&'a InprocAddr> for Endpoint {
     }
 }
 
+/// A local inter-process address with the `IPC` transport.
+///
+/// The `ipc` address is either a non-empty filesystem path or, on Linux only,
+/// a name in the abstract namespace prefixed by `@`. Binding to the special
+/// path `*` makes ØMQ generate a unique temporary file name, which can then
+/// be retrieved with `last_endpoint`.
+///
+/// # Supported Sockets
+/// [`Dish`], [`Radio`], [`Client`], [`Server`], [`Gather`] and [`Scatter`]
+///
+/// [`Dish`]: struct.Dish.html
+/// [`Radio`]: struct.Radio.html
+/// [`Client`]: struct.Client.html
+/// [`Server`]: struct.Server.html
+/// [`Gather`]: struct.Gather.html
+/// [`Scatter`]: struct.Scatter.html
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::{prelude::TryInto, IpcAddr};
+///
+/// // A filesystem path.
+/// let path: IpcAddr = "/tmp/feeds/0".try_into()?;
+/// assert!(!path.is_abstract());
+///
+/// // A name in the abstract namespace.
+/// let name: IpcAddr = "@feeds".try_into()?;
+/// assert!(name.is_abstract());
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct IpcAddr {
+    path: String,
+}
+
+impl IpcAddr {
+    /// Create a new `IpcAddr` addr from a path.
+    ///
+    /// The path cannot be empty or contain a null byte.
+    pub fn new<S>(path: S) -> Result<Self, AddrParseError>
+    where
+        S: Into<String>,
+    {
+        let path = path.into();
+
+        if path.is_empty() {
+            Err(AddrParseError::new("empty path"))
+        } else if path.contains('\0') {
+            Err(AddrParseError::new("path cannot contain null bytes"))
+        } else if path == "@" {
+            Err(AddrParseError::new("empty abstract name"))
+        } else {
+            Ok(Self { path })
+        }
+    }
+
+    /// Returns `true` if the address is in the Linux abstract namespace.
+    pub fn is_abstract(&self) -> bool {
+        self.path.starts_with('@')
+    }
+
+    /// Returns the underlying string of the `IpcAddr`.
+    pub fn as_str(&self) -> &str {
+        self.path.as_str()
+    }
+}
+
+impl FromStr for IpcAddr {
+    type Err = AddrParseError;
+    fn from_str(s: &str) -> Result<Self, AddrParseError> {
+        Self::new(s)
+    }
+}
+
+impl fmt::Display for IpcAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.path.fmt(f)
+    }
+}
+
+serde_display_tryfrom!(IpcAddr);
+tryfrom_fromstr!(IpcAddr);
+
+impl IntoIterator for IpcAddr {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+impl IntoIterator for &IpcAddr {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+impl From<IpcAddr> for Endpoint {
+    fn from(addr: IpcAddr) -> Endpoint {
+        Endpoint::Ipc(addr)
+    }
+}
+
+impl<'a> From<&'a IpcAddr> for Endpoint {
+    fn from(addr: &'a IpcAddr) -> Endpoint {
+        Endpoint::Ipc(addr.to_owned())
+    }
+}
+
 /// A transport and a transport-specific address supported by ØMQ.
 ///
 /// The transport specifies the underlying protocol to use. The address
@@ -1252,6 +1615,10 @@ pub enum Endpoint {
     ///
     /// [`zmq_inproc`]: http://api.zeromq.org/master:zmq-inproc
     Inproc(InprocAddr),
+    /// Local inter-process communication transport, see [`zmq_ipc`].
+    ///
+    /// [`zmq_ipc`]: http://api.zeromq.org/master:zmq-ipc
+    Ipc(IpcAddr),
     /// Reliable multicast transport using PGM, see [`zmq_pgm`].
     ///
     /// [`zmq_pgm`]: http://api.zeromq.org/master:zmq-pgm
@@ -1289,6 +1656,16 @@ impl Endpoint {
             false
         }
     }
+
+    /// Returns `true` if the endpoint uses the `Ipc` transport.
+    pub fn is_ipc(&self) -> bool {
+        if let Endpoint::Ipc(_) = self {
+            true
+        } else {
+            false
+        }
+    }
+
     /// Returns `true` if the endpoint uses the `Pgm` transport.
     pub fn is_pgm(&self) -> bool {
         if let Endpoint::Pgm(_) = self {
@@ -1307,37 +1684,48 @@ impl Endpoint {
     }
 
     pub(crate) fn from_zmq(s: &str) -> Self {
-        let index = s.find("://").unwrap();
+        Self::try_from_zmq(s).unwrap()
+    }
+
+    pub(crate) fn try_from_zmq(s: &str) -> Option<Self> {
+        let index = s.find("://")?;
 
-        match &s[0..index] {
+        let endpoint = match &s[0..index] {
             "tcp" => {
-                let addr = TcpAddr::from_str(&s[index + 3..]).unwrap();
+                let addr = TcpAddr::from_str(&s[index + 3..]).ok()?;
                 Endpoint::Tcp(addr)
             }
             "inproc" => {
-                let addr = InprocAddr::from_str(&s[index + 3..]).unwrap();
+                let addr = InprocAddr::from_str(&s[index + 3..]).ok()?;
                 Endpoint::Inproc(addr)
             }
+            "ipc" => {
+                let addr = IpcAddr::from_str(&s[index + 3..]).ok()?;
+                Endpoint::Ipc(addr)
+            }
             "udp" => {
-                let addr = UdpAddr::from_str(&s[index + 3..]).unwrap();
+                let addr = UdpAddr::from_str(&s[index + 3..]).ok()?;
                 Endpoint::Udp(addr)
             }
             "pgm" => {
-                let addr = PgmAddr::from_str(&s[index + 3..]).unwrap();
+                let addr = PgmAddr::from_str(&s[index + 3..]).ok()?;
                 Endpoint::Pgm(addr)
             }
             "epgm" => {
-                let addr = EpgmAddr::from_str(&s[index + 3..]).unwrap();
+                let addr = EpgmAddr::from_str(&s[index + 3..]).ok()?;
                 Endpoint::Epgm(addr)
             }
-            _ => unreachable!(),
-        }
+            _ => return None,
+        };
+
+        Some(endpoint)
     }
 
     pub(crate) fn to_zmq(&self) -> String {
         match self {
             Endpoint::Tcp(addr) => format!("tcp://{}", addr),
             Endpoint::Inproc(addr) => format!("inproc://{}", addr),
+            Endpoint::Ipc(addr) => format!("ipc://{}", addr),
             Endpoint::Udp(addr) => format!("udp://{}", addr),
             Endpoint::Epgm(addr) => format!("pgm://{}", addr),
             Endpoint::Pgm(addr) => format!("epgm://{}", addr),
@@ -1400,4 +1788,73 @@ mod test {
     test_addr_ser_de!(pgm, PgmAddr, "0.0.0.0:3000");
     test_addr_ser_de!(epgm, EpgmAddr, "0.0.0.0:3000");
     test_addr_ser_de!(inproc, InprocAddr, "test");
+    test_addr_ser_de!(ipc, IpcAddr, "/tmp/test");
+    test_addr_ser_de!(ipc_abstract, IpcAddr, "@test");
+
+    #[test]
+    fn test_subnet() {
+        use crate::addr::Subnet;
+        use std::{net::IpAddr, str::FromStr};
+
+        let subnet = Subnet::from_str("10.1.2.3/8").unwrap();
+        assert_eq!(subnet.to_string(), "10.0.0.0/8");
+        assert!(subnet.contains(IpAddr::from_str("10.255.0.1").unwrap()));
+        assert!(subnet.contains(IpAddr::from_str("::ffff:10.0.0.1").unwrap()));
+        assert!(!subnet.contains(IpAddr::from_str("11.0.0.1").unwrap()));
+        assert!(!subnet.contains(IpAddr::from_str("::a00:1").unwrap()));
+
+        let subnet = Subnet::from_str("2001:db8::/32").unwrap();
+        assert!(subnet.contains(IpAddr::from_str("2001:db8::1").unwrap()));
+        assert!(!subnet.contains(IpAddr::from_str("2001:db9::1").unwrap()));
+
+        let host = Subnet::from_str("127.0.0.1").unwrap();
+        assert_eq!(host.prefix_len(), 32);
+        assert!(host.contains(IpAddr::from_str("127.0.0.1").unwrap()));
+        assert!(!host.contains(IpAddr::from_str("127.0.0.2").unwrap()));
+
+        let any = Subnet::from_str("0.0.0.0/0").unwrap();
+        assert!(any.contains(IpAddr::from_str("1.2.3.4").unwrap()));
+        assert!(!any.contains(IpAddr::from_str("::1").unwrap()));
+
+        Subnet::from_str("10.0.0.0/33").unwrap_err();
+        Subnet::from_str("::/129").unwrap_err();
+        Subnet::from_str("10.0.0.0/").unwrap_err();
+        Subnet::from_str("eth0/8").unwrap_err();
+
+        let ron = serde_yaml::to_string(&subnet).unwrap();
+        let de: Subnet = serde_yaml::from_str(&ron).unwrap();
+        assert_eq!(subnet, de);
+    }
+
+    #[test]
+    fn test_ipc() {
+        use crate::{addr::Endpoint, prelude::*, *};
+        use std::env;
+
+        let path = env::temp_dir().join(InprocAddr::new_unique().as_str());
+        let addr: IpcAddr = path.to_str().unwrap().try_into().unwrap();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+
+        let bound = server.last_endpoint().unwrap();
+        assert_eq!(bound, Endpoint::Ipc(addr));
+
+        let client = ClientBuilder::new().connect(bound).build().unwrap();
+        client.send("").unwrap();
+        server.recv_msg().unwrap();
+
+        drop(client);
+        drop(server);
+        // ØMQ might not have removed the socket file yet.
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_ipc_invalid() {
+        use crate::IpcAddr;
+        use std::str::FromStr;
+
+        IpcAddr::from_str("").unwrap_err();
+        IpcAddr::from_str("@").unwrap_err();
+        IpcAddr::from_str("/tmp/\0").unwrap_err();
+    }
 }
@@ -272,6 +272,12 @@ impl AsRef<GroupSlice> for Group {
     }
 }
 
+impl AsRef<GroupSlice> for GroupSlice {
+    fn as_ref(&self) -> &GroupSlice {
+        self
+    }
+}
+
 impl ops::Deref for Group {
     type Target = GroupSlice;
 
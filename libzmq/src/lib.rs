@@ -4,6 +4,8 @@
 
 pub use failure;
 
+#[cfg(feature = "async")]
+mod asynchronous;
 #[macro_use]
 mod core;
 pub mod auth;
@@ -11,20 +13,29 @@ mod ctx;
 mod endpoint;
 mod error;
 mod group;
+pub mod monitor;
 mod msg;
 mod old;
 pub mod poll;
+mod proxy;
+pub mod reactor;
 mod socket;
+pub mod timer;
 mod utils;
+pub mod z85;
+pub mod zpl;
 
-pub use crate::core::{Heartbeat, Period};
+pub use crate::core::{BufPool, Heartbeat, Period, PooledBuf, RecvLen};
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncSocket;
 pub use ctx::{Ctx, CtxBuilder, CtxHandle};
 pub use endpoint::{
-    EpgmAddr, InprocAddr, PgmAddr, TcpAddr, UdpAddr, INPROC_MAX_SIZE,
+    EpgmAddr, InprocAddr, IpcAddr, PgmAddr, TcpAddr, UdpAddr, INPROC_MAX_SIZE,
 };
 pub use error::{Error, ErrorKind};
 pub use group::*;
 pub use msg::*;
+pub use proxy::{Proxy, ProxyBuilder, ProxyHandle, ProxyStats, SocketStats};
 pub use socket::{
     Client, ClientBuilder, Dish, DishBuilder, Gather, GatherBuilder, Radio,
     RadioBuilder, Scatter, ScatterBuilder, Server, ServerBuilder, SocketType,
@@ -43,8 +54,8 @@ pub mod config {
 /// Address related types.
 pub mod addr {
     pub use crate::endpoint::{
-        AddrParseError, Endpoint, Hostname, Interface, IntoIpAddrs, Port,
-        SocketAddr, SrcAddr,
+        AddrParseError, Endpoint, Hostname, Interface, IntoIpAddrs,
+        IntoSubnets, Port, SocketAddr, SrcAddr, Subnet,
     };
 }
 
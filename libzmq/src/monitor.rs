@@ -0,0 +1,476 @@
+//! Socket event monitoring.
+
+use crate::{
+    addr::Endpoint,
+    auth::StatusCode,
+    core::{GetRawSocket, RawSocket},
+    error::*,
+    old::{OldSocket, OldSocketType},
+    poll::Pollable,
+    InprocAddr, Msg,
+};
+use libzmq_sys as sys;
+
+use std::{
+    convert::{TryFrom, TryInto},
+    os::raw::c_long,
+    time::Duration,
+};
+
+/// A protocol error that caused a handshake to fail.
+///
+/// See [`zmq_socket_monitor_versioned`].
+///
+/// [`zmq_socket_monitor_versioned`]: http://api.zeromq.org/master:zmq-socket-monitor-versioned
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ProtocolError {
+    ZmtpUnspecified,
+    ZmtpUnexpectedCommand,
+    ZmtpInvalidSequence,
+    ZmtpKeyExchange,
+    ZmtpMalformedCommandUnspecified,
+    ZmtpMalformedCommandMessage,
+    ZmtpMalformedCommandHello,
+    ZmtpMalformedCommandInitiate,
+    ZmtpMalformedCommandError,
+    ZmtpMalformedCommandReady,
+    ZmtpMalformedCommandWelcome,
+    ZmtpInvalidMetadata,
+    ZmtpCryptographic,
+    ZmtpMechanismMismatch,
+    ZapUnspecified,
+    ZapMalformedReply,
+    ZapBadRequestId,
+    ZapBadVersion,
+    ZapInvalidStatusCode,
+    ZapInvalidMetadata,
+}
+
+impl From<u64> for ProtocolError {
+    fn from(code: u64) -> Self {
+        match code as u32 {
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_UNEXPECTED_COMMAND => {
+                ProtocolError::ZmtpUnexpectedCommand
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_INVALID_SEQUENCE => {
+                ProtocolError::ZmtpInvalidSequence
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_KEY_EXCHANGE => {
+                ProtocolError::ZmtpKeyExchange
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_UNSPECIFIED => {
+                ProtocolError::ZmtpMalformedCommandUnspecified
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_MESSAGE => {
+                ProtocolError::ZmtpMalformedCommandMessage
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_HELLO => {
+                ProtocolError::ZmtpMalformedCommandHello
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_INITIATE => {
+                ProtocolError::ZmtpMalformedCommandInitiate
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_ERROR => {
+                ProtocolError::ZmtpMalformedCommandError
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_READY => {
+                ProtocolError::ZmtpMalformedCommandReady
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_WELCOME => {
+                ProtocolError::ZmtpMalformedCommandWelcome
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_INVALID_METADATA => {
+                ProtocolError::ZmtpInvalidMetadata
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_CRYPTOGRAPHIC => {
+                ProtocolError::ZmtpCryptographic
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_MECHANISM_MISMATCH => {
+                ProtocolError::ZmtpMechanismMismatch
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZAP_MALFORMED_REPLY => {
+                ProtocolError::ZapMalformedReply
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZAP_BAD_REQUEST_ID => {
+                ProtocolError::ZapBadRequestId
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZAP_BAD_VERSION => {
+                ProtocolError::ZapBadVersion
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZAP_INVALID_STATUS_CODE => {
+                ProtocolError::ZapInvalidStatusCode
+            }
+            sys::ZMQ_PROTOCOL_ERROR_ZAP_INVALID_METADATA => {
+                ProtocolError::ZapInvalidMetadata
+            }
+            // Unknown ZAP errors are reported as unspecified.
+            code if code & sys::ZMQ_PROTOCOL_ERROR_ZAP_UNSPECIFIED != 0 => {
+                ProtocolError::ZapUnspecified
+            }
+            _ => ProtocolError::ZmtpUnspecified,
+        }
+    }
+}
+
+/// An event that occured on a monitored socket.
+///
+/// Events that relate to a listener or connecter contain the `endpoint`
+/// it was bound or connected to. Events that relate to an established
+/// connection contain its `local` and `remote` endpoints when they are
+/// known.
+///
+/// See [`zmq_socket_monitor_versioned`].
+///
+/// [`zmq_socket_monitor_versioned`]: http://api.zeromq.org/master:zmq-socket-monitor-versioned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SocketEvent {
+    /// The socket has successfully connected to a remote peer.
+    Connected {
+        local: Option<Endpoint>,
+        remote: Option<Endpoint>,
+    },
+    /// A connect request on the socket is pending.
+    ConnectDelayed { endpoint: Endpoint },
+    /// A connect request failed, and is now being retried after
+    /// the given interval.
+    ConnectRetried {
+        endpoint: Endpoint,
+        interval: Duration,
+    },
+    /// The socket was successfully bound to a network interface.
+    Listening { endpoint: Endpoint },
+    /// The socket could not bind to the given interface.
+    BindFailed { endpoint: Endpoint },
+    /// The socket has accepted a connection from a remote peer.
+    Accepted {
+        local: Option<Endpoint>,
+        remote: Option<Endpoint>,
+    },
+    /// The socket has rejected a connection from a remote peer.
+    AcceptFailed { endpoint: Endpoint },
+    /// The socket was closed.
+    Closed { endpoint: Endpoint },
+    /// The socket close failed.
+    CloseFailed { endpoint: Endpoint },
+    /// The socket was disconnected unexpectedly.
+    Disconnected {
+        local: Option<Endpoint>,
+        remote: Option<Endpoint>,
+    },
+    /// The monitored socket was closed or another monitor was
+    /// installed on it. No more events will be received.
+    MonitorStopped,
+    /// The ZMTP security mechanism handshake succeeded.
+    HandshakeSucceeded {
+        local: Option<Endpoint>,
+        remote: Option<Endpoint>,
+    },
+    /// The handshake failed for a reason unrelated to the protocol,
+    /// such as the peer disconnecting.
+    HandshakeFailedNoDetail {
+        local: Option<Endpoint>,
+        remote: Option<Endpoint>,
+    },
+    /// The handshake failed due to a ZMTP or ZAP protocol error.
+    HandshakeFailedProtocol {
+        local: Option<Endpoint>,
+        remote: Option<Endpoint>,
+        error: ProtocolError,
+    },
+    /// The handshake was rejected by the authentication handler.
+    HandshakeFailedAuth {
+        local: Option<Endpoint>,
+        remote: Option<Endpoint>,
+        status_code: StatusCode,
+    },
+    /// An event that could not be parsed, either because it is unknown to
+    /// *libzmq* or because its endpoint is not supported. It contains the
+    /// raw event id, values and endpoints.
+    Unknown {
+        event: u32,
+        values: Vec<u64>,
+        local: Option<String>,
+        remote: Option<String>,
+    },
+}
+
+fn to_u64(msg: &Msg) -> u64 {
+    let bytes: [u8; 8] = msg.as_bytes().try_into().unwrap();
+    u64::from_ne_bytes(bytes)
+}
+
+fn to_endpoint(msg: &Msg) -> Option<Endpoint> {
+    msg.to_str().ok().and_then(Endpoint::try_from_zmq)
+}
+
+fn to_string(msg: &Msg) -> Option<String> {
+    msg.to_str()
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(str::to_owned)
+}
+
+impl SocketEvent {
+    /// Parse a version 2 monitor event.
+    ///
+    /// The parts are the event id, the number of values, the values
+    /// themselves and finally the local and remote endpoints.
+    fn from_parts(parts: &[Msg]) -> Self {
+        let event = to_u64(&parts[0]);
+        let count = to_u64(&parts[1]) as usize;
+        let values: Vec<u64> = parts[2..2 + count].iter().map(to_u64).collect();
+        let local = to_endpoint(&parts[2 + count]);
+        let remote = to_endpoint(&parts[3 + count]);
+        // Events on listeners only have a local endpoint while events
+        // on connecters only have a remote one.
+        let endpoint = local.clone().or_else(|| remote.clone());
+        let unknown = || SocketEvent::Unknown {
+            event: event as u32,
+            values: values.clone(),
+            local: to_string(&parts[2 + count]),
+            remote: to_string(&parts[3 + count]),
+        };
+
+        match (event as u32, endpoint) {
+            (sys::ZMQ_EVENT_CONNECTED, _) => {
+                SocketEvent::Connected { local, remote }
+            }
+            (sys::ZMQ_EVENT_CONNECT_DELAYED, Some(endpoint)) => {
+                SocketEvent::ConnectDelayed { endpoint }
+            }
+            (sys::ZMQ_EVENT_CONNECT_RETRIED, Some(endpoint)) => {
+                SocketEvent::ConnectRetried {
+                    endpoint,
+                    interval: Duration::from_millis(values[0]),
+                }
+            }
+            (sys::ZMQ_EVENT_LISTENING, Some(endpoint)) => {
+                SocketEvent::Listening { endpoint }
+            }
+            (sys::ZMQ_EVENT_BIND_FAILED, Some(endpoint)) => {
+                SocketEvent::BindFailed { endpoint }
+            }
+            (sys::ZMQ_EVENT_ACCEPTED, _) => {
+                SocketEvent::Accepted { local, remote }
+            }
+            (sys::ZMQ_EVENT_ACCEPT_FAILED, Some(endpoint)) => {
+                SocketEvent::AcceptFailed { endpoint }
+            }
+            (sys::ZMQ_EVENT_CLOSED, Some(endpoint)) => {
+                SocketEvent::Closed { endpoint }
+            }
+            (sys::ZMQ_EVENT_CLOSE_FAILED, Some(endpoint)) => {
+                SocketEvent::CloseFailed { endpoint }
+            }
+            (sys::ZMQ_EVENT_DISCONNECTED, _) => {
+                SocketEvent::Disconnected { local, remote }
+            }
+            (sys::ZMQ_EVENT_MONITOR_STOPPED, _) => SocketEvent::MonitorStopped,
+            (sys::ZMQ_EVENT_HANDSHAKE_SUCCEEDED, _) => {
+                SocketEvent::HandshakeSucceeded { local, remote }
+            }
+            (sys::ZMQ_EVENT_HANDSHAKE_FAILED_NO_DETAIL, _) => {
+                SocketEvent::HandshakeFailedNoDetail { local, remote }
+            }
+            (sys::ZMQ_EVENT_HANDSHAKE_FAILED_PROTOCOL, _) => {
+                SocketEvent::HandshakeFailedProtocol {
+                    local,
+                    remote,
+                    error: values[0].into(),
+                }
+            }
+            (sys::ZMQ_EVENT_HANDSHAKE_FAILED_AUTH, _) => {
+                match StatusCode::try_from(values[0] as c_long) {
+                    Ok(status_code) => SocketEvent::HandshakeFailedAuth {
+                        local,
+                        remote,
+                        status_code,
+                    },
+                    Err(_) => unknown(),
+                }
+            }
+            _ => unknown(),
+        }
+    }
+}
+
+/// A handle that receives the [`SocketEvent`] of a monitored socket.
+///
+/// It is obtained via the [`monitor`] method of a socket. Only a single
+/// monitor can be active for a given socket, so creating a new one will stop
+/// the previous.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::{prelude::*, *, monitor::SocketEvent};
+///
+/// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+///
+/// let server = Server::new()?;
+/// let mut monitor = server.monitor()?;
+///
+/// server.bind(&addr)?;
+/// let bound = server.last_endpoint()?;
+///
+/// match monitor.recv_event()? {
+///     SocketEvent::Listening { endpoint } => assert_eq!(endpoint, bound),
+///     _ => unreachable!(),
+/// }
+///
+/// let client = ClientBuilder::new().connect(&bound).build()?;
+///
+/// loop {
+///     if let SocketEvent::HandshakeSucceeded { .. } = monitor.recv_event()? {
+///         break;
+///     }
+/// }
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`SocketEvent`]: enum.SocketEvent.html
+/// [`monitor`]: ../prelude/trait.Socket.html#method.monitor
+#[derive(Debug)]
+pub struct Monitor {
+    inner: OldSocket,
+}
+
+impl Monitor {
+    pub(crate) fn new(raw_socket: &RawSocket) -> Result<Self, Error> {
+        let endpoint: Endpoint = InprocAddr::new_unique().into();
+        raw_socket.monitor(&endpoint)?;
+
+        let mut inner =
+            OldSocket::with_ctx(OldSocketType::Pair, raw_socket.ctx())?;
+        inner.connect(endpoint)?;
+
+        Ok(Self { inner })
+    }
+
+    /// Receive the next event from the monitored socket, blocking until
+    /// one is available.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`]
+    /// * [`Interrupted`]
+    ///
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    pub fn recv_event(&mut self) -> Result<SocketEvent, Error> {
+        let parts = self.inner.recv_msg_multipart()?;
+        Ok(SocketEvent::from_parts(&parts))
+    }
+
+    /// Try to receive the next event from the monitored socket without
+    /// blocking.
+    ///
+    /// # Returned Errors
+    /// * [`WouldBlock`] (no event is pending)
+    /// * [`InvalidCtx`]
+    /// * [`Interrupted`]
+    ///
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    pub fn try_recv_event(&mut self) -> Result<SocketEvent, Error> {
+        let parts = self.inner.try_recv_msg_multipart()?;
+        Ok(SocketEvent::from_parts(&parts))
+    }
+}
+
+impl<'a> From<&'a Monitor> for Pollable<'a> {
+    fn from(monitor: &'a Monitor) -> Self {
+        Pollable::Socket(monitor.inner.raw_socket())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, *};
+
+    #[test]
+    fn test_monitor_disconnected() {
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new().bind(addr).build().unwrap();
+        let bound = server.last_endpoint().unwrap();
+
+        let mut monitor = server.monitor().unwrap();
+
+        let client = ClientBuilder::new().connect(&bound).build().unwrap();
+
+        let remote = loop {
+            if let SocketEvent::Accepted { local, remote } =
+                monitor.recv_event().unwrap()
+            {
+                assert_eq!(local, Some(bound.clone()));
+                break remote.unwrap();
+            }
+        };
+
+        drop(client);
+
+        loop {
+            if let SocketEvent::Disconnected {
+                remote: Some(r), ..
+            } = monitor.recv_event().unwrap()
+            {
+                assert_eq!(r, remote);
+                break;
+            }
+        }
+
+        // Closing the socket stops its monitor.
+        drop(server);
+        while monitor.recv_event().unwrap() != SocketEvent::MonitorStopped {}
+    }
+
+    #[test]
+    fn test_unknown_event() {
+        let parts = |event: u32, endpoint: &str| {
+            vec![
+                Msg::from(&u64::from(event).to_ne_bytes()[..]),
+                Msg::from(&1_u64.to_ne_bytes()[..]),
+                Msg::from(&42_u64.to_ne_bytes()[..]),
+                Msg::from(endpoint),
+                Msg::from(""),
+            ]
+        };
+
+        let event = SocketEvent::from_parts(&parts(1 << 30, "tcp://a:1"));
+        assert_eq!(
+            event,
+            SocketEvent::Unknown {
+                event: 1 << 30,
+                values: vec![42],
+                local: Some("tcp://a:1".to_owned()),
+                remote: None,
+            }
+        );
+
+        let event =
+            SocketEvent::from_parts(&parts(sys::ZMQ_EVENT_LISTENING, "x://"));
+        match event {
+            SocketEvent::Unknown { event, .. } => {
+                assert_eq!(event, sys::ZMQ_EVENT_LISTENING)
+            }
+            _ => panic!("unexpected {:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_monitor_try_recv_event() {
+        let server = Server::new().unwrap();
+        let mut monitor = server.monitor().unwrap();
+
+        let err = monitor.try_recv_event().unwrap_err();
+        match err.kind() {
+            ErrorKind::WouldBlock => (),
+            _ => panic!(),
+        }
+    }
+}
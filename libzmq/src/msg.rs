@@ -1,4 +1,4 @@
-use crate::{error::msg_from_errno, GroupSlice};
+use crate::{core::metadata_key, error::msg_from_errno, GroupSlice};
 use libzmq_sys as sys;
 use sys::errno;
 
@@ -7,11 +7,13 @@ use log::error;
 use serde::{Deserialize, Serialize};
 
 use std::{
-    ffi::CStr,
+    ffi::{CStr, CString},
     fmt,
+    net::IpAddr,
     os::raw::c_void,
     ptr, slice,
     str::{self, Utf8Error},
+    sync::Arc,
 };
 
 /// A generated ID used to route messages to the approriate client.
@@ -60,8 +62,20 @@ pub struct RoutingId(pub u32);
 /// or components of the same application. ØMQ messages have no internal
 /// structure and from the point of view of ØMQ itself they are considered
 /// to be opaque binary data.
+///
+/// # Zero-Copy
+/// A `Msg` can be created from a `Vec<u8>`, a `Box<[u8]>` or a `String`
+/// without copying. Content that is shared, such as an `Arc<[u8]>`, can
+/// also be sent without copying via [`from_shared`]. This allows the same
+/// large payload to be sent to many destinations. With the `bytes` feature,
+/// a `bytes::Bytes` can also be converted into a `Msg` without copying.
+///
+/// [`from_shared`]: #method.from_shared
 pub struct Msg {
     msg: sys::zmq_msg_t,
+    // Whether the content is shared with its owner, in which case it is
+    // copied before being mutated.
+    shared: bool,
 }
 
 impl From<RoutingId> for u32 {
@@ -116,6 +130,88 @@ impl Msg {
         }
     }
 
+    /// Create a `Msg` that references the content of `owner` without
+    /// copying it.
+    ///
+    /// The owner is kept alive until ØMQ releases the message, which can
+    /// happen in a background I/O thread after the message was sent.
+    ///
+    /// Since the content is shared, it is copied if mutated via
+    /// [`as_bytes_mut`].
+    ///
+    /// See [`zmq_msg_init_data`].
+    ///
+    /// [`as_bytes_mut`]: #method.as_bytes_mut
+    /// [`zmq_msg_init_data`]: http://api.zeromq.org/master:zmq-msg-init-data
+    ///
+    /// ```
+    /// # use failure::Error;
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// use libzmq::{prelude::*, *};
+    /// use std::sync::Arc;
+    ///
+    /// let addr: InprocAddr = InprocAddr::new_unique();
+    ///
+    /// let radio = RadioBuilder::new()
+    ///     .bind(&addr)
+    ///     .build()?;
+    ///
+    /// let payload: Arc<[u8]> = vec![0; 4 * 1024 * 1024].into();
+    ///
+    /// // Each message references the same payload.
+    /// for name in &["A", "B", "C"] {
+    ///     let group: Group = name.to_string().try_into()?;
+    ///     let mut msg = Msg::from_shared(Arc::clone(&payload));
+    ///     assert_eq!(msg.as_bytes().as_ptr(), payload.as_ptr());
+    ///
+    ///     msg.set_group(group);
+    ///     radio.send(msg)?;
+    /// }
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn from_shared<T>(owner: T) -> Self
+    where
+        T: AsRef<[u8]> + Send + 'static,
+    {
+        unsafe extern "C" fn drop_owner<T>(
+            _data: *mut c_void,
+            hint: *mut c_void,
+        ) {
+            // Convert the hint back into the boxed owner and drop it.
+            drop(Box::from_raw(hint as *mut T));
+        }
+
+        // The content of the owner must not move, so it is boxed first.
+        let owner = Box::new(owner);
+        let (data, size) = {
+            let bytes = (*owner).as_ref();
+            (bytes.as_ptr(), bytes.len())
+        };
+
+        if size == 0 {
+            return Msg::new();
+        }
+
+        let hint = Box::into_raw(owner);
+        let mut msg = unsafe {
+            Self::deferred_alloc(|msg| {
+                sys::zmq_msg_init_data(
+                    msg,
+                    data as *mut c_void,
+                    size as size_t,
+                    Some(drop_owner::<T>),
+                    hint as *mut c_void,
+                )
+            })
+        };
+        msg.shared = true;
+
+        msg
+    }
+
     /// Returns the message content size in bytes.
     ///
     /// See [`zmq_msg_size`].
@@ -171,7 +267,23 @@ impl Msg {
     }
 
     /// Return the message content as a mutable byte slice.
+    ///
+    /// If the content is shared with its owner (see [`from_shared`]), it is
+    /// first copied into a new message along with its routing ID and group.
+    ///
+    /// [`from_shared`]: #method.from_shared
     pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        if self.shared {
+            let mut copy = Msg::from(self.as_bytes());
+            if let Some(id) = self.routing_id() {
+                copy.set_routing_id(id);
+            }
+            if let Some(group) = self.group() {
+                copy.set_group(group);
+            }
+            *self = copy;
+        }
+
         // This is safe because we're constraining the slice to the lifetime of
         // this message.
         unsafe {
@@ -279,6 +391,79 @@ impl Msg {
         }
     }
 
+    /// Get the metadata property of the message associated with `name`.
+    ///
+    /// Metadata properties are only available on received messages. This
+    /// includes the properties set by the [`ZAP`] handler, as well as the
+    /// properties exchanged during the connection handshake.
+    ///
+    /// Returns `None` if the property does not exist or is not valid UTF-8.
+    ///
+    /// See [`zmq_msg_gets`].
+    ///
+    /// [`zmq_msg_gets`]: http://api.zeromq.org/master:zmq-msg-gets
+    /// [`ZAP`]: https://rfc.zeromq.org/spec:27/ZAP/
+    pub fn property(&self, name: &str) -> Option<&str> {
+        let c_string = CString::new(name).ok()?;
+        self.property_c_str(&c_string)
+    }
+
+    /// The IP address of the peer that sent the message.
+    ///
+    /// This is only available for the `tcp` transport. IPv4-mapped IPv6
+    /// addresses are converted back to IPv4.
+    pub fn peer_addr(&self) -> Option<IpAddr> {
+        let addr: IpAddr = self
+            .property_c_str(property_name(sys::ZMQ_MSG_PROPERTY_PEER_ADDRESS))?
+            .parse()
+            .ok()?;
+
+        match addr {
+            IpAddr::V6(ipv6) => match ipv6.segments() {
+                [0, 0, 0, 0, 0, 0xffff, ..] => {
+                    Some(IpAddr::V4(ipv6.to_ipv4().unwrap()))
+                }
+                _ => Some(addr),
+            },
+            IpAddr::V4(_) => Some(addr),
+        }
+    }
+
+    /// The user id of the peer that sent the message, as returned by the
+    /// `ZAP` handler during authentication.
+    ///
+    /// For the `PLAIN` mechanism this is the username, while for the `CURVE`
+    /// mechanism this is the public key of the peer.
+    pub fn user_id(&self) -> Option<&str> {
+        self.property_c_str(property_name(sys::ZMQ_MSG_PROPERTY_USER_ID))
+    }
+
+    /// The type of the socket that sent the message (e.g. `"CLIENT"`).
+    pub fn socket_type(&self) -> Option<&str> {
+        self.property_c_str(property_name(sys::ZMQ_MSG_PROPERTY_SOCKET_TYPE))
+    }
+
+    /// The application metadata property `key` that the peer sent during
+    /// the connection handshake.
+    ///
+    /// The `X-` prefix required by ØMQ is prepended to `key` if missing.
+    pub fn metadata(&self, key: &str) -> Option<&str> {
+        self.property(&metadata_key(key))
+    }
+
+    fn property_c_str(&self, name: &CStr) -> Option<&str> {
+        let char_ptr =
+            unsafe { sys::zmq_msg_gets(self.as_ptr(), name.as_ptr()) };
+
+        if char_ptr.is_null() {
+            None
+        } else {
+            // The property lives as long as the message.
+            let c_str = unsafe { CStr::from_ptr(char_ptr) };
+            c_str.to_str().ok()
+        }
+    }
+
     // Defers the allocation of a zmq_msg_t to the closure.
     //
     // TODO Consider allocating without zeroing.
@@ -295,13 +480,19 @@ impl Msg {
             panic!(msg_from_errno(sys::zmq_errno()));
         }
 
-        Msg { msg }
+        Msg { msg, shared: false }
     }
 
     pub(crate) fn as_mut_ptr(&mut self) -> *mut sys::zmq_msg_t {
         &mut self.msg
     }
 
+    // Must be called once `libzmq` replaced the `zmq_msg_t`, since its new
+    // content is no longer shared.
+    pub(crate) fn clear_shared(&mut self) {
+        self.shared = false;
+    }
+
     pub(crate) fn as_ptr(&self) -> *const sys::zmq_msg_t {
         &self.msg
     }
@@ -312,6 +503,10 @@ impl Msg {
     }
 }
 
+fn property_name(bytes: &'static [u8]) -> &'static CStr {
+    CStr::from_bytes_with_nul(bytes).unwrap()
+}
+
 impl PartialEq for Msg {
     /// Compares the two underlying raw C pointers.
     fn eq(&self, other: &Self) -> bool {
@@ -362,6 +557,7 @@ impl Clone for Msg {
                 _ => panic!(msg_from_errno(errno)),
             }
         }
+        msg.shared = self.shared;
 
         msg
     }
@@ -415,6 +611,29 @@ impl From<Box<[u8]>> for Msg {
     }
 }
 
+impl From<Arc<[u8]>> for Msg {
+    /// Converts a shared byte slice into a `Msg` without copying.
+    ///
+    /// See [`Msg::from_shared`].
+    ///
+    /// [`Msg::from_shared`]: struct.Msg.html#method.from_shared
+    fn from(data: Arc<[u8]>) -> Self {
+        Msg::from_shared(data)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<bytes::Bytes> for Msg {
+    /// Converts a `Bytes` into a `Msg` without copying.
+    ///
+    /// See [`Msg::from_shared`].
+    ///
+    /// [`Msg::from_shared`]: struct.Msg.html#method.from_shared
+    fn from(data: bytes::Bytes) -> Self {
+        Msg::from_shared(data)
+    }
+}
+
 impl<'a> From<&[u8]> for Msg {
     /// Converts a byte slice into a `Msg` by copying.
     fn from(slice: &[u8]) -> Self {
@@ -506,4 +725,89 @@ mod tests {
             assert_eq!(i, j.0);
         }
     }
+
+    #[test]
+    fn test_from_shared() {
+        let data: Arc<[u8]> = b"some data".to_vec().into();
+
+        let msg = Msg::from(Arc::clone(&data));
+        assert_eq!(msg.as_bytes().as_ptr(), data.as_ptr());
+        let clone = msg.clone();
+        drop(msg);
+        assert_eq!(Arc::strong_count(&data), 2);
+        drop(clone);
+        assert_eq!(Arc::strong_count(&data), 1);
+
+        // Mutating the msg copies the shared content.
+        let mut msg = Msg::from_shared(Arc::clone(&data));
+        msg.set_routing_id(RoutingId(1));
+        msg.as_bytes_mut()[0] = b'S';
+        assert_eq!(msg.as_bytes(), b"Some data");
+        assert_eq!(msg.routing_id(), Some(RoutingId(1)));
+        assert_eq!(&data[..], b"some data");
+        assert_eq!(Arc::strong_count(&data), 1);
+
+        assert!(Msg::from_shared(Vec::new()).is_empty());
+
+        // The received content replaces the shared content.
+        use crate::{prelude::*, *};
+
+        let addr: InprocAddr = InprocAddr::new_unique();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let mut msg = Msg::from_shared(Arc::clone(&data));
+        client.send("other data").unwrap();
+        server.recv(&mut msg).unwrap();
+        assert!(!msg.shared);
+        msg.as_bytes_mut()[0] = b'O';
+        assert_eq!(msg.as_bytes(), b"Other data");
+        assert_eq!(&data[..], b"some data");
+    }
+
+    #[test]
+    fn test_properties() {
+        use crate::{auth::*, prelude::*, *};
+        use std::{net::Ipv4Addr, time::Duration};
+
+        let msg = Msg::new();
+        assert!(msg.property("Socket-Type").is_none());
+        assert!(msg.property("Socket\0Type").is_none());
+
+        // Create a new context to use a disctinct auth handler.
+        let ctx = Ctx::new();
+        let handle = ctx.handle();
+
+        let creds = PlainClientCreds::new("user", "pwd");
+        let _ = AuthBuilder::new()
+            .hash_params(HashParams::new(8, 1, 1).unwrap())
+            .plain_registry(&creds)
+            .with_ctx(handle)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .mechanism(Mechanism::PlainServer)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(handle)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(bound)
+            .mechanism(creds)
+            .with_ctx(handle)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        let msg = server.recv_msg().unwrap();
+
+        assert_eq!(msg.user_id(), Some("user"));
+        assert_eq!(msg.socket_type(), Some("CLIENT"));
+        assert_eq!(msg.property("Socket-Type"), Some("CLIENT"));
+        assert_eq!(msg.peer_addr(), Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    }
 }
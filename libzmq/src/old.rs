@@ -36,8 +36,14 @@ fn send(
     }
 }
 
-fn recv(mut_sock_ptr: *mut c_void, msg: &mut Msg) -> Result<(), Error> {
-    let rc = unsafe { sys::zmq_msg_recv(msg.as_mut_ptr(), mut_sock_ptr, 0) };
+fn recv(
+    mut_sock_ptr: *mut c_void,
+    msg: &mut Msg,
+    no_block: bool,
+) -> Result<(), Error> {
+    let rc = unsafe {
+        sys::zmq_msg_recv(msg.as_mut_ptr(), mut_sock_ptr, no_block as c_int)
+    };
 
     if rc == -1 {
         let errno = unsafe { sys::zmq_errno() };
@@ -50,6 +56,7 @@ fn recv(mut_sock_ptr: *mut c_void, msg: &mut Msg) -> Result<(), Error> {
 
         Err(err)
     } else {
+        msg.clear_shared();
         Ok(())
     }
 }
@@ -96,6 +103,14 @@ impl OldSocket {
         self.inner.bind(&endpoint)
     }
 
+    pub(crate) fn connect<E>(&mut self, endpoint: E) -> Result<(), Error>
+    where
+        E: Into<Endpoint>,
+    {
+        let endpoint = endpoint.into();
+        self.inner.connect(&endpoint)
+    }
+
     pub(crate) fn send<M>(&mut self, msg: M, more: bool) -> Result<(), Error>
     where
         M: Into<Msg>,
@@ -125,10 +140,21 @@ impl OldSocket {
     }
 
     pub(crate) fn recv_msg_multipart(&mut self) -> Result<Vec<Msg>, Error> {
+        self.recv_multipart(false)
+    }
+
+    pub(crate) fn try_recv_msg_multipart(&mut self) -> Result<Vec<Msg>, Error> {
+        self.recv_multipart(true)
+    }
+
+    fn recv_multipart(&mut self, no_block: bool) -> Result<Vec<Msg>, Error> {
         let mut vec = Vec::new();
         loop {
             let mut msg = Msg::new();
-            recv(self.inner.as_mut_ptr(), &mut msg)?;
+            // Multipart messages are delivered atomically so only
+            // the first part can block.
+            let no_block = no_block && vec.is_empty();
+            recv(self.inner.as_mut_ptr(), &mut msg, no_block)?;
             let has_more = msg.has_more();
             vec.push(msg);
             if !has_more {
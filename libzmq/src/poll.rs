@@ -9,6 +9,7 @@ use crate::{
     error::{msg_from_errno, Error, ErrorKind},
     old::OldSocket,
     socket::*,
+    timer::Timers,
 };
 use libzmq_sys as sys;
 use sys::errno;
@@ -124,6 +125,7 @@ impl From<PollId> for usize {
 /// ```
 ///
 /// [`Poller`]: struct.Poller.html#method.add
+#[derive(Copy, Clone, Debug)]
 pub enum Pollable<'a> {
     /// A `Socket` type.
     Socket(&'a RawSocket),
@@ -730,6 +732,32 @@ impl Poller {
         }
     }
 
+    /// Returns the file descriptor that signals the thread-safe sockets
+    /// monitored by the poller.
+    ///
+    /// It becomes readable when the state of one of these sockets changes,
+    /// until the poller is polled again.
+    #[cfg(feature = "async")]
+    pub(crate) fn fd(&self) -> Result<RawFd, Error> {
+        let mut fd: RawFd = -1;
+        let rc = unsafe { sys::zmq_poller_fd(self.poller, &mut fd) };
+
+        if rc == -1 {
+            let errno = unsafe { sys::zmq_errno() };
+            let err = match errno {
+                errno::EINVAL => Error::new(ErrorKind::InvalidInput(
+                    "poller has no thread-safe socket",
+                )),
+                errno::EFAULT => panic!("invalid poller"),
+                _ => panic!("{}", msg_from_errno(errno)),
+            };
+
+            Err(err)
+        } else {
+            Ok(fd)
+        }
+    }
+
     fn wait(&mut self, events: &mut Events, timeout: i64) -> Result<(), Error> {
         events.clear();
         for _i in 0..self.count {
@@ -810,6 +838,36 @@ impl Poller {
             Period::Infinite => self.wait(events, -1),
         }
     }
+
+    /// The poller will wait for events in the monitored elements,
+    /// blocking until at least one event occurs, or the nearest timer
+    /// expires. The expired timers are then executed.
+    ///
+    /// Contrary to [`poll`], the `Events` are empty if only timers expired.
+    /// If there are no active timers, this blocks until an event occurs.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`] (`Ctx` of a polled socket was terminated)
+    /// * [`Interrupted`]
+    ///
+    /// [`poll`]: #method.poll
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    pub fn poll_timers(
+        &mut self,
+        events: &mut Events,
+        timers: &mut Timers,
+    ) -> Result<(), Error> {
+        if let Err(err) = self.poll(events, timers.timeout()) {
+            if err.kind() != ErrorKind::WouldBlock {
+                return Err(err);
+            }
+            events.clear();
+        }
+        timers.execute();
+
+        Ok(())
+    }
 }
 
 impl Default for Poller {
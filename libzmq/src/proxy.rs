@@ -0,0 +1,433 @@
+use crate::{
+    core::GetRawSocket,
+    endpoint::InprocAddr,
+    error::*,
+    old::{OldSocket, OldSocketType},
+    Msg,
+};
+use libzmq_sys as sys;
+use sys::errno;
+
+use std::{convert::TryInto, fmt, ptr};
+
+const PAUSE: &str = "PAUSE";
+const RESUME: &str = "RESUME";
+const TERMINATE: &str = "TERMINATE";
+const STATISTICS: &str = "STATISTICS";
+
+/// The message statistics of one side of a [`Proxy`].
+///
+/// [`Proxy`]: struct.Proxy.html
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SocketStats {
+    msg_in: u64,
+    bytes_in: u64,
+    msg_out: u64,
+    bytes_out: u64,
+}
+
+impl SocketStats {
+    /// The number of messages received by the socket.
+    pub fn msg_in(&self) -> u64 {
+        self.msg_in
+    }
+
+    /// The number of bytes received by the socket.
+    pub fn bytes_in(&self) -> u64 {
+        self.bytes_in
+    }
+
+    /// The number of messages sent by the socket.
+    pub fn msg_out(&self) -> u64 {
+        self.msg_out
+    }
+
+    /// The number of bytes sent by the socket.
+    pub fn bytes_out(&self) -> u64 {
+        self.bytes_out
+    }
+}
+
+/// The message statistics of a [`Proxy`], as returned by
+/// [`ProxyHandle::statistics`].
+///
+/// [`Proxy`]: struct.Proxy.html
+/// [`ProxyHandle::statistics`]: struct.ProxyHandle.html#method.statistics
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ProxyStats {
+    frontend: SocketStats,
+    backend: SocketStats,
+}
+
+impl ProxyStats {
+    fn from_parts(parts: &[Msg]) -> Self {
+        assert_eq!(parts.len(), 8, "invalid statistics reply");
+        let values: Vec<u64> = parts
+            .iter()
+            .map(|part| {
+                let bytes = part.as_bytes().try_into().unwrap();
+                u64::from_ne_bytes(bytes)
+            })
+            .collect();
+
+        Self {
+            frontend: SocketStats {
+                msg_in: values[0],
+                bytes_in: values[1],
+                msg_out: values[2],
+                bytes_out: values[3],
+            },
+            backend: SocketStats {
+                msg_in: values[4],
+                bytes_in: values[5],
+                msg_out: values[6],
+                bytes_out: values[7],
+            },
+        }
+    }
+
+    /// The statistics of the frontend socket.
+    pub fn frontend(&self) -> SocketStats {
+        self.frontend
+    }
+
+    /// The statistics of the backend socket.
+    pub fn backend(&self) -> SocketStats {
+        self.backend
+    }
+}
+
+/// A builder for a steerable [`Proxy`].
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::{prelude::*, *};
+/// use std::thread;
+///
+/// let frontend_addr = InprocAddr::new_unique();
+/// let backend_addr = InprocAddr::new_unique();
+///
+/// let frontend = GatherBuilder::new().bind(&frontend_addr).build()?;
+/// let backend = ScatterBuilder::new().bind(&backend_addr).build()?;
+///
+/// let (proxy, mut handle) = ProxyBuilder::new(frontend, backend).build()?;
+/// let proxy_thread = thread::spawn(move || proxy.run());
+///
+/// let scatter = ScatterBuilder::new().connect(&frontend_addr).build()?;
+/// let gather = GatherBuilder::new().connect(&backend_addr).build()?;
+///
+/// scatter.send("some msg")?;
+/// let msg = gather.recv_msg()?;
+/// assert_eq!(msg.to_str()?, "some msg");
+///
+/// let stats = handle.statistics()?;
+/// assert_eq!(stats.frontend().msg_in(), 1);
+/// assert_eq!(stats.backend().bytes_out(), 8);
+///
+/// handle.terminate()?;
+/// proxy_thread.join().unwrap()?;
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Proxy`]: struct.Proxy.html
+pub struct ProxyBuilder<F, B> {
+    frontend: F,
+    backend: B,
+    capture: Option<Box<dyn GetRawSocket + Send>>,
+}
+
+impl<F, B> ProxyBuilder<F, B>
+where
+    F: GetRawSocket,
+    B: GetRawSocket,
+{
+    /// Create a builder for a proxy between the frontend and the backend
+    /// socket.
+    ///
+    /// The two sockets must be configured before creating the proxy.
+    pub fn new(frontend: F, backend: B) -> Self {
+        Self {
+            frontend,
+            backend,
+            capture: None,
+        }
+    }
+
+    /// Send a copy of every message going through the proxy to the
+    /// capture socket.
+    ///
+    /// The capture socket should be a [`Scatter`] or a [`Radio`] socket.
+    ///
+    /// [`Scatter`]: struct.Scatter.html
+    /// [`Radio`]: struct.Radio.html
+    pub fn capture<C>(mut self, capture: C) -> Self
+    where
+        C: GetRawSocket + Send + 'static,
+    {
+        self.capture = Some(Box::new(capture));
+        self
+    }
+
+    /// Build the [`Proxy`] and the [`ProxyHandle`] that controls it.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`]
+    /// * [`SocketLimit`]
+    ///
+    /// [`Proxy`]: struct.Proxy.html
+    /// [`ProxyHandle`]: struct.ProxyHandle.html
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    /// [`SocketLimit`]: ../enum.ErrorKind.html#variant.SocketLimit
+    pub fn build(self) -> Result<(Proxy<F, B>, ProxyHandle), Error> {
+        // The control socket must share the frontend's `Ctx` since it
+        // uses the inproc transport.
+        let ctx = self.frontend.raw_socket().ctx();
+        let addr = InprocAddr::new_unique();
+
+        let mut control = OldSocket::with_ctx(OldSocketType::Pair, ctx)?;
+        control.bind(&addr)?;
+        let mut handle = OldSocket::with_ctx(OldSocketType::Pair, ctx)?;
+        handle.connect(&addr)?;
+
+        let proxy = Proxy {
+            frontend: self.frontend,
+            backend: self.backend,
+            capture: self.capture,
+            control,
+        };
+
+        Ok((proxy, ProxyHandle { inner: handle }))
+    }
+}
+
+impl<F, B> fmt::Debug for ProxyBuilder<F, B>
+where
+    F: fmt::Debug,
+    B: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyBuilder")
+            .field("frontend", &self.frontend)
+            .field("backend", &self.backend)
+            .field("capture", &self.capture.is_some())
+            .finish()
+    }
+}
+
+/// A built-in ØMQ proxy between a frontend and a backend socket that can be
+/// controlled via a [`ProxyHandle`].
+///
+/// Contrary to the [`proxy`] function, a `Proxy` can be paused, resumed and
+/// terminated without terminating its `Ctx`.
+///
+/// See [`ProxyBuilder`] for an example.
+///
+/// [`ProxyHandle`]: struct.ProxyHandle.html
+/// [`proxy`]: fn.proxy.html
+/// [`ProxyBuilder`]: struct.ProxyBuilder.html
+pub struct Proxy<F, B> {
+    frontend: F,
+    backend: B,
+    capture: Option<Box<dyn GetRawSocket + Send>>,
+    control: OldSocket,
+}
+
+impl<F, B> Proxy<F, B>
+where
+    F: GetRawSocket,
+    B: GetRawSocket,
+{
+    /// Run the proxy in the current thread.
+    ///
+    /// This blocks until the [`ProxyHandle`] terminates the proxy.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`]
+    ///
+    /// [`ProxyHandle`]: struct.ProxyHandle.html
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    pub fn run(self) -> Result<(), Error> {
+        let frontend_socket_ptr = self.frontend.raw_socket().as_mut_ptr();
+        let backend_socket_ptr = self.backend.raw_socket().as_mut_ptr();
+        let capture_socket_ptr = match &self.capture {
+            Some(capture) => capture.raw_socket().as_mut_ptr(),
+            None => ptr::null_mut(),
+        };
+        let control_socket_ptr = self.control.raw_socket().as_mut_ptr();
+
+        let rc = unsafe {
+            sys::zmq_proxy_steerable(
+                frontend_socket_ptr,
+                backend_socket_ptr,
+                capture_socket_ptr,
+                control_socket_ptr,
+            )
+        };
+
+        if rc == -1 {
+            let errno = unsafe { sys::zmq_errno() };
+            let err = match errno {
+                errno::ETERM => Error::new(ErrorKind::InvalidCtx),
+                _ => panic!("{}", msg_from_errno(errno)),
+            };
+
+            Err(err)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<F, B> fmt::Debug for Proxy<F, B>
+where
+    F: fmt::Debug,
+    B: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Proxy")
+            .field("frontend", &self.frontend)
+            .field("backend", &self.backend)
+            .field("capture", &self.capture.is_some())
+            .finish()
+    }
+}
+
+/// A handle to control a running [`Proxy`].
+///
+/// If the `Proxy` is no longer running, the commands have no effect, except
+/// for [`statistics`] which will block forever.
+///
+/// [`Proxy`]: struct.Proxy.html
+/// [`statistics`]: #method.statistics
+#[derive(Debug)]
+pub struct ProxyHandle {
+    inner: OldSocket,
+}
+
+impl ProxyHandle {
+    /// Pause the proxy, which stops forwarding messages.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`]
+    ///
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    pub fn pause(&mut self) -> Result<(), Error> {
+        self.inner.send(PAUSE, false)
+    }
+
+    /// Resume a paused proxy.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`]
+    ///
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    pub fn resume(&mut self) -> Result<(), Error> {
+        self.inner.send(RESUME, false)
+    }
+
+    /// Terminate the proxy, which causes [`run`] to return.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`]
+    ///
+    /// [`run`]: struct.Proxy.html#method.run
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    pub fn terminate(mut self) -> Result<(), Error> {
+        self.inner.send(TERMINATE, false)
+    }
+
+    /// Query the message statistics of the proxy.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`]
+    ///
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    pub fn statistics(&mut self) -> Result<ProxyStats, Error> {
+        self.inner.send(STATISTICS, false)?;
+        let parts = self.inner.recv_msg_multipart()?;
+
+        Ok(ProxyStats::from_parts(&parts))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, *};
+
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn test_proxy_pause_resume() {
+        let frontend_addr = InprocAddr::new_unique();
+        let backend_addr = InprocAddr::new_unique();
+        let capture_addr = InprocAddr::new_unique();
+
+        let frontend =
+            GatherBuilder::new().bind(&frontend_addr).build().unwrap();
+        let backend =
+            ScatterBuilder::new().bind(&backend_addr).build().unwrap();
+        let capture =
+            ScatterBuilder::new().bind(&capture_addr).build().unwrap();
+
+        let (proxy, mut handle) = ProxyBuilder::new(frontend, backend)
+            .capture(capture)
+            .build()
+            .unwrap();
+        let proxy_thread = thread::spawn(move || proxy.run());
+
+        let scatter = ScatterBuilder::new()
+            .connect(&frontend_addr)
+            .build()
+            .unwrap();
+        let gather = GatherBuilder::new()
+            .connect(&backend_addr)
+            .recv_timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let captured =
+            GatherBuilder::new().connect(&capture_addr).build().unwrap();
+
+        handle.pause().unwrap();
+        // Make sure that the proxy processed the command.
+        handle.statistics().unwrap();
+
+        scatter.send("a").unwrap();
+        let err = gather.recv_msg().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        handle.resume().unwrap();
+        let msg = gather.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "a");
+
+        // The control commands are also captured.
+        let captured: Vec<String> = (0..4)
+            .map(|_| captured.recv_msg().unwrap().to_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(captured, vec![PAUSE, STATISTICS, RESUME, "a"]);
+
+        let stats = handle.statistics().unwrap();
+        let expected = SocketStats {
+            msg_in: 1,
+            bytes_in: 1,
+            msg_out: 0,
+            bytes_out: 0,
+        };
+        assert_eq!(stats.frontend(), expected);
+        let expected = SocketStats {
+            msg_in: 0,
+            bytes_in: 0,
+            msg_out: 1,
+            bytes_out: 1,
+        };
+        assert_eq!(stats.backend(), expected);
+
+        handle.terminate().unwrap();
+        proxy_thread.join().unwrap().unwrap();
+    }
+}
@@ -0,0 +1,461 @@
+//! An event loop built on top of the `Poller`.
+//!
+//! See the [`Reactor`] documentation to get started.
+//!
+//! [`Reactor`]: struct.Reactor.html
+
+use crate::{
+    error::{Error, ErrorKind},
+    poll::{Event, Events, PollId, Pollable, Poller, Trigger},
+    timer::{TimerId, Timers},
+};
+
+use std::{collections::HashMap, fmt, mem, time::Duration};
+
+type PollHandler<'a> =
+    Box<dyn FnMut(&mut Reactor<'a>, Event) -> Result<Flow, Error> + 'a>;
+type TimerHandler<'a> =
+    Box<dyn FnMut(&mut Reactor<'a>, TimerId) -> Result<Flow, Error> + 'a>;
+
+/// Specifies whether the [`Reactor`] should keep running after a handler
+/// returns.
+///
+/// [`Reactor`]: struct.Reactor.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Flow {
+    /// Keep running the reactor.
+    Continue,
+    /// Stop the reactor, which returns from [`run`].
+    ///
+    /// [`run`]: struct.Reactor.html#method.run
+    Stop,
+}
+
+/// A unique identifier for a handler registered in the [`Reactor`].
+///
+/// [`Reactor`]: struct.Reactor.html
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HandlerId(usize);
+
+struct PollEntry<'a> {
+    pollable: Pollable<'a>,
+    // The handler is taken out of its entry while it is being called.
+    handler: Option<PollHandler<'a>>,
+}
+
+/// An event loop that dispatches socket events and timers to handlers.
+///
+/// Handlers are registered for a [`Pollable`] element via [`add`] and for a
+/// timer via [`add_timer`]. The reactor then [`runs`] until one of the
+/// handlers returns [`Flow::Stop`] or an error.
+///
+/// Since each handler receives a mutable reference to the reactor, handlers
+/// can be added or removed from within a handler, including the one being
+/// called.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::{prelude::*, *, poll::*, reactor::*};
+/// use std::time::Duration;
+///
+/// let addr = InprocAddr::new_unique();
+///
+/// let server = ServerBuilder::new().bind(&addr).build()?;
+/// let client = ClientBuilder::new().connect(&addr).build()?;
+///
+/// let mut count = 0;
+/// let mut reactor = Reactor::new();
+///
+/// // Echo back the incoming messages.
+/// reactor.add(&server, READABLE, |_, _| {
+///     let msg = server.recv_msg()?;
+///     server.send(msg).map_err(|err| err.cast())?;
+///     Ok(Flow::Continue)
+/// })?;
+///
+/// // Send a message to the server at a fixed interval.
+/// reactor.add_timer(Duration::from_millis(1), |_, _| {
+///     client.send("ping").map_err(|err| err.cast())?;
+///     Ok(Flow::Continue)
+/// })?;
+///
+/// reactor.add(&client, READABLE, |_, _| {
+///     let msg = client.recv_msg()?;
+///     assert_eq!(msg.to_str().unwrap(), "ping");
+///
+///     count += 1;
+///     if count == 10 {
+///         Ok(Flow::Stop)
+///     } else {
+///         Ok(Flow::Continue)
+///     }
+/// })?;
+///
+/// reactor.run()?;
+///
+/// // Release the borrows of the handlers.
+/// drop(reactor);
+/// assert_eq!(count, 10);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Pollable`]: ../poll/enum.Pollable.html
+/// [`add`]: #method.add
+/// [`add_timer`]: #method.add_timer
+/// [`runs`]: #method.run
+/// [`Flow::Stop`]: enum.Flow.html#variant.Stop
+pub struct Reactor<'a> {
+    poller: Poller,
+    timers: Timers,
+    events: Events,
+    expired: Vec<TimerId>,
+    next_id: usize,
+    entries: HashMap<HandlerId, PollEntry<'a>>,
+    // The handler is taken out of its entry while it is being called.
+    timer_handlers: HashMap<TimerId, Option<TimerHandler<'a>>>,
+}
+
+impl<'a> Reactor<'a> {
+    /// Create a new reactor without any handler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler that is called whenever the [`Pollable`] element
+    /// triggers an event with the specified [`Trigger`] condition.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (element already registered or invalid fd)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`Pollable`]: ../poll/enum.Pollable.html
+    /// [`Trigger`]: ../poll/struct.Trigger.html
+    pub fn add<P, F>(
+        &mut self,
+        pollable: P,
+        trigger: Trigger,
+        handler: F,
+    ) -> Result<HandlerId, Error>
+    where
+        P: Into<Pollable<'a>>,
+        F: FnMut(&mut Reactor<'a>, Event) -> Result<Flow, Error> + 'a,
+    {
+        let pollable = pollable.into();
+        let id = HandlerId(self.next_id);
+
+        self.poller.add(pollable, PollId(id.0), trigger)?;
+        self.next_id += 1;
+        self.entries.insert(
+            id,
+            PollEntry {
+                pollable,
+                handler: Some(Box::new(handler)),
+            },
+        );
+
+        Ok(id)
+    }
+
+    /// Remove the handler, which stops the monitoring of its
+    /// [`Pollable`] element.
+    ///
+    /// # Returned Errors
+    /// * [`NotFound`] (handler was already removed)
+    ///
+    /// [`NotFound`]: ../enum.ErrorKind.html#variant.NotFound
+    /// [`Pollable`]: ../poll/enum.Pollable.html
+    pub fn remove(&mut self, id: HandlerId) -> Result<(), Error> {
+        match self.entries.remove(&id) {
+            Some(entry) => self.poller.remove(entry.pollable),
+            None => Err(Error::new(ErrorKind::NotFound("handler not found"))),
+        }
+    }
+
+    /// Register a handler that is called every `interval`, until the timer
+    /// is removed.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (interval is less than 1 ms or overflows)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn add_timer<F>(
+        &mut self,
+        interval: Duration,
+        handler: F,
+    ) -> Result<TimerId, Error>
+    where
+        F: FnMut(&mut Reactor<'a>, TimerId) -> Result<Flow, Error> + 'a,
+    {
+        let id = self.timers.add(interval)?;
+        self.timer_handlers.insert(id, Some(Box::new(handler)));
+
+        Ok(id)
+    }
+
+    /// Remove the timer and its handler.
+    ///
+    /// # Returned Errors
+    /// * [`NotFound`] (timer was already removed)
+    ///
+    /// [`NotFound`]: ../enum.ErrorKind.html#variant.NotFound
+    pub fn remove_timer(&mut self, id: TimerId) -> Result<(), Error> {
+        self.timers.cancel(id)?;
+        self.timer_handlers.remove(&id);
+
+        Ok(())
+    }
+
+    /// Reschedule the next expiry of the timer one interval from now.
+    ///
+    /// # Returned Errors
+    /// * [`NotFound`] (timer was removed)
+    ///
+    /// [`NotFound`]: ../enum.ErrorKind.html#variant.NotFound
+    pub fn reset_timer(&mut self, id: TimerId) -> Result<(), Error> {
+        self.timers.reset(id)
+    }
+
+    /// Change the interval of the timer.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (interval is less than 1 ms or overflows)
+    /// * [`NotFound`] (timer was removed)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`NotFound`]: ../enum.ErrorKind.html#variant.NotFound
+    pub fn set_timer_interval(
+        &mut self,
+        id: TimerId,
+        interval: Duration,
+    ) -> Result<(), Error> {
+        self.timers.set_interval(id, interval)
+    }
+
+    /// Returns `true` if the reactor has no registered handler.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.timer_handlers.is_empty()
+    }
+
+    /// Run the reactor, dispatching events and timers to their handlers.
+    ///
+    /// This blocks until a handler returns [`Flow::Stop`] or an error,
+    /// which is then returned. Returns immediately if the reactor has no
+    /// registered handler.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidCtx`] (`Ctx` of a polled socket was terminated)
+    /// * [`Interrupted`]
+    /// * Any error returned by a handler
+    ///
+    /// [`Flow::Stop`]: enum.Flow.html#variant.Stop
+    /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    pub fn run(&mut self) -> Result<(), Error> {
+        while !self.is_empty() {
+            let mut events = mem::take(&mut self.events);
+            let result = self.poller.poll_timers(&mut events, &mut self.timers);
+            let flow = result.and_then(|()| self.dispatch(&events));
+            self.events = events;
+
+            if let Flow::Stop = flow? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn dispatch(&mut self, events: &Events) -> Result<Flow, Error> {
+        let mut expired = mem::take(&mut self.expired);
+        expired.clear();
+        expired.extend_from_slice(self.timers.expired());
+
+        let mut flow = Ok(Flow::Continue);
+        for &id in &expired {
+            flow = self.call_timer(id);
+            if !matches!(flow, Ok(Flow::Continue)) {
+                break;
+            }
+        }
+        self.expired = expired;
+
+        if let Ok(Flow::Continue) = flow {
+            for event in events {
+                let flow = self.call(HandlerId(event.id().0), event)?;
+                if flow == Flow::Stop {
+                    return Ok(flow);
+                }
+            }
+        }
+
+        flow
+    }
+
+    fn call(&mut self, id: HandlerId, event: Event) -> Result<Flow, Error> {
+        // The handler might have been removed by a previous handler.
+        let mut handler = match self.entries.get_mut(&id) {
+            Some(entry) => entry.handler.take().unwrap(),
+            None => return Ok(Flow::Continue),
+        };
+
+        let flow = handler(self, event);
+
+        // Unless the handler was removed while it was called.
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.handler = Some(handler);
+        }
+
+        flow
+    }
+
+    fn call_timer(&mut self, id: TimerId) -> Result<Flow, Error> {
+        let mut handler = match self.timer_handlers.get_mut(&id) {
+            Some(handler) => handler.take().unwrap(),
+            None => return Ok(Flow::Continue),
+        };
+
+        let flow = handler(self, id);
+
+        if let Some(slot) = self.timer_handlers.get_mut(&id) {
+            *slot = Some(handler);
+        }
+
+        flow
+    }
+}
+
+impl<'a> Default for Reactor<'a> {
+    fn default() -> Self {
+        Self {
+            poller: Poller::new(),
+            timers: Timers::new(),
+            events: Events::new(),
+            expired: Vec::new(),
+            next_id: 0,
+            entries: HashMap::new(),
+            timer_handlers: HashMap::new(),
+        }
+    }
+}
+
+impl<'a> fmt::Debug for Reactor<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut handlers: Vec<&HandlerId> = self.entries.keys().collect();
+        handlers.sort();
+        let mut timers: Vec<&TimerId> = self.timer_handlers.keys().collect();
+        timers.sort();
+
+        f.debug_struct("Reactor")
+            .field("handlers", &handlers)
+            .field("timers", &timers)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{poll::*, prelude::*, *};
+
+    use std::cell::Cell;
+
+    #[test]
+    fn test_reactor_empty() {
+        let mut reactor = Reactor::new();
+        assert!(reactor.is_empty());
+        reactor.run().unwrap();
+    }
+
+    #[test]
+    fn test_reactor_remove_in_handler() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        client.send("").unwrap();
+
+        let recv_count = Cell::new(0);
+        let timer_count = Cell::new(0);
+
+        let mut reactor = Reactor::new();
+        let id = reactor
+            .add(&server, READABLE, |_, event| {
+                assert!(event.is_readable());
+                server.recv_msg().unwrap();
+                recv_count.set(recv_count.get() + 1);
+                Ok(Flow::Continue)
+            })
+            .unwrap();
+
+        reactor
+            .add_timer(Duration::from_millis(20), |reactor, timer| {
+                timer_count.set(timer_count.get() + 1);
+                // The server handler is removed before the second message
+                // is received.
+                if timer_count.get() == 1 {
+                    reactor.remove(id).unwrap();
+                    client.send("").unwrap();
+                    Ok(Flow::Continue)
+                } else {
+                    reactor.remove_timer(timer).unwrap();
+                    Ok(Flow::Continue)
+                }
+            })
+            .unwrap();
+
+        // Stops since every handler removed.
+        reactor.run().unwrap();
+        assert!(reactor.is_empty());
+        drop(reactor);
+
+        assert_eq!(recv_count.get(), 1);
+        assert_eq!(timer_count.get(), 2);
+        // The second message was never received.
+        server.try_recv_msg().unwrap();
+    }
+
+    #[test]
+    fn test_reactor_add_in_handler() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let mut reactor = Reactor::new();
+        reactor
+            .add_timer(Duration::from_millis(1), |reactor, timer| {
+                reactor.remove_timer(timer).unwrap();
+                reactor
+                    .add(&server, READABLE, |_, _| {
+                        let msg = server.recv_msg()?;
+                        assert_eq!(msg.to_str().unwrap(), "stop");
+                        Ok(Flow::Stop)
+                    })
+                    .unwrap();
+                client.send("stop").map_err(Error::cast)?;
+                Ok(Flow::Continue)
+            })
+            .unwrap();
+
+        reactor.run().unwrap();
+    }
+
+    #[test]
+    fn test_reactor_handler_error() {
+        let mut reactor = Reactor::new();
+        reactor
+            .add_timer(Duration::from_millis(1), |_, _| {
+                Err(Error::new(ErrorKind::Interrupted))
+            })
+            .unwrap();
+
+        let err = reactor.run().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+    }
+}
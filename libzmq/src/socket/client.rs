@@ -2,7 +2,7 @@ use crate::{addr::Endpoint, auth::*, core::*, error::*, Ctx, CtxHandle};
 
 use serde::{Deserialize, Serialize};
 
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
 /// A `Client` socket is used for advanced request-reply messaging.
 ///
@@ -183,6 +183,8 @@ struct FlatClientConfig {
     recv_hwm: HighWaterMark,
     recv_timeout: Period,
     mechanism: Option<Mechanism>,
+    metadata: Option<BTreeMap<String, String>>,
+    zap_domain: Option<String>,
 }
 
 impl From<ClientConfig> for FlatClientConfig {
@@ -196,6 +198,8 @@ impl From<ClientConfig> for FlatClientConfig {
             bind: socket_config.bind,
             heartbeat: heartbeat_config.heartbeat,
             mechanism: socket_config.mechanism,
+            metadata: socket_config.metadata,
+            zap_domain: socket_config.zap_domain,
             send_hwm: send_config.send_hwm,
             send_timeout: send_config.send_timeout,
             recv_hwm: recv_config.recv_hwm,
@@ -210,6 +214,8 @@ impl From<FlatClientConfig> for ClientConfig {
             connect: flat.connect,
             bind: flat.bind,
             mechanism: flat.mechanism,
+            metadata: flat.metadata,
+            zap_domain: flat.zap_domain,
         };
         let send_config = SendConfig {
             send_hwm: flat.send_hwm,
@@ -360,6 +366,8 @@ mod test {
 
         let mut config = ClientConfig::new();
         config.set_connect(Some(&addr));
+        config.set_metadata(Some(vec![("Service", "echo")]));
+        config.set_zap_domain(Some("internal"));
 
         let ron = serde_yaml::to_string(&config).unwrap();
         let de: ClientConfig = serde_yaml::from_str(&ron).unwrap();
@@ -8,6 +8,7 @@ use sys::errno;
 use serde::{Deserialize, Serialize};
 
 use std::{
+    collections::BTreeMap,
     ffi::c_void,
     str,
     sync::{Arc, Mutex},
@@ -375,6 +376,8 @@ struct FlatDishConfig {
     recv_timeout: Period,
     groups: Option<Vec<Group>>,
     mechanism: Option<Mechanism>,
+    metadata: Option<BTreeMap<String, String>>,
+    zap_domain: Option<String>,
 }
 
 impl From<DishConfig> for FlatDishConfig {
@@ -385,6 +388,8 @@ impl From<DishConfig> for FlatDishConfig {
             connect: socket_config.connect,
             bind: socket_config.bind,
             mechanism: socket_config.mechanism,
+            metadata: socket_config.metadata,
+            zap_domain: socket_config.zap_domain,
             recv_hwm: recv_config.recv_hwm,
             recv_timeout: recv_config.recv_timeout,
             groups: config.groups,
@@ -398,6 +403,8 @@ impl From<FlatDishConfig> for DishConfig {
             connect: flat.connect,
             bind: flat.bind,
             mechanism: flat.mechanism,
+            metadata: flat.metadata,
+            zap_domain: flat.zap_domain,
         };
         let recv_config = RecvConfig {
             recv_hwm: flat.recv_hwm,
@@ -2,7 +2,7 @@ use crate::{addr::Endpoint, auth::*, core::*, error::*, Ctx, CtxHandle};
 
 use serde::{Deserialize, Serialize};
 
-use std::{str, sync::Arc};
+use std::{collections::BTreeMap, str, sync::Arc};
 
 /// A `Gather` socket is used to receive pipelined messages.
 ///
@@ -176,6 +176,8 @@ struct FlatGatherConfig {
     recv_hwm: HighWaterMark,
     recv_timeout: Period,
     mechanism: Option<Mechanism>,
+    metadata: Option<BTreeMap<String, String>>,
+    zap_domain: Option<String>,
 }
 
 impl From<GatherConfig> for FlatGatherConfig {
@@ -188,6 +190,8 @@ impl From<GatherConfig> for FlatGatherConfig {
             bind: socket_config.bind,
             heartbeat: heartbeat_config.heartbeat,
             mechanism: socket_config.mechanism,
+            metadata: socket_config.metadata,
+            zap_domain: socket_config.zap_domain,
             recv_hwm: recv_config.recv_hwm,
             recv_timeout: recv_config.recv_timeout,
         }
@@ -200,6 +204,8 @@ impl From<FlatGatherConfig> for GatherConfig {
             connect: flat.connect,
             bind: flat.bind,
             mechanism: flat.mechanism,
+            metadata: flat.metadata,
+            zap_domain: flat.zap_domain,
         };
         let recv_config = RecvConfig {
             recv_hwm: flat.recv_hwm,
@@ -2,7 +2,7 @@ use crate::{addr::Endpoint, auth::*, core::*, error::*, *};
 
 use serde::{Deserialize, Serialize};
 
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
 /// A `Radio` socket is used by a publisher to distribute data to [`Dish`]
 /// sockets.
@@ -259,6 +259,8 @@ struct FlatRadioConfig {
     send_timeout: Period,
     no_drop: Option<bool>,
     mechanism: Option<Mechanism>,
+    metadata: Option<BTreeMap<String, String>>,
+    zap_domain: Option<String>,
 }
 
 impl From<RadioConfig> for FlatRadioConfig {
@@ -272,6 +274,8 @@ impl From<RadioConfig> for FlatRadioConfig {
             send_timeout: send_config.send_timeout,
             no_drop: config.no_drop,
             mechanism: socket_config.mechanism,
+            metadata: socket_config.metadata,
+            zap_domain: socket_config.zap_domain,
         }
     }
 }
@@ -282,6 +286,8 @@ impl From<FlatRadioConfig> for RadioConfig {
             connect: flat.connect,
             bind: flat.bind,
             mechanism: flat.mechanism,
+            metadata: flat.metadata,
+            zap_domain: flat.zap_domain,
         };
         let send_config = SendConfig {
             send_hwm: flat.send_hwm,
@@ -2,7 +2,7 @@ use crate::{addr::Endpoint, auth::*, core::*, error::*, Ctx, CtxHandle};
 
 use serde::{Deserialize, Serialize};
 
-use std::{str, sync::Arc};
+use std::{collections::BTreeMap, str, sync::Arc};
 
 /// A `Scatter` socket is used to pipeline messages to workers.
 ///
@@ -167,6 +167,8 @@ struct FlatScatterConfig {
     send_hwm: HighWaterMark,
     send_timeout: Period,
     mechanism: Option<Mechanism>,
+    metadata: Option<BTreeMap<String, String>>,
+    zap_domain: Option<String>,
 }
 
 impl From<ScatterConfig> for FlatScatterConfig {
@@ -179,6 +181,8 @@ impl From<ScatterConfig> for FlatScatterConfig {
             bind: socket_config.bind,
             heartbeat: heartbeat_config.heartbeat,
             mechanism: socket_config.mechanism,
+            metadata: socket_config.metadata,
+            zap_domain: socket_config.zap_domain,
             send_hwm: send_config.send_hwm,
             send_timeout: send_config.send_timeout,
         }
@@ -191,6 +195,8 @@ impl From<FlatScatterConfig> for ScatterConfig {
             connect: flat.connect,
             bind: flat.bind,
             mechanism: flat.mechanism,
+            metadata: flat.metadata,
+            zap_domain: flat.zap_domain,
         };
         let send_config = SendConfig {
             send_hwm: flat.send_hwm,
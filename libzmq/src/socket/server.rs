@@ -2,7 +2,7 @@ use crate::{addr::Endpoint, auth::*, core::*, error::*, *};
 
 use serde::{Deserialize, Serialize};
 
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
 /// A `Server` socket is a socket used for advanced request-reply messaging.
 ///
@@ -15,6 +15,13 @@ use std::sync::Arc;
 /// does not refer to a connected server peer, the send call will fail with
 /// [`HostUnreachable`].
 ///
+/// # Peer Notifications
+/// A `Server` is not notified when a peer connects or disconnects. The
+/// `ZMQ_ROUTER_NOTIFY` option which provides this for `ZMQ_ROUTER` sockets
+/// is not supported by `ZMQ_SERVER` sockets as of *libzmq* 4.3.2.
+/// Connection level events, which do not carry a [`routing_id`], can be
+/// observed via a [`monitor`] instead.
+///
 /// # Mute State
 /// When a `Server` socket enters the mute state due to having reached the high
 /// water mark for all servers, or if there are no servers at
@@ -79,6 +86,7 @@ use std::sync::Arc;
 /// [`routing_id`]: struct.Msg.html#method.routing_id
 /// [`set_routing_id`]: struct.Msg.html#method.set_routing_id
 /// [`HostUnreachable`]: enum.ErrorKind.html#variant.host-unreachable
+/// [`monitor`]: prelude/trait.Socket.html#method.monitor
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Server {
     inner: Arc<RawSocket>,
@@ -224,6 +232,8 @@ struct FlatServerConfig {
     recv_hwm: HighWaterMark,
     recv_timeout: Period,
     mechanism: Option<Mechanism>,
+    metadata: Option<BTreeMap<String, String>>,
+    zap_domain: Option<String>,
 }
 
 impl From<ServerConfig> for FlatServerConfig {
@@ -237,6 +247,8 @@ impl From<ServerConfig> for FlatServerConfig {
             bind: socket_config.bind,
             heartbeat: heartbeat_config.heartbeat,
             mechanism: socket_config.mechanism,
+            metadata: socket_config.metadata,
+            zap_domain: socket_config.zap_domain,
             send_hwm: send_config.send_hwm,
             send_timeout: send_config.send_timeout,
             recv_hwm: recv_config.recv_hwm,
@@ -251,6 +263,8 @@ impl From<FlatServerConfig> for ServerConfig {
             connect: flat.connect,
             bind: flat.bind,
             mechanism: flat.mechanism,
+            metadata: flat.metadata,
+            zap_domain: flat.zap_domain,
         };
         let send_config = SendConfig {
             send_hwm: flat.send_hwm,
@@ -402,4 +416,29 @@ mod test {
         let de: ServerConfig = serde_yaml::from_str(&ron).unwrap();
         assert_eq!(config, de);
     }
+
+    // This test acts as a canary for when `ZMQ_SERVER` sockets support
+    // peer notifications.
+    #[test]
+    fn test_router_notify_unsupported() {
+        use libzmq_sys as sys;
+        use std::{
+            mem,
+            os::raw::{c_int, c_void},
+        };
+
+        let server = Server::new().unwrap();
+        let value = sys::ZMQ_NOTIFY_CONNECT as c_int;
+        let rc = unsafe {
+            sys::zmq_setsockopt(
+                server.raw_socket().as_mut_ptr(),
+                sys::ZMQ_ROUTER_NOTIFY as c_int,
+                &value as *const c_int as *const c_void,
+                mem::size_of::<c_int>(),
+            )
+        };
+
+        assert_eq!(rc, -1);
+        assert_eq!(unsafe { sys::zmq_errno() }, sys::errno::EINVAL);
+    }
 }
@@ -0,0 +1,418 @@
+//! Timers that can be driven alongside a `Poller`.
+//!
+//! See the [`Timers`] documentation to get started.
+//!
+//! [`Timers`]: struct.Timers.html
+
+use crate::{
+    core::Period,
+    error::{msg_from_errno, Error, ErrorKind},
+};
+use libzmq_sys as sys;
+use sys::errno;
+
+use std::{
+    collections::HashMap,
+    fmt,
+    os::raw::{c_int, c_void},
+    time::Duration,
+};
+
+type Handler = Box<dyn FnMut(TimerId)>;
+
+/// A unique identifier for a timer, assigned by the [`Timers`].
+///
+/// [`Timers`]: struct.Timers.html
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimerId(c_int);
+
+unsafe extern "C" fn on_expiry(timer_id: c_int, arg: *mut c_void) {
+    let expired = &mut *(arg as *mut Vec<TimerId>);
+    expired.push(TimerId(timer_id));
+}
+
+fn interval_ms(interval: Duration) -> Result<usize, Error> {
+    let ms = interval.as_millis();
+    if ms == 0 {
+        Err(Error::new(ErrorKind::InvalidInput(
+            "interval must be at least 1 ms",
+        )))
+    } else if ms > usize::MAX as u128 {
+        Err(Error::new(ErrorKind::InvalidInput(
+            "ms in interval must be less than usize::MAX",
+        )))
+    } else {
+        Ok(ms as usize)
+    }
+}
+
+fn timer_error(rc: c_int) -> Result<(), Error> {
+    if rc == -1 {
+        let errno = unsafe { sys::zmq_errno() };
+        let err = match errno {
+            errno::EINVAL => Error::new(ErrorKind::NotFound("timer not found")),
+            errno::EFAULT => panic!("invalid timers"),
+            _ => panic!("{}", msg_from_errno(errno)),
+        };
+
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
+/// A set of recurring timers.
+///
+/// Each timer expires every `interval` until it is [`cancelled`]. Timers are
+/// not driven by a background thread, instead [`execute`] must be called
+/// periodically. The [`timeout`] until the next expiry can be passed to the
+/// [`Poller`] so that a single thread can wait for both socket events and
+/// timers. This is what [`poll_timers`] does.
+///
+/// A timer can either be identified by its [`TimerId`], which is reported
+/// by [`expired`], or be associated with a callback.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::{prelude::*, *, poll::*, timer::*};
+/// use std::{cell::Cell, rc::Rc, time::Duration};
+///
+/// let server = Server::new()?;
+///
+/// let mut poller = Poller::new();
+/// poller.add(&server, PollId(0), READABLE)?;
+///
+/// let mut timers = Timers::new();
+/// // A timer identified by its id.
+/// let heartbeat = timers.add(Duration::from_millis(10))?;
+/// // A timer with a callback.
+/// let count = Rc::new(Cell::new(0));
+/// let clone = Rc::clone(&count);
+/// timers.add_fn(Duration::from_millis(10), move |_| {
+///     clone.set(clone.get() + 1);
+/// })?;
+///
+/// let mut events = Events::new();
+///
+/// // Wait until either the server receives a message or the
+/// // nearest timer expires.
+/// poller.poll_timers(&mut events, &mut timers)?;
+///
+/// // Since no one will ever send a message to the server, the
+/// // timers expired.
+/// assert!(events.is_empty());
+/// assert!(timers.expired().contains(&heartbeat));
+/// assert_eq!(count.get(), 1);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`cancelled`]: #method.cancel
+/// [`execute`]: #method.execute
+/// [`timeout`]: #method.timeout
+/// [`expired`]: #method.expired
+/// [`Poller`]: ../poll/struct.Poller.html
+/// [`poll_timers`]: ../poll/struct.Poller.html#method.poll_timers
+/// [`TimerId`]: struct.TimerId.html
+pub struct Timers {
+    timers: *mut c_void,
+    handlers: HashMap<TimerId, Option<Handler>>,
+    // Boxed so that its address remains stable, since it is passed to
+    // the expiry handler.
+    #[allow(clippy::box_collection)]
+    expired: Box<Vec<TimerId>>,
+}
+
+impl Timers {
+    /// Create a new empty set of timers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a timer that expires every `interval`.
+    ///
+    /// The timer is reported by [`expired`] after each expiry.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (interval is less than 1 ms or overflows)
+    ///
+    /// [`expired`]: #method.expired
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn add(&mut self, interval: Duration) -> Result<TimerId, Error> {
+        self.add_handler(interval, None)
+    }
+
+    /// Add a timer that expires every `interval` and calls the callback
+    /// on each expiry.
+    ///
+    /// The timer is also reported by [`expired`] after each expiry.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (interval is less than 1 ms or overflows)
+    ///
+    /// [`expired`]: #method.expired
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn add_fn<F>(
+        &mut self,
+        interval: Duration,
+        callback: F,
+    ) -> Result<TimerId, Error>
+    where
+        F: FnMut(TimerId) + 'static,
+    {
+        self.add_handler(interval, Some(Box::new(callback)))
+    }
+
+    fn add_handler(
+        &mut self,
+        interval: Duration,
+        handler: Option<Handler>,
+    ) -> Result<TimerId, Error> {
+        let interval = interval_ms(interval)?;
+        let arg = &mut *self.expired as *mut Vec<TimerId> as *mut c_void;
+
+        let rc = unsafe {
+            sys::zmq_timers_add(self.timers, interval, Some(on_expiry), arg)
+        };
+        timer_error(rc)?;
+
+        let id = TimerId(rc);
+        self.handlers.insert(id, handler);
+
+        Ok(id)
+    }
+
+    /// Cancel the timer, which will no longer expire.
+    ///
+    /// # Returned Errors
+    /// * [`NotFound`] (timer was already cancelled)
+    ///
+    /// [`NotFound`]: ../enum.ErrorKind.html#variant.NotFound
+    pub fn cancel(&mut self, id: TimerId) -> Result<(), Error> {
+        self.check_exists(id)?;
+
+        let rc = unsafe { sys::zmq_timers_cancel(self.timers, id.0) };
+        timer_error(rc)?;
+
+        self.handlers.remove(&id);
+        Ok(())
+    }
+
+    /// Change the interval of the timer.
+    ///
+    /// The next expiry is rescheduled one interval from now.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (interval is less than 1 ms or overflows)
+    /// * [`NotFound`] (timer was cancelled)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`NotFound`]: ../enum.ErrorKind.html#variant.NotFound
+    pub fn set_interval(
+        &mut self,
+        id: TimerId,
+        interval: Duration,
+    ) -> Result<(), Error> {
+        self.check_exists(id)?;
+        let interval = interval_ms(interval)?;
+
+        let rc = unsafe {
+            sys::zmq_timers_set_interval(self.timers, id.0, interval)
+        };
+        timer_error(rc)
+    }
+
+    /// Reschedule the next expiry of the timer one interval from now.
+    ///
+    /// # Returned Errors
+    /// * [`NotFound`] (timer was cancelled)
+    ///
+    /// [`NotFound`]: ../enum.ErrorKind.html#variant.NotFound
+    pub fn reset(&mut self, id: TimerId) -> Result<(), Error> {
+        self.check_exists(id)?;
+
+        let rc = unsafe { sys::zmq_timers_reset(self.timers, id.0) };
+        timer_error(rc)
+    }
+
+    fn check_exists(&self, id: TimerId) -> Result<(), Error> {
+        if self.handlers.contains_key(&id) {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::NotFound("timer not found")))
+        }
+    }
+
+    /// Returns the number of active timers.
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    /// Returns `true` if there are no active timers.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Returns the time left until the nearest timer expires.
+    ///
+    /// If there are no active timers, returns `Period::Infinite`.
+    pub fn timeout(&mut self) -> Period {
+        let ms = unsafe { sys::zmq_timers_timeout(self.timers) };
+
+        if ms < 0 {
+            Period::Infinite
+        } else {
+            Period::Finite(Duration::from_millis(ms as u64))
+        }
+    }
+
+    /// Execute the timers that expired, calling their callbacks.
+    ///
+    /// The expired timers can then be retrieved via [`expired`].
+    ///
+    /// [`expired`]: #method.expired
+    pub fn execute(&mut self) {
+        self.expired.clear();
+
+        let rc = unsafe { sys::zmq_timers_execute(self.timers) };
+        timer_error(rc).unwrap();
+
+        for id in self.expired.iter() {
+            if let Some(Some(handler)) = self.handlers.get_mut(id) {
+                handler(*id);
+            }
+        }
+    }
+
+    /// Returns the timers that expired during the last call to [`execute`].
+    ///
+    /// [`execute`]: #method.execute
+    pub fn expired(&self) -> &[TimerId] {
+        &self.expired
+    }
+}
+
+impl Default for Timers {
+    fn default() -> Self {
+        let timers = unsafe { sys::zmq_timers_new() };
+
+        if timers.is_null() {
+            panic!("{}", msg_from_errno(unsafe { sys::zmq_errno() }));
+        }
+
+        Self {
+            timers,
+            handlers: HashMap::new(),
+            expired: Box::new(Vec::new()),
+        }
+    }
+}
+
+impl fmt::Debug for Timers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ids: Vec<&TimerId> = self.handlers.keys().collect();
+        ids.sort();
+
+        f.debug_struct("Timers")
+            .field("timers", &ids)
+            .field("expired", &self.expired)
+            .finish()
+    }
+}
+
+impl Drop for Timers {
+    fn drop(&mut self) {
+        let rc = unsafe { sys::zmq_timers_destroy(&mut self.timers) };
+
+        if rc != 0 {
+            let errno = unsafe { sys::zmq_errno() };
+
+            match errno {
+                errno::EFAULT => panic!("invalid timers"),
+                _ => panic!("{}", msg_from_errno(errno)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::{cell::Cell, rc::Rc, thread};
+
+    #[test]
+    fn test_timers_execute() {
+        let mut timers = Timers::new();
+        assert_eq!(timers.timeout(), Period::Infinite);
+
+        let fast = timers.add(Duration::from_millis(10)).unwrap();
+        let count = Rc::new(Cell::new(0));
+        let clone = Rc::clone(&count);
+        let slow = timers
+            .add_fn(Duration::from_secs(3600), move |_| {
+                clone.set(clone.get() + 1)
+            })
+            .unwrap();
+        assert_eq!(timers.len(), 2);
+
+        match timers.timeout() {
+            Period::Finite(duration) => {
+                assert!(duration <= Duration::from_millis(10))
+            }
+            Period::Infinite => panic!("expected finite timeout"),
+        }
+
+        thread::sleep(Duration::from_millis(20));
+        timers.execute();
+        assert_eq!(timers.expired(), &[fast]);
+        assert_eq!(count.get(), 0);
+
+        timers.set_interval(slow, Duration::from_millis(1)).unwrap();
+        timers.cancel(fast).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        timers.execute();
+        assert_eq!(timers.expired(), &[slow]);
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn test_timers_cancel() {
+        let mut timers = Timers::new();
+        let id = timers.add(Duration::from_millis(1)).unwrap();
+
+        timers.cancel(id).unwrap();
+        assert!(timers.is_empty());
+        assert_eq!(timers.timeout(), Period::Infinite);
+
+        for result in &[
+            timers.cancel(id),
+            timers.reset(id),
+            timers.set_interval(id, Duration::from_millis(1)),
+        ] {
+            match result.as_ref().unwrap_err().kind() {
+                ErrorKind::NotFound(_) => (),
+                _ => panic!("unexpected error"),
+            }
+        }
+
+        thread::sleep(Duration::from_millis(10));
+        timers.execute();
+        assert!(timers.expired().is_empty());
+    }
+
+    #[test]
+    fn test_timers_invalid_interval() {
+        let mut timers = Timers::new();
+        let err = timers.add(Duration::from_micros(500)).unwrap_err();
+
+        match err.kind() {
+            ErrorKind::InvalidInput(_) => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+}
@@ -43,6 +43,9 @@ pub fn version() -> (i32, i32, i32) {
 /// is fully symmetric and there is no technical difference between frontend and
 /// backend.
 ///
+/// The proxy can only be stopped by terminating its `Ctx`. See [`Proxy`] for
+/// a proxy that can be paused, resumed and terminated.
+///
 /// # Returned Errors
 /// * [`InvalidCtx`]
 ///
@@ -90,6 +93,7 @@ pub fn version() -> (i32, i32, i32) {
 /// ```
 ///
 /// [`InvalidCtx`]: ../enum.ErrorKind.html#variant.InvalidCtx
+/// [`Proxy`]: struct.Proxy.html
 pub fn proxy<F, B>(frontend: F, backend: B) -> Result<(), Error>
 where
     F: GetRawSocket,
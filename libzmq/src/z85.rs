@@ -0,0 +1,699 @@
+//! The [`Z85`] binary to text encoding.
+//!
+//! `Z85` encodes every 4 bytes of binary data as 5 printable characters.
+//! The spec only defines inputs whose length is a multiple of 4. To support
+//! arbitrary lengths, a trailing chunk of `n` bytes is padded with zeros and
+//! only the first `n + 1` characters of its encoding are kept. Inputs whose
+//! length is a multiple of 4 are thus encoded as per the spec.
+//!
+//! # Example
+//! ```
+//! # use failure::Error;
+//! #
+//! # fn main() -> Result<(), Error> {
+//! use libzmq::z85;
+//!
+//! let bytes = [0x86, 0x4F, 0xD2, 0x6F, 0xB5, 0x59, 0xF7, 0x5B];
+//! assert_eq!(z85::encode(&bytes), "HelloWorld");
+//! assert_eq!(z85::decode("HelloWorld")?, bytes);
+//!
+//! // Any length is supported.
+//! let text = z85::encode("ping");
+//! assert_eq!(z85::encode("ping!").len(), text.len() + 2);
+//! assert_eq!(z85::decode(&text)?, b"ping");
+//! #
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! # Serde
+//! The module can be used to serialize a byte field as a `Z85` string in
+//! human readable formats. Other formats use the raw bytes.
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Payload {
+//!     #[serde(with = "libzmq::z85")]
+//!     data: Vec<u8>,
+//! }
+//! ```
+//!
+//! [`Z85`]: https://rfc.zeromq.org/spec:32/Z85/
+
+// The codec logic is largely based on https://github.com/decafbad/z85
+use byteorder::{BigEndian, ByteOrder};
+use failure::Fail;
+use serde::{de, Deserializer, Serializer};
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+// The number of chars read at once by a `DecodeReader`. A multiple of the
+// size of a chunk.
+const READ_BUF_SIZE: usize = 5 * 1024;
+
+static LETTERS: [u8; 85] = [
+    0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x61, 0x62,
+    0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x6B, 0x6C, 0x6D, 0x6E,
+    0x6F, 0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A,
+    0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x4B, 0x4C,
+    0x4D, 0x4E, 0x4F, 0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58,
+    0x59, 0x5A, 0x2E, 0x2D, 0x3A, 0x2B, 0x3D, 0x5E, 0x21, 0x2F, 0x2A, 0x3F,
+    0x26, 0x3C, 0x3E, 0x28, 0x29, 0x5B, 0x5D, 0x7B, 0x7D, 0x40, 0x25, 0x24,
+    0x23,
+];
+
+static OCTETS: [u8; 96] = [
+    0xFF, 0x44, 0xFF, 0x54, 0x53, 0x52, 0x48, 0xFF, 0x4B, 0x4C, 0x46, 0x41,
+    0xFF, 0x3F, 0x3E, 0x45, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+    0x08, 0x09, 0x40, 0xFF, 0x49, 0x42, 0x4A, 0x47, 0x51, 0x24, 0x25, 0x26,
+    0x27, 0x28, 0x29, 0x2A, 0x2B, 0x2C, 0x2D, 0x2E, 0x2F, 0x30, 0x31, 0x32,
+    0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x4D,
+    0xFF, 0x4E, 0x43, 0xFF, 0xFF, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x10,
+    0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x1B, 0x1C,
+    0x1D, 0x1E, 0x1F, 0x20, 0x21, 0x22, 0x23, 0x4F, 0xFF, 0x50, 0xFF, 0xFF,
+];
+
+/// An error when decoding a `Z85` string.
+#[derive(Debug, Fail, Eq, PartialEq)]
+pub enum Z85Error {
+    #[fail(
+        display = "input string contains invalid byte 0x{:2X} at offset {}",
+        byte, pos
+    )]
+    InvalidByte { pos: usize, byte: u8 },
+    #[fail(display = "input string has a trailing chunk of 1 char")]
+    InvalidLength,
+    #[fail(display = "input string chunk at offset {} overflows", pos)]
+    Overflow { pos: usize },
+}
+
+fn encode_chunk(input: &[u8]) -> [u8; 5] {
+    let mut num = BigEndian::read_u32(input) as usize;
+    let mut out = [0_u8; 5];
+
+    for i in (0..5).rev() {
+        out[i] = LETTERS[num % 85];
+        num /= 85;
+    }
+
+    out
+}
+
+// On error, returns the offset of the invalid byte in the chunk, or `None`
+// if the chunk overflows.
+fn decode_chunk(input: &[u8]) -> Result<[u8; 4], Option<usize>> {
+    let mut num: u64 = 0;
+
+    for (i, &byte) in input.iter().enumerate().take(5) {
+        num *= 85;
+
+        if !(0x20..=0x7F).contains(&byte) {
+            return Err(Some(i));
+        }
+
+        let b = OCTETS[byte as usize - 32];
+        if b == 0xFF {
+            return Err(Some(i));
+        }
+
+        num += u64::from(b);
+    }
+
+    if num > u64::from(u32::MAX) {
+        return Err(None);
+    }
+
+    let mut out = [0_u8; 4];
+    BigEndian::write_u32(&mut out, num as u32);
+
+    Ok(out)
+}
+
+// Encodes a trailing chunk of less than 4 bytes.
+fn encode_tail(input: &[u8]) -> Vec<u8> {
+    let mut chunk = [0_u8; 4];
+    chunk[..input.len()].copy_from_slice(input);
+
+    encode_chunk(&chunk)[..=input.len()].to_vec()
+}
+
+// Decodes a trailing chunk of 2 to 4 chars.
+fn decode_tail(input: &[u8], offset: usize) -> Result<Vec<u8>, Z85Error> {
+    if input.len() == 1 {
+        return Err(Z85Error::InvalidLength);
+    }
+
+    // Padding with the last letter rounds up the value, which is then
+    // truncated back to the original bytes.
+    let mut chunk = [LETTERS[84]; 5];
+    chunk[..input.len()].copy_from_slice(input);
+
+    let bytes = decode_chunk_at(&chunk, offset)?;
+    Ok(bytes[..input.len() - 1].to_vec())
+}
+
+fn decode_chunk_at(input: &[u8], offset: usize) -> Result<[u8; 4], Z85Error> {
+    decode_chunk(input).map_err(|pos| match pos {
+        Some(pos) => Z85Error::InvalidByte {
+            pos: offset + pos,
+            byte: input[pos],
+        },
+        None => Z85Error::Overflow { pos: offset },
+    })
+}
+
+/// Encode the bytes into a `Z85` string.
+pub fn encode<B>(input: B) -> String
+where
+    B: AsRef<[u8]>,
+{
+    let input = input.as_ref();
+    let mut out = Vec::with_capacity(encoded_len(input.len()));
+
+    let mut chunks = input.chunks_exact(4);
+    for chunk in &mut chunks {
+        out.extend_from_slice(&encode_chunk(chunk));
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        out.extend_from_slice(&encode_tail(tail));
+    }
+
+    unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decode a `Z85` string into bytes.
+pub fn decode<T>(input: T) -> Result<Vec<u8>, Z85Error>
+where
+    T: AsRef<[u8]>,
+{
+    let input = input.as_ref();
+    let mut out = Vec::with_capacity(input.len() / 5 * 4 + 3);
+
+    let mut chunks = input.chunks_exact(5);
+    for (i, chunk) in (&mut chunks).enumerate() {
+        out.extend_from_slice(&decode_chunk_at(chunk, i * 5)?);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let offset = input.len() - tail.len();
+        out.extend_from_slice(&decode_tail(tail, offset)?);
+    }
+
+    Ok(out)
+}
+
+/// Returns the length of the `Z85` encoding of `len` bytes.
+pub fn encoded_len(len: usize) -> usize {
+    let tail = len % 4;
+    len / 4 * 5 + if tail == 0 { 0 } else { tail + 1 }
+}
+
+/// A writer that encodes the bytes written to it as `Z85` and writes the
+/// resulting text to the inner writer.
+///
+/// Call [`finish`] to write the trailing chunk. Otherwise it is written,
+/// ignoring errors, when the `EncodeWriter` is dropped.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::z85::{self, EncodeWriter};
+/// use std::io::Write;
+///
+/// let mut writer = EncodeWriter::new(Vec::new());
+/// writer.write_all(b"Hello")?;
+/// writer.write_all(b" World")?;
+/// let text = writer.finish()?;
+///
+/// assert_eq!(text, z85::encode("Hello World").as_bytes());
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`finish`]: #method.finish
+pub struct EncodeWriter<W: Write> {
+    inner: Option<W>,
+    buf: [u8; 4],
+    len: usize,
+}
+
+impl<W: Write> EncodeWriter<W> {
+    /// Create a new `EncodeWriter` that writes to the inner writer.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner: Some(inner),
+            buf: [0; 4],
+            len: 0,
+        }
+    }
+
+    /// Write the trailing chunk and return the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_tail()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn write_tail(&mut self) -> io::Result<()> {
+        if self.len > 0 {
+            let tail = encode_tail(&self.buf[..self.len]);
+            self.inner.as_mut().unwrap().write_all(&tail)?;
+            self.len = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for EncodeWriter<W> {
+    fn write(&mut self, input: &[u8]) -> io::Result<usize> {
+        let inner = self.inner.as_mut().unwrap();
+
+        // Complete the buffered chunk first.
+        if self.len > 0 {
+            let count = input.len().min(4 - self.len);
+            self.buf[self.len..self.len + count]
+                .copy_from_slice(&input[..count]);
+            if self.len + count < 4 {
+                self.len += count;
+                return Ok(count);
+            }
+            inner.write_all(&encode_chunk(&self.buf))?;
+            self.len = 0;
+            return Ok(count);
+        }
+
+        let mut chunks = input.chunks_exact(4);
+        let mut out = Vec::with_capacity(input.len() / 4 * 5);
+        for chunk in &mut chunks {
+            out.extend_from_slice(&encode_chunk(chunk));
+        }
+        inner.write_all(&out)?;
+
+        let tail = chunks.remainder();
+        self.buf[..tail.len()].copy_from_slice(tail);
+        self.len = tail.len();
+
+        Ok(input.len())
+    }
+
+    /// Flushes the inner writer. The trailing chunk is only written by
+    /// `finish` since the encoding of a partial chunk is final.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> fmt::Debug for EncodeWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EncodeWriter")
+            .field("buffered", &self.len)
+            .finish()
+    }
+}
+
+impl<W: Write> Drop for EncodeWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_tail();
+        }
+    }
+}
+
+/// A reader that decodes the `Z85` text read from the inner reader.
+///
+/// Invalid input is reported as an `io::Error` of kind `InvalidData`
+/// whose message is the [`Z85Error`]. The inner reader is read in blocks,
+/// so it does not need to be buffered.
+///
+/// # Example
+/// ```
+/// # use failure::Error;
+/// #
+/// # fn main() -> Result<(), Error> {
+/// use libzmq::z85::{self, DecodeReader};
+/// use std::io::Read;
+///
+/// let text = z85::encode("Hello World");
+/// let mut reader = DecodeReader::new(text.as_bytes());
+/// let mut bytes = Vec::new();
+/// reader.read_to_end(&mut bytes)?;
+///
+/// assert_eq!(bytes, b"Hello World");
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Z85Error`]: enum.Z85Error.html
+pub struct DecodeReader<R: Read> {
+    inner: R,
+    // The chars that are not yet decoded.
+    text: Box<[u8]>,
+    text_len: usize,
+    // The decoded bytes that are not yet read.
+    bytes: Vec<u8>,
+    pos: usize,
+    // The offset of the next chunk in the input.
+    offset: usize,
+    eof: bool,
+}
+
+impl<R: Read> DecodeReader<R> {
+    /// Create a new `DecodeReader` that reads from the inner reader.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            text: vec![0; READ_BUF_SIZE].into_boxed_slice(),
+            text_len: 0,
+            bytes: Vec::new(),
+            pos: 0,
+            offset: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    // Decodes the buffered whole chunks, or the trailing chunk at the end
+    // of the input. Returns `false` at the end of the input.
+    fn fill(&mut self) -> io::Result<bool> {
+        while self.text_len < 5 && !self.eof {
+            match self.inner.read(&mut self.text[self.text_len..]) {
+                Ok(0) => self.eof = true,
+                Ok(count) => self.text_len += count,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        self.bytes.clear();
+        self.pos = 0;
+
+        let mut len = self.text_len - self.text_len % 5;
+        if len == 0 {
+            if self.text_len == 0 {
+                return Ok(false);
+            }
+            len = self.text_len;
+            let tail = decode_tail(&self.text[..len], self.offset)
+                .map_err(invalid_data)?;
+            self.bytes.extend_from_slice(&tail);
+        } else {
+            for (i, chunk) in self.text[..len].chunks(5).enumerate() {
+                match decode_chunk_at(chunk, self.offset + i * 5) {
+                    Ok(bytes) => self.bytes.extend_from_slice(&bytes),
+                    // The valid chunks are read before the error.
+                    Err(_) if i > 0 => {
+                        len = i * 5;
+                        break;
+                    }
+                    Err(err) => return Err(invalid_data(err)),
+                }
+            }
+        }
+
+        self.text.copy_within(len..self.text_len, 0);
+        self.text_len -= len;
+        self.offset += len;
+
+        Ok(true)
+    }
+}
+
+fn invalid_data(err: Z85Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.compat())
+}
+
+impl<R: Read> Read for DecodeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pos == self.bytes.len() && !self.fill()? {
+            return Ok(0);
+        }
+
+        let count = buf.len().min(self.bytes.len() - self.pos);
+        buf[..count].copy_from_slice(&self.bytes[self.pos..self.pos + count]);
+        self.pos += count;
+
+        Ok(count)
+    }
+}
+
+impl<R: Read> fmt::Debug for DecodeReader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DecodeReader")
+            .field("offset", &self.offset)
+            .field("eof", &self.eof)
+            .finish()
+    }
+}
+
+/// Serialize bytes as a `Z85` string in human readable formats.
+///
+/// To be used via `#[serde(with = "libzmq::z85")]`.
+pub fn serialize<B, S>(bytes: &B, serializer: S) -> Result<S::Ok, S::Error>
+where
+    B: AsRef<[u8]>,
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&encode(bytes))
+    } else {
+        serializer.serialize_bytes(bytes.as_ref())
+    }
+}
+
+/// Deserialize bytes from a `Z85` string in human readable formats.
+///
+/// To be used via `#[serde(with = "libzmq::z85")]`.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct BytesVisitor;
+
+    impl<'de> de::Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a Z85 string or bytes")
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            decode(value).map_err(de::Error::custom)
+        }
+
+        fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(value.to_vec())
+        }
+
+        fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(value)
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: de::SeqAccess<'de>,
+        {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            Ok(bytes)
+        }
+    }
+
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(BytesVisitor)
+    } else {
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::quickcheck;
+    use serde::{Deserialize, Serialize};
+
+    const Z85_RFC: &str = "HelloWorld";
+    const BIN_RFC: [u8; 8] = [0x86, 0x4F, 0xD2, 0x6F, 0xB5, 0x59, 0xF7, 0x5B];
+
+    #[test]
+    fn z85_encode_chunk_rfc() {
+        let curve_chunk_1 = decode_chunk(&Z85_RFC.as_bytes()[..5]).unwrap();
+        let curve_chunk_2 = decode_chunk(&Z85_RFC.as_bytes()[5..]).unwrap();
+        assert_eq!(curve_chunk_1, BIN_RFC[..4]);
+        assert_eq!(curve_chunk_2, BIN_RFC[4..]);
+    }
+
+    #[test]
+    fn z85_decode_chunk_rfc() {
+        let z85_chunk_1 = encode_chunk(&BIN_RFC[..4]);
+        let z85_chunk_2 = encode_chunk(&BIN_RFC[4..]);
+        assert_eq!(z85_chunk_1, Z85_RFC.as_bytes()[..5]);
+        assert_eq!(z85_chunk_2, Z85_RFC.as_bytes()[5..]);
+    }
+
+    #[test]
+    fn z85_encode_rfc() {
+        let curve_key = decode(Z85_RFC).unwrap();
+        assert_eq!(curve_key, BIN_RFC);
+    }
+
+    #[test]
+    fn z85_decode_rfc() {
+        let curve_key = encode(BIN_RFC);
+        assert_eq!(curve_key, Z85_RFC);
+    }
+
+    #[test]
+    fn z85_decode_errors() {
+        assert_eq!(
+            decode("Hello~orld"),
+            Err(Z85Error::InvalidByte { pos: 5, byte: 0x7E })
+        );
+        assert_eq!(decode("HelloW"), Err(Z85Error::InvalidLength));
+        assert_eq!(decode("Hello#####"), Err(Z85Error::Overflow { pos: 5 }));
+    }
+
+    #[test]
+    fn z85_streaming() {
+        let input: Vec<u8> = (0..=255).collect();
+        let text = encode(&input);
+
+        // Write in uneven chunks to exercise the buffering.
+        let mut writer = EncodeWriter::new(Vec::new());
+        for chunk in input.chunks(7) {
+            writer.write_all(chunk).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), text.as_bytes());
+
+        let mut reader = DecodeReader::new(text.as_bytes());
+        let mut bytes = Vec::new();
+        let mut buf = [0; 3];
+        loop {
+            let count = reader.read(&mut buf).unwrap();
+            if count == 0 {
+                break;
+            }
+            bytes.extend_from_slice(&buf[..count]);
+        }
+        assert_eq!(bytes, input);
+
+        let mut reader = DecodeReader::new(&b"Hello~orld"[..]);
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // The valid chunks before an error are still read.
+        let mut reader = DecodeReader::new(&b"Hello~orld"[..]);
+        let mut buf = [0; 8];
+        assert_eq!(reader.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], &decode("Hello").unwrap()[..]);
+        reader.read(&mut buf).unwrap_err();
+    }
+
+    #[test]
+    fn z85_read_blocks() {
+        struct CountingReader<'a> {
+            inner: &'a [u8],
+            reads: usize,
+        }
+
+        impl<'a> Read for CountingReader<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.reads += 1;
+                self.inner.read(buf)
+            }
+        }
+
+        let input = vec![42; 16 * 1024];
+        let text = encode(&input);
+        let inner = CountingReader {
+            inner: text.as_bytes(),
+            reads: 0,
+        };
+
+        let mut reader = DecodeReader::new(inner);
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, input);
+
+        let reads = reader.into_inner().reads;
+        assert_eq!(reads, text.len() / READ_BUF_SIZE + 1);
+    }
+
+    #[test]
+    fn z85_serde() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Payload {
+            #[serde(with = "crate::z85")]
+            data: Vec<u8>,
+        }
+
+        let payload = Payload {
+            data: b"Hello World".to_vec(),
+        };
+
+        let yaml = serde_yaml::to_string(&payload).unwrap();
+        assert!(yaml.contains(&encode(&payload.data)));
+        let de: Payload = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(de, payload);
+
+        let bin = bincode::serialize(&payload).unwrap();
+        let de: Payload = bincode::deserialize(&bin).unwrap();
+        assert_eq!(de, payload);
+    }
+
+    #[test]
+    fn seven_bit_letters() {
+        for &l in LETTERS.iter() {
+            assert!(l < 0x80)
+        }
+    }
+
+    quickcheck! {
+        fn codec_chunk_quickcheck(num: u32) -> bool {
+            let mut buf = [0_u8; 4];
+            BigEndian::write_u32(&mut buf, num);
+
+            let z85_chunk = encode_chunk(&buf);
+            if let Ok(curve_chunk) = decode_chunk(&z85_chunk) {
+                if curve_chunk == buf {
+                    return true;
+                }
+            }
+
+            false
+        }
+    }
+
+    quickcheck! {
+        fn codec_quickcheck(input: Vec<u8>) -> bool {
+            let z85 = encode(&input);
+            z85.len() == encoded_len(input.len())
+                && decode(&z85).map(|bytes| bytes == input).unwrap_or(false)
+        }
+    }
+}
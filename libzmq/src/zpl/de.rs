@@ -0,0 +1,603 @@
+use super::{Zpl, ZplError};
+
+use serde::{
+    de::{
+        self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess,
+        SeqAccess, VariantAccess, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+
+use std::{fmt::Display, vec};
+
+impl de::Error for ZplError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        ZplError::new(msg.to_string())
+    }
+}
+
+macro_rules! deserialize_parse {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, ZplError>
+            where
+                V: Visitor<'de>,
+            {
+                let parsed = self.0.parse().map_err(|_| {
+                    ZplError::new(format!("invalid value: {:?}", self.0))
+                })?;
+                visitor.$visit(parsed)
+            }
+        )*
+    };
+}
+
+// Since `ZPL` values are untyped, they are parsed according to the type
+// requested by the visitor.
+struct Scalar<'de>(&'de str);
+
+impl<'de> de::Deserializer<'de> for Scalar<'de> {
+    type Error = ZplError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    deserialize_parse! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_bytes(self.0.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    fn deserialize_ignored_any<V>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 str string seq tuple tuple_struct map struct identifier
+    }
+}
+
+// Groups the children by name, in the order of their first occurrence.
+fn group(children: &[Zpl]) -> Vec<(&str, Vec<&Zpl>)> {
+    let mut groups: Vec<(&str, Vec<&Zpl>)> = vec![];
+    for child in children {
+        match groups.iter_mut().find(|(name, _)| *name == child.name) {
+            Some((_, nodes)) => nodes.push(child),
+            None => groups.push((&child.name, vec![child])),
+        }
+    }
+
+    groups
+}
+
+// A single node.
+pub(super) struct Node<'de>(pub(super) &'de Zpl);
+
+impl<'de> Node<'de> {
+    fn scalar(&self) -> Result<Scalar<'de>, ZplError> {
+        if self.0.children.is_empty() {
+            Ok(Scalar(self.0.value.as_deref().unwrap_or_default()))
+        } else {
+            Err(ZplError::new(format!(
+                "expected a value for {:?}, found a section",
+                self.0.name
+            )))
+        }
+    }
+}
+
+macro_rules! deserialize_scalar {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, ZplError>
+            where
+                V: Visitor<'de>,
+            {
+                self.scalar()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Node<'de> {
+    type Error = ZplError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0.value {
+            Some(ref value) if self.0.children.is_empty() => {
+                visitor.visit_borrowed_str(value)
+            }
+            _ => self.deserialize_map(visitor),
+        }
+    }
+
+    deserialize_scalar! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_u8 deserialize_u16 deserialize_u32
+        deserialize_u64 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes
+        deserialize_byte_buf deserialize_identifier
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        Group(vec![self.0]).deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple<V>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        if self.0.value.is_some() {
+            return Err(ZplError::new(format!(
+                "expected a section for {:?}, found a value",
+                self.0.name
+            )));
+        }
+
+        visitor.visit_map(Entries {
+            groups: group(&self.0.children).into_iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        if self.0.children.is_empty() {
+            // A unit variant.
+            return self.scalar()?.deserialize_enum(name, variants, visitor);
+        }
+
+        let mut groups = group(&self.0.children);
+        if groups.len() != 1 {
+            return Err(ZplError::new(format!(
+                "expected a single variant for {:?}",
+                self.0.name
+            )));
+        }
+        let (variant, nodes) = groups.pop().unwrap();
+
+        visitor.visit_enum(Variant { variant, nodes })
+    }
+
+    fn deserialize_ignored_any<V>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128
+    }
+}
+
+// The sibling nodes that share the same name.
+struct Group<'de>(Vec<&'de Zpl>);
+
+impl<'de> Group<'de> {
+    fn single(mut self) -> Result<Node<'de>, ZplError> {
+        if self.0.len() == 1 {
+            Ok(Node(self.0.pop().unwrap()))
+        } else {
+            Err(ZplError::new(format!(
+                "duplicate entry {:?}",
+                self.0[0].name
+            )))
+        }
+    }
+}
+
+macro_rules! deserialize_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, ZplError>
+            where
+                V: Visitor<'de>,
+            {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Group<'de> {
+    type Error = ZplError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        if self.0.len() == 1 {
+            self.single()?.deserialize_any(visitor)
+        } else {
+            self.deserialize_seq(visitor)
+        }
+    }
+
+    deserialize_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_u8 deserialize_u16 deserialize_u32
+        deserialize_u64 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes
+        deserialize_byte_buf deserialize_identifier deserialize_unit
+        deserialize_map deserialize_ignored_any
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        self.single()?.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        if self.0.len() == 1 {
+            self.single()?.deserialize_newtype_struct(name, visitor)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        let is_empty = match self.0.as_slice() {
+            [node] => node.value.is_none() && node.children.is_empty(),
+            _ => false,
+        };
+
+        let nodes = if is_empty { vec![] } else { self.0 };
+        visitor.visit_seq(Elements(nodes.into_iter()))
+    }
+
+    fn deserialize_tuple<V>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        self.single()?.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128
+    }
+}
+
+struct Elements<'de>(vec::IntoIter<&'de Zpl>);
+
+impl<'de> SeqAccess<'de> for Elements<'de> {
+    type Error = ZplError;
+
+    fn next_element_seed<T>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, ZplError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.0.next() {
+            Some(node) => seed.deserialize(Node(node)).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+struct Entries<'de> {
+    groups: vec::IntoIter<(&'de str, Vec<&'de Zpl>)>,
+    value: Option<Vec<&'de Zpl>>,
+}
+
+impl<'de> MapAccess<'de> for Entries<'de> {
+    type Error = ZplError;
+
+    fn next_key_seed<K>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ZplError>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.groups.next() {
+            Some((name, nodes)) => {
+                self.value = Some(nodes);
+                seed.deserialize(Scalar(name)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, ZplError>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let nodes = self.value.take().expect("value requested before key");
+        seed.deserialize(Group(nodes))
+    }
+}
+
+struct Variant<'de> {
+    variant: &'de str,
+    nodes: Vec<&'de Zpl>,
+}
+
+impl<'de> EnumAccess<'de> for Variant<'de> {
+    type Error = ZplError;
+    type Variant = Group<'de>;
+
+    fn variant_seed<V>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Group<'de>), ZplError>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(Scalar(self.variant))?;
+        Ok((variant, Group(self.nodes)))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Group<'de> {
+    type Error = ZplError;
+
+    fn unit_variant(self) -> Result<(), ZplError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, ZplError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ZplError>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
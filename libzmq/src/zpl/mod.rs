@@ -0,0 +1,464 @@
+//! Serialization in the ZeroMQ Property Language.
+//!
+//! [`ZPL`] is the hierarchical configuration format used by `czmq`. Each
+//! line contains a name, optionally followed by a value, and the indentation
+//! defines the hierarchy:
+//!
+//! ```text
+//! # A comment.
+//! connect
+//!     tcp = "127.0.0.1:3000"
+//! connect
+//!     tcp = "127.0.0.1:3001"
+//! recv_timeout = "300ms"
+//! metadata
+//!     Service = "echo"
+//! ```
+//!
+//! The configuration types of *libzmq* can be read from and written to `ZPL`
+//! via [`from_str`] and [`to_string`]. Since `ZPL` has no notion of types,
+//! every value is stored as a string and parsed on deserialization.
+//!
+//! * Structs, maps and enum variants with data become sections.
+//! * Sequences are written as repeated entries with the same name. An empty
+//!   section is read as an empty sequence.
+//! * `None` values are omitted.
+//!
+//! Nested sequences are not supported.
+//!
+//! # Example
+//! ```
+//! # use failure::Error;
+//! #
+//! # fn main() -> Result<(), Error> {
+//! use libzmq::{config::ClientConfig, prelude::*, zpl};
+//!
+//! let text = "\
+//! connect
+//!     tcp = \"127.0.0.1:3000\"
+//! connect
+//!     tcp = \"127.0.0.1:3001\"
+//! recv_timeout = \"300ms\"
+//! metadata
+//!     Service = \"echo\"
+//! ";
+//!
+//! let config: ClientConfig = zpl::from_str(text)?;
+//! assert_eq!(config.connect().unwrap().len(), 2);
+//!
+//! let text = zpl::to_string(&config)?;
+//! let de: ClientConfig = zpl::from_str(&text)?;
+//! assert_eq!(config, de);
+//! #
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! [`ZPL`]: https://rfc.zeromq.org/spec:4/ZPL/
+//! [`from_str`]: fn.from_str.html
+//! [`to_string`]: fn.to_string.html
+
+mod de;
+mod ser;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use std::{error, fmt};
+
+// The number of spaces per level of indentation.
+const INDENT: usize = 4;
+
+/// An error when reading or writing `ZPL`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZplError {
+    line: Option<usize>,
+    msg: String,
+}
+
+impl ZplError {
+    pub(crate) fn new<M>(msg: M) -> Self
+    where
+        M: Into<String>,
+    {
+        Self {
+            line: None,
+            msg: msg.into(),
+        }
+    }
+
+    /// Returns the line at which a syntax error occured, if the error is
+    /// a syntax error.
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Returns the description of the error.
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+}
+
+impl fmt::Display for ZplError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.msg),
+            None => write!(f, "{}", self.msg),
+        }
+    }
+}
+
+impl error::Error for ZplError {}
+
+/// Deserialize an instance of `T` from `ZPL` text.
+pub fn from_str<T>(text: &str) -> Result<T, ZplError>
+where
+    T: DeserializeOwned,
+{
+    let root = Zpl {
+        name: "root".to_owned(),
+        value: None,
+        children: Zpl::parse(text)?,
+    };
+
+    T::deserialize(de::Node(&root))
+}
+
+/// Serialize the given struct or map as `ZPL` text.
+pub fn to_string<T>(value: &T) -> Result<String, ZplError>
+where
+    T: Serialize + ?Sized,
+{
+    let nodes = ser::to_nodes(value)?;
+
+    let mut text = String::new();
+    Zpl::write(&nodes, &mut text);
+    Ok(text)
+}
+
+// A named node of a ZPL tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Zpl {
+    pub(crate) name: String,
+    pub(crate) value: Option<String>,
+    pub(crate) children: Vec<Zpl>,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "$-_@.&+/".contains(c)
+}
+
+// Checks that the rest of the line is empty or a comment.
+fn parse_end(rest: &str) -> Result<(), &'static str> {
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err("unexpected characters after value")
+    }
+}
+
+// Parses a line stripped of its indentation.
+fn parse_line(line: &str) -> Result<Zpl, &'static str> {
+    let len = line.find(|c| !is_name_char(c)).unwrap_or(line.len());
+    if len == 0 {
+        return Err("invalid name");
+    }
+    let (name, rest) = line.split_at(len);
+
+    let rest = rest.trim_start();
+    let value = if rest.is_empty() || rest.starts_with('#') {
+        None
+    } else if let Some(rest) = rest.strip_prefix('=') {
+        let rest = rest.trim_start();
+        match rest.chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => {
+                let end = rest[1..].find(quote).ok_or("unterminated value")?;
+                parse_end(&rest[end + 2..])?;
+                Some(rest[1..=end].to_owned())
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || c == '#')
+                    .unwrap_or(rest.len());
+                parse_end(&rest[end..])?;
+                Some(rest[..end].to_owned())
+            }
+        }
+    } else {
+        return Err("expected '=' after name");
+    };
+
+    Ok(Zpl::new(name, value))
+}
+
+impl Zpl {
+    pub(crate) fn new<N>(name: N, value: Option<String>) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            value,
+            children: vec![],
+        }
+    }
+
+    // Returns the first child with the given name.
+    pub(crate) fn child(&self, name: &str) -> Option<&Zpl> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    // Parses the top level nodes of the text.
+    pub(crate) fn parse(text: &str) -> Result<Vec<Zpl>, ZplError> {
+        // The nodes that can still receive children, the first one
+        // being the implicit root.
+        let mut stack = vec![Zpl::new("root", None)];
+
+        for (i, line) in text.lines().enumerate() {
+            let trimmed = line.trim_start_matches(' ');
+            if trimmed.trim().is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let err = |msg: &str| ZplError {
+                line: Some(i + 1),
+                msg: msg.to_owned(),
+            };
+            let indent = line.len() - trimmed.len();
+            if indent % INDENT != 0 {
+                return Err(err("indentation must be a multiple of 4 spaces"));
+            }
+
+            let depth = indent / INDENT + 1;
+            if depth > stack.len() {
+                return Err(err("unexpected indentation"));
+            }
+            while stack.len() > depth {
+                let node = stack.pop().unwrap();
+                stack.last_mut().unwrap().children.push(node);
+            }
+
+            stack.push(parse_line(trimmed).map_err(err)?);
+        }
+
+        while stack.len() > 1 {
+            let node = stack.pop().unwrap();
+            stack.last_mut().unwrap().children.push(node);
+        }
+
+        Ok(stack.pop().unwrap().children)
+    }
+
+    // Checks that the name can be written.
+    pub(crate) fn check_name(name: &str) -> Result<(), ZplError> {
+        if name.is_empty() || !name.chars().all(is_name_char) {
+            Err(ZplError::new(format!("invalid name {:?}", name)))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Checks that the value can be written on a single line with
+    // one of the quotes.
+    pub(crate) fn check_value(value: &str) -> Result<(), ZplError> {
+        if value.contains('\n') || (value.contains('"') && value.contains('\''))
+        {
+            Err(ZplError::new(format!("cannot write value {:?}", value)))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Writes the nodes in the ZPL format.
+    pub(crate) fn write(nodes: &[Zpl], out: &mut String) {
+        fn write_node(node: &Zpl, depth: usize, out: &mut String) {
+            out.push_str(&" ".repeat(depth * INDENT));
+            out.push_str(&node.name);
+            if let Some(ref value) = node.value {
+                let quote = if value.contains('"') { '\'' } else { '"' };
+                out.push_str(" = ");
+                out.push(quote);
+                out.push_str(value);
+                out.push(quote);
+            }
+            out.push('\n');
+
+            for child in &node.children {
+                write_node(child, depth + 1, out);
+            }
+        }
+
+        for node in nodes {
+            write_node(node, 0, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{addr::Subnet, auth::*, config::*, prelude::*, *};
+
+    use std::{net::IpAddr, time::Duration};
+
+    #[test]
+    fn test_parse_write() {
+        let text = "#   A comment\n\
+                    \n\
+                    metadata\n    \
+                        name = \"some value\"  # comment\n    \
+                        quote = 'say \"hi\"'\n\
+                    curve\n    \
+                        public-key = abc\n    \
+                        nested\n        \
+                            leaf\n\
+                    last\n";
+
+        let nodes = Zpl::parse(text).unwrap();
+        assert_eq!(nodes.len(), 3);
+
+        let metadata = &nodes[0];
+        assert_eq!(metadata.name, "metadata");
+        assert_eq!(metadata.value, None);
+        let name = metadata.child("name").unwrap();
+        assert_eq!(name.value.as_deref(), Some("some value"));
+        let quote = metadata.child("quote").unwrap();
+        assert_eq!(quote.value.as_deref(), Some("say \"hi\""));
+
+        let curve = &nodes[1];
+        let key = curve.child("public-key").unwrap();
+        assert_eq!(key.value.as_deref(), Some("abc"));
+        assert_eq!(curve.child("nested").unwrap().children.len(), 1);
+        assert!(nodes[2].children.is_empty());
+
+        let mut out = String::new();
+        Zpl::write(&nodes, &mut out);
+        assert_eq!(Zpl::parse(&out).unwrap(), nodes);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = vec![
+            ("a\n   b\n", 2),
+            ("a\n        b\n", 2),
+            ("    a\n", 1),
+            ("a = \"b\n", 1),
+            ("a b\n", 1),
+            ("a = b c\n", 1),
+            ("\tb\n", 1),
+        ];
+
+        for (text, line) in cases {
+            let err = Zpl::parse(text).unwrap_err();
+            assert_eq!(err.line(), Some(line), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_ser_de_server() {
+        let cert = CurveCert::new_unique();
+        let mut config = ServerConfig::new();
+        let addr: TcpAddr = "127.0.0.1:3000".try_into().unwrap();
+        config.set_bind(Some(vec![addr]));
+        config.set_mechanism(Some(CurveServerCreds::new(cert.secret()).into()));
+        config.set_heartbeat(Some(
+            Heartbeat::new(Duration::from_millis(300))
+                .add_timeout(Duration::from_secs(1)),
+        ));
+        config.set_recv_timeout(Period::Finite(Duration::from_millis(100)));
+        config.set_metadata(Some(vec![("Service", "say \"hi\"")]));
+        config.set_zap_domain(Some("global"));
+
+        let text = to_string(&config).unwrap();
+        let de: ServerConfig = from_str(&text).unwrap();
+        assert_eq!(config, de);
+
+        let config = ServerConfig::new();
+        let text = to_string(&config).unwrap();
+        let de: ServerConfig = from_str(&text).unwrap();
+        assert_eq!(config, de);
+    }
+
+    #[test]
+    fn test_ser_de_auth() {
+        let mut config = AuthConfig::new();
+        let net: Subnet = "10.0.0.0/8".try_into().unwrap();
+        config.set_domain(Some("internal"));
+        config.set_whitelist(Some(net));
+        let ip: IpAddr = "10.1.2.3".parse().unwrap();
+        config.set_blacklist(Some(ip));
+
+        let creds = PlainClientCreds::new("user", "pwd");
+        config.set_plain_registry(Some(vec![
+            PlainRegistryEntry::new(&creds),
+            PlainRegistryEntry::new(creds).add_metadata("Role", "admin"),
+        ]));
+        config.set_hash_params(Some(HashParams::new(8, 1, 1).unwrap()));
+        let cert = CurveCert::new_unique();
+        config.set_curve_registry(Some(vec![
+            CurveRegistryEntry::new(cert.public()),
+            CurveRegistryEntry::new(cert.public()).with_user_id("user"),
+        ]));
+        config.set_ban_policy(Some(BanPolicy::new(
+            5,
+            Duration::from_secs(60),
+            Duration::from_secs(3600),
+        )));
+
+        let text = to_string(&config).unwrap();
+        let de: AuthConfig = from_str(&text).unwrap();
+        assert_eq!(config, de);
+
+        // An empty section is an empty list.
+        let de: AuthConfig = from_str("whitelist\n").unwrap();
+        let mut config = AuthConfig::new();
+        config.set_whitelist(Some(Vec::<Subnet>::new()));
+        assert_eq!(config, de);
+    }
+
+    #[test]
+    fn test_de_handwritten() {
+        let cert = CurveCert::new_unique();
+        let text = format!(
+            "# Hand written configuration.\n\
+             domain = internal\n\
+             whitelist = 127.0.0.1\n\
+             whitelist = \"10.0.0.0/8\"\n\
+             curve_registry = \"{}\"\n\
+             ban_policy\n    \
+                 max_failures = 3\n    \
+                 window = 1m\n    \
+                 ban_duration = 1h\n",
+            cert.public().as_str()
+        );
+
+        let de: AuthConfig = from_str(&text).unwrap();
+        assert_eq!(de.domain(), Some("internal"));
+        assert_eq!(de.whitelist().unwrap().len(), 2);
+        let entries = de.curve_registry().unwrap();
+        assert_eq!(entries, &[CurveRegistryEntry::new(cert.public())]);
+        assert_eq!(de.ban_policy().unwrap().max_failures(), 3);
+    }
+
+    #[test]
+    fn test_ser_de_errors() {
+        // Duplicate entries for a single value.
+        assert!(from_str::<AuthConfig>("domain = a\ndomain = b\n").is_err());
+        // A section instead of a value.
+        assert!(from_str::<AuthConfig>("domain\n    a = b\n").is_err());
+        // Invalid value.
+        let err = from_str::<AuthConfig>("ban_policy\n    max_failures = x\n")
+            .unwrap_err();
+        assert_eq!(err.line(), None);
+
+        // Only structs and maps can be written at the top level.
+        assert!(to_string(&"value").is_err());
+        // Keys must be valid names.
+        let mut config = ClientConfig::new();
+        config.set_metadata(Some(vec![("has space", "value")]));
+        assert!(to_string(&config).is_err());
+        // Values cannot contain both kinds of quotes.
+        config.set_metadata(Some(vec![("key", "'\"")]));
+        assert!(to_string(&config).is_err());
+    }
+}
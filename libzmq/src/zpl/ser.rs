@@ -0,0 +1,586 @@
+use super::{Zpl, ZplError};
+
+use serde::ser::{self, Impossible, Serialize};
+
+use std::fmt::Display;
+
+impl ser::Error for ZplError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        ZplError::new(msg.to_string())
+    }
+}
+
+// The intermediate representation of a serialized value, before it is
+// given a name.
+enum Value {
+    None,
+    Scalar(String),
+    Section(Vec<Zpl>),
+    Seq(Vec<Value>),
+}
+
+// Appends the nodes representing the named value.
+fn into_nodes(
+    name: &str,
+    value: Value,
+    out: &mut Vec<Zpl>,
+) -> Result<(), ZplError> {
+    Zpl::check_name(name)?;
+
+    match value {
+        Value::None => (),
+        Value::Scalar(value) => {
+            Zpl::check_value(&value)?;
+            out.push(Zpl::new(name, Some(value)));
+        }
+        Value::Section(children) => out.push(Zpl {
+            name: name.to_owned(),
+            value: None,
+            children,
+        }),
+        Value::Seq(values) => {
+            if values.is_empty() {
+                out.push(Zpl::new(name, None));
+            }
+            for value in values {
+                if let Value::Seq(_) = value {
+                    return Err(ZplError::new(format!(
+                        "nested sequence {:?}",
+                        name
+                    )));
+                }
+                into_nodes(name, value, out)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Wraps the value into a section containing a single node named after
+// the variant.
+fn into_variant(variant: &str, value: Value) -> Result<Value, ZplError> {
+    let mut nodes = vec![];
+    into_nodes(variant, value, &mut nodes)?;
+    Ok(Value::Section(nodes))
+}
+
+// Serializes the value into the top level nodes of a `ZPL` tree.
+pub(crate) fn to_nodes<T>(value: &T) -> Result<Vec<Zpl>, ZplError>
+where
+    T: Serialize + ?Sized,
+{
+    match value.serialize(ValueSerializer)? {
+        Value::Section(nodes) => Ok(nodes),
+        _ => Err(ZplError::new("expected a struct or a map")),
+    }
+}
+
+struct ValueSerializer;
+
+macro_rules! serialize_display {
+    ($($method:ident: $ty:ty,)*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Value, ZplError> {
+                Ok(Value::Scalar(v.to_string()))
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = ZplError;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    serialize_display! {
+        serialize_bool: bool,
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_f32: f32,
+        serialize_f64: f64,
+        serialize_char: char,
+        serialize_str: &str,
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, ZplError> {
+        let values = v.iter().map(|b| Value::Scalar(b.to_string())).collect();
+        Ok(Value::Seq(values))
+    }
+
+    fn serialize_none(self) -> Result<Value, ZplError> {
+        Ok(Value::None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Value, ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, ZplError> {
+        Ok(Value::Section(vec![]))
+    }
+
+    fn serialize_unit_struct(
+        self,
+        _name: &'static str,
+    ) -> Result<Value, ZplError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, ZplError> {
+        Ok(Value::Scalar(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        into_variant(variant, value.serialize(self)?)
+    }
+
+    fn serialize_seq(
+        self,
+        len: Option<usize>,
+    ) -> Result<SeqSerializer, ZplError> {
+        Ok(SeqSerializer {
+            values: Vec::with_capacity(len.unwrap_or_default()),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, ZplError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, ZplError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, ZplError> {
+        Ok(SeqSerializer {
+            values: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(
+        self,
+        _len: Option<usize>,
+    ) -> Result<MapSerializer, ZplError> {
+        Ok(MapSerializer {
+            nodes: vec![],
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<MapSerializer, ZplError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer, ZplError> {
+        Ok(MapSerializer {
+            nodes: vec![],
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+struct SeqSerializer {
+    values: Vec<Value>,
+    variant: Option<&'static str>,
+}
+
+impl SeqSerializer {
+    fn push<T>(&mut self, value: &T) -> Result<(), ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.values.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, ZplError> {
+        let value = Value::Seq(self.values);
+        match self.variant {
+            Some(variant) => into_variant(variant, value),
+            None => Ok(value),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Value;
+    type Error = ZplError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ZplError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Value;
+    type Error = ZplError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ZplError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Value;
+    type Error = ZplError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ZplError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Value;
+    type Error = ZplError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ZplError> {
+        self.finish()
+    }
+}
+
+struct MapSerializer {
+    nodes: Vec<Zpl>,
+    key: Option<String>,
+    variant: Option<&'static str>,
+}
+
+impl MapSerializer {
+    fn insert<T>(&mut self, key: &str, value: &T) -> Result<(), ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        into_nodes(key, value.serialize(ValueSerializer)?, &mut self.nodes)
+    }
+
+    fn finish(self) -> Result<Value, ZplError> {
+        let value = Value::Section(self.nodes);
+        match self.variant {
+            Some(variant) => into_variant(variant, value),
+            None => Ok(value),
+        }
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Value;
+    type Error = ZplError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self.key.take().expect("value serialized before key");
+        self.insert(&key, value)
+    }
+
+    fn end(self) -> Result<Value, ZplError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Value;
+    type Error = ZplError;
+
+    fn serialize_field<T>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, ZplError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Value;
+    type Error = ZplError;
+
+    fn serialize_field<T>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, ZplError> {
+        self.finish()
+    }
+}
+
+// Map keys are used as node names, so only scalars are accepted.
+struct KeySerializer;
+
+fn key_error() -> ZplError {
+    ZplError::new("map keys must be scalars")
+}
+
+macro_rules! serialize_key {
+    ($($method:ident: $ty:ty,)*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<String, ZplError> {
+                Ok(v.to_string())
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = ZplError;
+
+    type SerializeSeq = Impossible<String, ZplError>;
+    type SerializeTuple = Impossible<String, ZplError>;
+    type SerializeTupleStruct = Impossible<String, ZplError>;
+    type SerializeTupleVariant = Impossible<String, ZplError>;
+    type SerializeMap = Impossible<String, ZplError>;
+    type SerializeStruct = Impossible<String, ZplError>;
+    type SerializeStructVariant = Impossible<String, ZplError>;
+
+    serialize_key! {
+        serialize_bool: bool,
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_f32: f32,
+        serialize_f64: f64,
+        serialize_char: char,
+        serialize_str: &str,
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, ZplError> {
+        Err(key_error())
+    }
+
+    fn serialize_none(self) -> Result<String, ZplError> {
+        Err(key_error())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<String, ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, ZplError> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_struct(
+        self,
+        _name: &'static str,
+    ) -> Result<String, ZplError> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<String, ZplError> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, ZplError>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(key_error())
+    }
+
+    fn serialize_seq(
+        self,
+        _len: Option<usize>,
+    ) -> Result<Self::SerializeSeq, ZplError> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple(
+        self,
+        _len: usize,
+    ) -> Result<Self::SerializeTuple, ZplError> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, ZplError> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, ZplError> {
+        Err(key_error())
+    }
+
+    fn serialize_map(
+        self,
+        _len: Option<usize>,
+    ) -> Result<Self::SerializeMap, ZplError> {
+        Err(key_error())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, ZplError> {
+        Err(key_error())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, ZplError> {
+        Err(key_error())
+    }
+}